@@ -0,0 +1,11 @@
+-- Every channel has its own channel_<login> message table, this keeps track of which ones exist
+-- so they can be created up front instead of on a failed insert.
+CREATE TABLE IF NOT EXISTS log_channels (
+    channel TEXT PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+INSERT INTO log_channels (channel)
+    SELECT substring(table_name FROM 9) FROM information_schema.tables
+    WHERE table_schema = 'public' AND table_name LIKE 'channel\_%'
+ON CONFLICT (channel) DO NOTHING;
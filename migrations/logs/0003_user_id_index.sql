@@ -0,0 +1,13 @@
+-- looking someone up across every channel goes by user id, which survives name changes
+DO $$
+DECLARE
+    log_channel TEXT;
+BEGIN
+    FOR log_channel IN SELECT channel FROM log_channels LOOP
+        EXECUTE format(
+            'CREATE INDEX IF NOT EXISTS %I ON %I (user_id, timestamp)',
+            'channel_' || log_channel || '_user_id_idx',
+            'channel_' || log_channel
+        );
+    END LOOP;
+END $$;
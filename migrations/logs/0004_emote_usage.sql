@@ -0,0 +1,11 @@
+-- daily emote counters per channel, provider is Twitch for native emotes or 7TV, BTTV, FFZ
+CREATE TABLE IF NOT EXISTS emote_usage (
+    channel TEXT NOT NULL,
+    emote TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    day DATE NOT NULL,
+    uses BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (channel, emote, provider, day)
+);
+
+CREATE INDEX IF NOT EXISTS emote_usage_channel_day_idx ON emote_usage (channel, day);
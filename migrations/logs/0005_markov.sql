@@ -0,0 +1,15 @@
+-- word transitions learned from chat, model is a channel's login or user:<uid> for a user's
+-- own. prefix is the words leading up to next, joined by spaces
+CREATE TABLE IF NOT EXISTS markov (
+    model TEXT NOT NULL,
+    prefix TEXT NOT NULL,
+    next TEXT NOT NULL,
+    count BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (model, prefix, next)
+);
+
+-- users whose messages are never learned from
+CREATE TABLE IF NOT EXISTS markov_optouts (
+    uid INTEGER PRIMARY KEY,
+    opted_out_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
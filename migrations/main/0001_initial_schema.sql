@@ -0,0 +1,57 @@
+-- Baseline for databases that predate migrations. The pre-migration bot always wrote users as
+-- (uid, username, permissions) by name, so those columns are assumed. It only ever read commands
+-- with SELECT * by position though, so their columns are renamed to what the queries use now:
+-- name, about, permissions and user_cooldown, in that order.
+CREATE TABLE IF NOT EXISTS users (
+    uid INTEGER PRIMARY KEY,
+    username TEXT NOT NULL,
+    permissions INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS users_username_idx ON users (username);
+
+CREATE TABLE IF NOT EXISTS channels (
+    channel TEXT PRIMARY KEY,
+    joined BOOLEAN NOT NULL DEFAULT false
+);
+
+DO $$
+DECLARE
+    expected TEXT[] := ARRAY['name', 'about', 'permissions', 'user_cooldown'];
+    col RECORD;
+BEGIN
+    FOR col IN
+        SELECT column_name, row_number() OVER (ORDER BY ordinal_position) AS position
+        FROM information_schema.columns
+        WHERE table_schema = current_schema() AND table_name = 'commands'
+        ORDER BY ordinal_position
+        LIMIT 4
+    LOOP
+        IF col.column_name <> expected[col.position] THEN
+            EXECUTE format('ALTER TABLE commands RENAME COLUMN %I TO %I',
+                col.column_name, expected[col.position]);
+        END IF;
+    END LOOP;
+END $$;
+
+CREATE TABLE IF NOT EXISTS commands (
+    name TEXT PRIMARY KEY,
+    about TEXT NOT NULL,
+    permissions INTEGER NOT NULL DEFAULT 0,
+    user_cooldown INTEGER NOT NULL DEFAULT 5
+);
+
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('help', 'Lists every command, or describes the command given', 0, 5),
+    ('ping', 'Pong! Shows how long the bot has been running', 0, 5),
+    ('bot', 'Information about the bot', 0, 5),
+    ('greeting', 'Greets you based on your permission level', 0, 5),
+    ('expensive', 'Test command that takes 5 seconds to finish', 2, 5),
+    ('setpermissions', 'Usage: &setpermissions <user> <0|1|2>', 2, 0),
+    ('join', 'Usage: &join <channel>', 2, 0),
+    ('leave', 'Usage: &leave <channel>', 2, 0),
+    ('uid', 'Usage: &uid [user], shows the twitch user id', 0, 5),
+    ('say', 'Usage: &say <message>', 1, 5),
+    ('lastmessage', 'Usage: &lastmessage [user] [channel]', 0, 5),
+    ('randmessage', 'Usage: &randmessage [user|_] [channel]', 0, 5)
+ON CONFLICT (name) DO NOTHING;
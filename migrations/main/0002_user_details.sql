@@ -0,0 +1,3 @@
+-- filled in from helix in the background, NULL until the user has been looked up
+ALTER TABLE users ADD COLUMN IF NOT EXISTS display_name TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ;
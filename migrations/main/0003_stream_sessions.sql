@@ -0,0 +1,28 @@
+-- one row per broadcast, ended_at stays NULL while the channel is live
+CREATE TABLE IF NOT EXISTS stream_sessions (
+    id SERIAL PRIMARY KEY,
+    channel TEXT NOT NULL,
+    stream_id TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    ended_at TIMESTAMPTZ,
+    title TEXT NOT NULL,
+    category TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS stream_sessions_channel_idx ON stream_sessions (channel, started_at);
+
+-- every title or category change during a broadcast
+CREATE TABLE IF NOT EXISTS stream_changes (
+    id SERIAL PRIMARY KEY,
+    session_id INTEGER NOT NULL REFERENCES stream_sessions (id) ON DELETE CASCADE,
+    changed_at TIMESTAMPTZ NOT NULL,
+    title TEXT NOT NULL,
+    category TEXT NOT NULL
+);
+
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('title', 'Usage: &title [channel], shows the stream title', 0, 5),
+    ('game', 'Usage: &game [channel], shows the category being streamed', 0, 5),
+    ('uptime', 'Usage: &uptime [channel], shows how long the channel has been live', 0, 5),
+    ('lastseen', 'Usage: &lastseen live <channel>, shows when the channel was last live', 0, 5)
+ON CONFLICT (name) DO NOTHING;
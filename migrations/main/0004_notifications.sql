@@ -0,0 +1,13 @@
+-- users who want to be pinged in a channel, kind is either 'live' or 'title'
+CREATE TABLE IF NOT EXISTS notifications (
+    channel TEXT NOT NULL,
+    uid INTEGER NOT NULL REFERENCES users (uid) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    subscribed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (channel, uid, kind)
+);
+
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('notify', 'Usage: &notify <live|title>, pings you when this channel goes live or changes its title or category', 0, 5),
+    ('unnotify', 'Usage: &unnotify [live|title], stops the pings from &notify in this channel', 0, 5)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,4 @@
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('accountage', 'Usage: &accountage [user], how long ago the account was created', 0, 5),
+    ('followage', 'Usage: &followage [user] [channel], how long the user has been following the channel', 0, 5)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,3 @@
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('user', 'Usage: &user [login], what Twitch and the bot know about someone', 0, 5)
+ON CONFLICT (name) DO NOTHING;
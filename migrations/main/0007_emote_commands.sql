@@ -0,0 +1,4 @@
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('emote', 'Usage: &emote <name> [channel], where a 7TV, BTTV or FFZ emote comes from', 0, 5),
+    ('randomemote', 'Usage: &randomemote [channel], a random 7TV, BTTV or FFZ emote of the channel', 0, 5)
+ON CONFLICT (name) DO NOTHING;
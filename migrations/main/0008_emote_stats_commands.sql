@@ -0,0 +1,5 @@
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('emotecount', 'Usage: &emotecount <emote>, how often an emote was used in this channel', 0, 5),
+    ('topemotes', 'Usage: &topemotes [day|week|month|year|all], the most used emotes in this channel', 0, 5),
+    ('unusedemotes', 'Usage: &unusedemotes [days], 7TV, BTTV and FFZ emotes of this channel nobody used lately', 1, 5)
+ON CONFLICT (name) DO NOTHING;
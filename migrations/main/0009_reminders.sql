@@ -0,0 +1,22 @@
+-- reminders without a due time are delivered the next time the target chats anywhere,
+-- delivered and cancelled reminders are deleted
+CREATE TABLE IF NOT EXISTS reminders (
+    id SERIAL PRIMARY KEY,
+    author_uid INTEGER NOT NULL REFERENCES users (uid) ON DELETE CASCADE,
+    author_login TEXT NOT NULL,
+    target_uid INTEGER NOT NULL,
+    target_login TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    message TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    due_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS reminders_target_idx ON reminders (target_uid) WHERE due_at IS NULL;
+CREATE INDEX IF NOT EXISTS reminders_due_idx ON reminders (due_at) WHERE due_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS reminders_author_idx ON reminders (author_uid);
+
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('remind', 'Usage: &remind <user|me> [in <time>] <message>, reminds someone when they next chat or after some time like 2h or 1d12h', 0, 5),
+    ('unremind', 'Usage: &unremind <id>, cancels one of your reminders', 0, 5)
+ON CONFLICT (name) DO NOTHING;
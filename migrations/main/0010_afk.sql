@@ -0,0 +1,23 @@
+-- commands turned off in a channel by its moderators
+CREATE TABLE IF NOT EXISTS disabled_commands (
+    channel TEXT NOT NULL,
+    command TEXT NOT NULL,
+    PRIMARY KEY (channel, command)
+);
+
+-- users who are away, kind is 'afk', 'gn' or 'brb' and reason may be empty
+CREATE TABLE IF NOT EXISTS afk (
+    uid INTEGER PRIMARY KEY REFERENCES users (uid) ON DELETE CASCADE,
+    login TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    since TIMESTAMPTZ NOT NULL
+);
+
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('afk', 'Usage: &afk [reason], lets chat know you''re away until you type again', 0, 5),
+    ('gn', 'Usage: &gn [reason], lets chat know you''re sleeping until you type again', 0, 5),
+    ('brb', 'Usage: &brb [reason], lets chat know you''ll be right back', 0, 5),
+    ('disable', 'Usage: &disable <command>, turns a command off in this channel', 1, 0),
+    ('enable', 'Usage: &enable <command>, turns a disabled command back on in this channel', 1, 0)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,21 @@
+-- messages a channel's moderators want repeated or sent once later, exactly one of interval_secs
+-- and run_at is set. condition is 'always', 'live' or 'offline'
+CREATE TABLE IF NOT EXISTS timers (
+    id SERIAL PRIMARY KEY,
+    channel TEXT NOT NULL,
+    name TEXT NOT NULL,
+    message TEXT NOT NULL,
+    interval_secs INTEGER,
+    run_at TIMESTAMPTZ,
+    min_lines INTEGER NOT NULL DEFAULT 0,
+    condition TEXT NOT NULL DEFAULT 'always',
+    paused BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_sent_at TIMESTAMPTZ,
+    UNIQUE (channel, name),
+    CHECK ((interval_secs IS NULL) <> (run_at IS NULL))
+);
+
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('timer', 'Usage: &timer <add|once|list|pause|resume|delete>, repeats a message every so often (add <name> <interval> [--lines N] [--live|--offline] <message>) or sends it once later (once <name> <delay> [--live|--offline] <message>)', 1, 0)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,3 @@
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('markov', 'Usage: &markov [@user] [seed word], makes up a message from what was said in this channel or by a user. &markov optout stops it learning from you, &markov optin undoes that', 0, 10)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,20 @@
+-- memorable things said in a channel, quoted is the login of whoever said it. Every channel
+-- numbers its quotes on its own, starting at 1
+CREATE TABLE IF NOT EXISTS quotes (
+    channel TEXT NOT NULL,
+    id INTEGER NOT NULL,
+    quoted TEXT NOT NULL,
+    text TEXT NOT NULL,
+    added_by TEXT NOT NULL,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (channel, id)
+);
+
+CREATE INDEX IF NOT EXISTS quotes_channel_quoted ON quotes (channel, quoted);
+
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('addquote', 'Usage: &addquote <user> <text>, or &addquote <user> ~N to quote their Nth last message in this channel', 0, 10),
+    ('quote', 'Usage: &quote [id|user|search], a random quote from this channel, one by id, one from a user or one containing the search', 0, 5),
+    ('delquote', 'Usage: &delquote <id>', 1, 0),
+    ('quotes', 'Usage: &quotes export, or &quotes import <file>, moves this channel''s quotes to and from JSON files in the bot''s data directory', 2, 0)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,27 @@
+-- named per channel counters used as their own commands, template is what the command says
+CREATE TABLE IF NOT EXISTS counters (
+    id SERIAL PRIMARY KEY,
+    channel TEXT NOT NULL,
+    name TEXT NOT NULL,
+    template TEXT NOT NULL,
+    value BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (channel, name)
+);
+
+-- every change to a counter, kind is 'add' or 'set' and value is the counter's value after it
+CREATE TABLE IF NOT EXISTS counter_changes (
+    id SERIAL PRIMARY KEY,
+    counter_id INTEGER NOT NULL REFERENCES counters (id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    value BIGINT NOT NULL,
+    changed_by TEXT NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS counter_changes_counter ON counter_changes (counter_id, id);
+
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('counter', 'Usage: &counter <add|delete|list|history>, add <name> [template] makes &name show a count that moderators change with &name +1, -1, set <n> or reset. Templates can use {count}, {name} and {counter:<other name>}, timers can use {counter:<name>} too', 1, 0)
+ON CONFLICT (name) DO NOTHING;
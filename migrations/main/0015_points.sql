@@ -0,0 +1,20 @@
+-- points earned by chatting in a channel and spent on its games
+CREATE TABLE IF NOT EXISTS points (
+    channel TEXT NOT NULL,
+    uid INTEGER NOT NULL,
+    login TEXT NOT NULL,
+    balance BIGINT NOT NULL DEFAULT 0 CHECK (balance >= 0),
+    PRIMARY KEY (channel, uid)
+);
+
+CREATE INDEX IF NOT EXISTS points_channel_balance ON points (channel, balance DESC);
+
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('points', 'Usage: &points [user], points are earned by chatting. Moderators turn the economy off with &disable points', 0, 5),
+    ('give', 'Usage: &give <user> <amount|all>', 0, 5),
+    ('roulette', 'Usage: &roulette <amount|all>, doubles the bet or loses it', 0, 5),
+    ('slots', 'Usage: &slots <amount|all>, three of a kind pays 10x, a pair gets the bet back', 0, 5),
+    ('duel', 'Usage: &duel <user> <amount>, the winner takes the bet from the loser. &duel accept or &duel decline answers a challenge', 0, 5),
+    ('toppoints', 'Usage: &toppoints, the channel''s richest chatters', 0, 10),
+    ('resetpoints', 'Usage: &resetpoints <user|all>', 1, 0)
+ON CONFLICT (name) DO NOTHING;
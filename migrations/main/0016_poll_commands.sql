@@ -0,0 +1,4 @@
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('poll', 'Usage: &poll "question" option 1 | option 2 [| ...] [--duration 2m] [--bets], chat votes by typing an option''s number. &poll end shows the results early, &poll cancel drops the poll', 1, 0),
+    ('bet', 'Usage: &bet <option> <amount|all>, bets points on the outcome of a poll that takes bets. Losing bets are split between the winning ones', 0, 5)
+ON CONFLICT (name) DO NOTHING;
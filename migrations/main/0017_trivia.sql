@@ -0,0 +1,19 @@
+-- everyone's all-time score in every chat game per channel, the running games themselves aren't
+-- saved
+CREATE TABLE IF NOT EXISTS game_scores (
+    channel TEXT NOT NULL,
+    game TEXT NOT NULL,
+    uid INTEGER NOT NULL,
+    login TEXT NOT NULL,
+    points BIGINT NOT NULL DEFAULT 0,
+    -- how many times they scored, a trivia answer for example
+    scored INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (channel, game, uid)
+);
+
+CREATE INDEX IF NOT EXISTS game_scores_channel_game_points
+    ON game_scores (channel, game, points DESC);
+
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('trivia', 'Usage: &trivia start <pack> [questions], &trivia stop, &trivia packs, &trivia top or &trivia score [user]. Answer questions by typing in chat, packs are JSON or CSV files in data/trivia', 0, 5)
+ON CONFLICT (name) DO NOTHING;
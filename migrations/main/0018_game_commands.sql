@@ -0,0 +1,4 @@
+INSERT INTO commands (name, about, permissions, user_cooldown) VALUES
+    ('hangman', 'Usage: &hangman starts a word guessing game, guess a letter or the whole word in chat. &hangman stop, &hangman top or &hangman score [user]', 0, 30),
+    ('emoteguess', 'Usage: &emoteguess starts a game of guessing one of the channel''s emotes, type it in chat. &emoteguess stop, &emoteguess top or &emoteguess score [user]', 0, 30)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::time::Instant;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::bot::BorrowBot;
+use crate::commands::format_duration;
+use crate::database::{DBError, Database};
+use crate::types::parse_uid;
+
+// how long the bot stays quiet about an away user in a channel after telling someone there
+const PING_REPLY_COOLDOWN: Duration = Duration::from_secs(60);
+
+// announcements and ping replies are muted wherever this command is disabled
+const AFK_COMMAND: &str = "afk";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AfkKind {
+    Afk,
+    Gn,
+    Brb,
+}
+
+impl AfkKind {
+    // the command that sets it
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "afk" => Some(AfkKind::Afk),
+            "gn" => Some(AfkKind::Gn),
+            "brb" => Some(AfkKind::Brb),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AfkKind::Afk => "afk",
+            AfkKind::Gn => "gn",
+            AfkKind::Brb => "brb",
+        }
+    }
+
+    // how chat is told about it, "X is AFK"
+    pub fn describe(&self) -> &'static str {
+        match self {
+            AfkKind::Afk => "AFK",
+            AfkKind::Gn => "sleeping",
+            AfkKind::Brb => "away",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AfkStatus {
+    pub uid: i32,
+    pub login: String,
+    pub kind: AfkKind,
+
+    // empty if none was given
+    pub reason: String,
+    pub since: DateTime<Utc>,
+}
+
+impl AfkStatus {
+    // "alice is AFK: lunch (3h ago)" with `state` in place of "is AFK"
+    fn describe(&self, state: &str) -> String {
+        let reason = if self.reason.is_empty() {
+            String::new()
+        } else {
+            format!(": {}", self.reason)
+        };
+
+        format!(
+            "{} {}{} ({} ago)",
+            self.login,
+            state,
+            reason,
+            format_duration(Utc::now() - self.since)
+        )
+    }
+}
+
+// Everyone who is away, kept in memory so chat messages don't need a query. Statuses are saved
+// as they change and loaded back on startup
+pub struct AfkTracker {
+    db: Arc<dyn Database>,
+    statuses: Mutex<HashMap<i32, AfkStatus>>,
+
+    // (channel, uid) -> when someone pinging them there was last told
+    replied: Mutex<HashMap<(String, i32), Instant>>,
+}
+
+impl AfkTracker {
+    pub async fn new(db: Arc<dyn Database>) -> Result<Self, DBError> {
+        let statuses = db
+            .get_afk_statuses()
+            .await?
+            .into_iter()
+            .map(|status| (status.uid, status))
+            .collect();
+
+        Ok(Self {
+            db,
+            statuses: Mutex::new(statuses),
+            replied: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn set(&self, status: AfkStatus) -> Result<(), DBError> {
+        self.db.set_afk(&status).await?;
+        self.statuses.lock().unwrap().insert(status.uid, status);
+
+        Ok(())
+    }
+
+    // Welcomes the sender back if they were away, and tells them about anyone away they pinged.
+    // Reasons are written by the away user, they're left out unless the banphrase api clears them
+    pub fn on_message(bot: &BorrowBot, msg: &PrivmsgMessage) {
+        let uid = match parse_uid(&msg.sender.id) {
+            Ok(uid) => uid,
+            Err(_) => return,
+        };
+        let tracker = bot.afk();
+        let channel = msg.channel_login.to_lowercase();
+        let muted = bot.commands().is_disabled(&channel, AFK_COMMAND);
+
+        // going away again just replaces the status
+        let setting_status = msg
+            .message_text
+            .strip_prefix('&')
+            .and_then(|text| text.split(' ').next())
+            .and_then(AfkKind::parse)
+            .is_some();
+
+        let returned = if setting_status {
+            None
+        } else {
+            tracker.statuses.lock().unwrap().remove(&uid)
+        };
+
+        // (mention, status, state) of everything to tell the channel
+        let mut announcements = Vec::new();
+        if let Some(status) = returned {
+            let db = Arc::clone(&tracker.db);
+            let login = status.login.clone();
+            tokio::spawn(async move {
+                if let Err(e) = db.remove_afk(uid).await {
+                    eprintln!("Error clearing the afk status of {}: {}", login, e);
+                }
+            });
+            if !muted {
+                let state = format!("is no longer {}", status.kind.describe());
+                announcements.push((String::new(), status, state));
+            }
+        }
+
+        if muted {
+            return;
+        }
+        for status in tracker.pinged(&channel, uid, &msg.message_text) {
+            let state = format!("is {}", status.kind.describe());
+            announcements.push((format!("@{}, ", msg.sender.login), status, state));
+        }
+        if announcements.is_empty() {
+            return;
+        }
+
+        let banphrase = bot.api().banphrase();
+        let messenger = bot.messenger();
+        tokio::spawn(async move {
+            for (mention, status, state) in announcements {
+                let message = format!("{}{}", mention, status.describe(&state));
+                let cleared = status.reason.is_empty()
+                    || matches!(banphrase.contains_banphrase(&message).await, Ok(false));
+                let message = if cleared {
+                    message
+                } else {
+                    let status = AfkStatus {
+                        reason: String::new(),
+                        ..status
+                    };
+                    format!("{}{}", mention, status.describe(&state))
+                };
+                messenger.announce(&channel, vec![message]).await;
+            }
+        });
+    }
+
+    // away users mentioned in the message that nobody in the channel was told about lately
+    fn pinged(&self, channel: &str, sender_uid: i32, text: &str) -> Vec<AfkStatus> {
+        let statuses = self.statuses.lock().unwrap();
+        if statuses.is_empty() {
+            return Vec::new();
+        }
+
+        let mut replied = self.replied.lock().unwrap();
+        let now = Instant::now();
+        replied.retain(|_, at| now.duration_since(*at) < PING_REPLY_COOLDOWN);
+
+        let mut pinged: Vec<AfkStatus> = Vec::new();
+        for word in text.split_whitespace() {
+            let login = word
+                .trim_start_matches('@')
+                .trim_end_matches(|c: char| !c.is_alphanumeric() && c != '_')
+                .to_lowercase();
+            let status = match statuses.values().find(|status| status.login == login) {
+                Some(status) => status,
+                None => continue,
+            };
+            if status.uid == sender_uid || pinged.iter().any(|p| p.uid == status.uid) {
+                continue;
+            }
+
+            let key = (channel.to_owned(), status.uid);
+            if replied.contains_key(&key) {
+                continue;
+            }
+            replied.insert(key, now);
+            pinged.push(status.clone());
+        }
+
+        pinged
+    }
+}
@@ -1,7 +1,11 @@
+use std::env;
+
 use reqwest::Client;
 use serde::Deserialize;
 use std::collections::HashMap;
 
+const DEFAULT_BANPHRASE_URL: &str = "https://forsen.tv/api/v1/banphrases/test";
+
 #[derive(Debug, Deserialize)]
 pub struct BanphraseResponse {
     pub banned: bool,
@@ -20,19 +24,45 @@ pub struct BanphraseData {
     pub case_sensitive: bool,
 }
 
-pub async fn contains_banphrase(message: &str) -> Result<bool, reqwest::Error> {
-    let client = Client::new();
+pub struct Banphrase {
+    client: Client,
+    url: String,
+}
+
+impl Banphrase {
+    pub fn new() -> Self {
+        let url = env::var("BORROWBOT_BANPHRASE_URL")
+            .unwrap_or_else(|_| DEFAULT_BANPHRASE_URL.to_owned());
 
-    let mut data = HashMap::new();
-    data.insert("message", message);
+        Self::with_url(url)
+    }
 
-    let resp = client
-        .post("https://forsen.tv/api/v1/banphrases/test")
-        .json(&data)
-        .send()
-        .await?
-        .json::<BanphraseResponse>()
-        .await?;
+    pub fn with_url(url: String) -> Self {
+        Self {
+            client: Client::new(),
+            url,
+        }
+    }
+
+    pub async fn contains_banphrase(&self, message: &str) -> Result<bool, reqwest::Error> {
+        let mut data = HashMap::new();
+        data.insert("message", message);
+
+        let resp = self
+            .client
+            .post(&self.url)
+            .json(&data)
+            .send()
+            .await?
+            .json::<BanphraseResponse>()
+            .await?;
+
+        Ok(resp.banned)
+    }
+}
 
-    Ok(resp.banned)
+impl Default for Banphrase {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -0,0 +1,571 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tokio::time::Instant;
+
+const DEFAULT_SEVENTV_URL: &str = "https://7tv.io/v3";
+const DEFAULT_BTTV_URL: &str = "https://api.betterttv.net/3";
+const DEFAULT_FFZ_URL: &str = "https://api.frankerfacez.com/v1";
+
+// emotes get added and removed all the time, but nobody minds a list that's a bit behind
+const EMOTE_REFRESH_INTERVAL: Duration = Duration::from_secs(1800);
+
+// lists of channels the bot isn't in are only kept around for a while, and only so many
+const LOOKUP_TTL: Duration = Duration::from_secs(600);
+const MAX_LOOKUPS: usize = 100;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Provider {
+    SevenTv,
+    Bttv,
+    Ffz,
+}
+
+impl Provider {
+    const ALL: [Provider; 3] = [Provider::SevenTv, Provider::Bttv, Provider::Ffz];
+}
+
+impl fmt::Display for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Provider::SevenTv => write!(f, "7TV"),
+            Provider::Bttv => write!(f, "BTTV"),
+            Provider::Ffz => write!(f, "FFZ"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Emote {
+    pub id: String,
+    pub name: String,
+    pub provider: Provider,
+
+    // login of whoever uploaded it, if the provider tells us
+    pub owner: Option<String>,
+
+    // usable in every channel, not just the one it was looked up for
+    pub global: bool,
+}
+
+impl Emote {
+    // the emote's page on the provider's site
+    pub fn url(&self) -> String {
+        match self.provider {
+            Provider::SevenTv => format!("https://7tv.app/emotes/{}", self.id),
+            Provider::Bttv => format!("https://betterttv.com/emotes/{}", self.id),
+            Provider::Ffz => format!("https://www.frankerfacez.com/emoticon/{}", self.id),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SevenTvUser {
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct SevenTvEmoteData {
+    owner: Option<SevenTvUser>,
+}
+
+#[derive(Deserialize)]
+struct SevenTvEmote {
+    id: String,
+    name: String,
+    data: Option<SevenTvEmoteData>,
+}
+
+#[derive(Deserialize)]
+struct SevenTvEmoteSet {
+    emotes: Option<Vec<SevenTvEmote>>,
+}
+
+#[derive(Deserialize)]
+struct SevenTvUserConnection {
+    emote_set: Option<SevenTvEmoteSet>,
+}
+
+#[derive(Deserialize)]
+struct BttvUser {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct BttvEmote {
+    id: String,
+    code: String,
+    user: Option<BttvUser>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BttvChannel {
+    channel_emotes: Vec<BttvEmote>,
+    shared_emotes: Vec<BttvEmote>,
+}
+
+#[derive(Deserialize)]
+struct FfzUser {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct FfzEmote {
+    id: i64,
+    name: String,
+    owner: Option<FfzUser>,
+}
+
+#[derive(Deserialize)]
+struct FfzSet {
+    emoticons: Vec<FfzEmote>,
+}
+
+#[derive(Deserialize)]
+struct FfzGlobal {
+    default_sets: Vec<i64>,
+    sets: HashMap<String, FfzSet>,
+}
+
+#[derive(Deserialize)]
+struct FfzRoom {
+    sets: HashMap<String, FfzSet>,
+}
+
+fn from_seventv(set: Option<SevenTvEmoteSet>) -> Vec<Emote> {
+    set.and_then(|set| set.emotes)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|emote| Emote {
+            id: emote.id,
+            name: emote.name,
+            provider: Provider::SevenTv,
+            global: false,
+            owner: emote
+                .data
+                .and_then(|data| data.owner)
+                .map(|owner| owner.username),
+        })
+        .collect()
+}
+
+// channel emotes on bttv are uploaded by the channel itself and don't say so
+fn from_bttv(emotes: Vec<BttvEmote>, owner: Option<&str>) -> Vec<Emote> {
+    emotes
+        .into_iter()
+        .map(|emote| Emote {
+            id: emote.id,
+            name: emote.code,
+            provider: Provider::Bttv,
+            global: false,
+            owner: emote
+                .user
+                .map(|user| user.name)
+                .or_else(|| owner.map(|owner| owner.to_owned())),
+        })
+        .collect()
+}
+
+fn from_ffz<'a>(sets: impl Iterator<Item = &'a FfzSet>) -> Vec<Emote> {
+    sets.flat_map(|set| set.emoticons.iter())
+        .map(|emote| Emote {
+            id: emote.id.to_string(),
+            name: emote.name.clone(),
+            provider: Provider::Ffz,
+            global: false,
+            owner: emote.owner.as_ref().map(|owner| owner.name.clone()),
+        })
+        .collect()
+}
+
+pub struct EmoteConfig {
+    // base urls, can be pointed at local stubs serving test fixtures
+    pub seventv_url: String,
+    pub bttv_url: String,
+    pub ffz_url: String,
+}
+
+impl EmoteConfig {
+    pub fn from_env() -> Self {
+        Self {
+            seventv_url: env::var("BORROWBOT_7TV_URL")
+                .unwrap_or_else(|_| DEFAULT_SEVENTV_URL.to_owned()),
+            bttv_url: env::var("BORROWBOT_BTTV_URL")
+                .unwrap_or_else(|_| DEFAULT_BTTV_URL.to_owned()),
+            ffz_url: env::var("BORROWBOT_FFZ_URL").unwrap_or_else(|_| DEFAULT_FFZ_URL.to_owned()),
+        }
+    }
+}
+
+struct ChannelEmotes {
+    channel_id: String,
+    emotes: Arc<Vec<Emote>>,
+    loaded_at: Instant,
+}
+
+// which list is being loaded, None for the global one
+type LoadingKey = Option<String>;
+
+// Third party emotes of the joined channels, plus the global ones. Lists are loaded the first
+// time they're needed and kept fresh in the background after that. Other channels are looked
+// up on demand and forgotten again after a while
+pub struct Emotes {
+    client: Client,
+    config: EmoteConfig,
+    global: Mutex<Option<Arc<Vec<Emote>>>>,
+
+    // joined channel login -> its emotes
+    channels: Mutex<HashMap<String, ChannelEmotes>>,
+
+    // channel login -> emotes of a channel the bot isn't in
+    lookups: Mutex<HashMap<String, ChannelEmotes>>,
+
+    // held while a missing list is loaded, so everyone asking for it at once shares one fetch
+    loading: Mutex<HashMap<LoadingKey, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl Emotes {
+    pub fn new(config: EmoteConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config,
+            global: Mutex::new(None),
+            channels: Mutex::new(HashMap::new()),
+            lookups: Mutex::new(HashMap::new()),
+            loading: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Runs `load` while nobody else loads the same list, the lock is dropped again once
+    // nobody waits on it anymore
+    async fn single_flight<F, Fut>(
+        &self,
+        key: LoadingKey,
+        load: F,
+    ) -> Result<Arc<Vec<Emote>>, reqwest::Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Arc<Vec<Emote>>, reqwest::Error>>,
+    {
+        let lock = Arc::clone(self.loading.lock().unwrap().entry(key.clone()).or_default());
+        let result = {
+            let _loading = lock.lock().await;
+            load().await
+        };
+        drop(lock);
+
+        let mut loading = self.loading.lock().unwrap();
+        if loading
+            .get(&key)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            loading.remove(&key);
+        }
+
+        result
+    }
+
+    // None if the provider doesn't know the channel
+    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<Option<T>, reqwest::Error> {
+        let resp = self.client.get(url).send().await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        Ok(Some(resp.error_for_status()?.json::<T>().await?))
+    }
+
+    async fn fetch_global(&self, provider: Provider) -> Result<Vec<Emote>, reqwest::Error> {
+        let mut emotes = match provider {
+            Provider::SevenTv => from_seventv(
+                self.get_json(&format!("{}/emote-sets/global", self.config.seventv_url))
+                    .await?,
+            ),
+            Provider::Bttv => from_bttv(
+                self.get_json(&format!("{}/cached/emotes/global", self.config.bttv_url))
+                    .await?
+                    .unwrap_or_default(),
+                None,
+            ),
+            Provider::Ffz => {
+                match self
+                    .get_json::<FfzGlobal>(&format!("{}/set/global", self.config.ffz_url))
+                    .await?
+                {
+                    Some(global) => from_ffz(
+                        global
+                            .default_sets
+                            .iter()
+                            .filter_map(|id| global.sets.get(&id.to_string())),
+                    ),
+                    None => Vec::new(),
+                }
+            }
+        };
+
+        for emote in &mut emotes {
+            emote.global = true;
+        }
+        Ok(emotes)
+    }
+
+    async fn fetch_channel(
+        &self,
+        provider: Provider,
+        channel: &str,
+        channel_id: &str,
+    ) -> Result<Vec<Emote>, reqwest::Error> {
+        Ok(match provider {
+            Provider::SevenTv => {
+                let url = format!("{}/users/twitch/{}", self.config.seventv_url, channel_id);
+                from_seventv(
+                    self.get_json::<SevenTvUserConnection>(&url)
+                        .await?
+                        .and_then(|connection| connection.emote_set),
+                )
+            }
+            Provider::Bttv => {
+                let url = format!(
+                    "{}/cached/users/twitch/{}",
+                    self.config.bttv_url, channel_id
+                );
+                match self.get_json::<BttvChannel>(&url).await? {
+                    Some(bttv) => {
+                        let mut emotes = from_bttv(bttv.channel_emotes, Some(channel));
+                        emotes.extend(from_bttv(bttv.shared_emotes, None));
+                        emotes
+                    }
+                    None => Vec::new(),
+                }
+            }
+            Provider::Ffz => {
+                let url = format!("{}/room/id/{}", self.config.ffz_url, channel_id);
+                match self.get_json::<FfzRoom>(&url).await? {
+                    Some(room) => from_ffz(room.sets.values()),
+                    None => Vec::new(),
+                }
+            }
+        })
+    }
+
+    // One provider being down doesn't take the others with it, whatever it had before is
+    // kept until it answers again
+    async fn fetch_all(
+        &self,
+        previous: Option<&[Emote]>,
+        channel: Option<(&str, &str)>,
+    ) -> Result<Vec<Emote>, reqwest::Error> {
+        let mut emotes = Vec::new();
+        let mut last_error = None;
+        let mut failures = 0;
+
+        for provider in Provider::ALL.iter().copied() {
+            let fetched = match channel {
+                Some((channel, channel_id)) => {
+                    self.fetch_channel(provider, channel, channel_id).await
+                }
+                None => self.fetch_global(provider).await,
+            };
+
+            match fetched {
+                Ok(fetched) => emotes.extend(fetched),
+                Err(e) => {
+                    eprintln!(
+                        "{}: Error fetching {} emotes for {}: {}",
+                        Utc::now(),
+                        provider,
+                        channel.map_or("global", |(channel, _)| channel),
+                        e
+                    );
+                    emotes.extend(
+                        previous
+                            .unwrap_or_default()
+                            .iter()
+                            .filter(|emote| emote.provider == provider)
+                            .cloned(),
+                    );
+                    failures += 1;
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        // with nothing to fall back on, an outage everywhere is an error and not an empty list
+        match last_error {
+            Some(e) if failures == Provider::ALL.len() && previous.is_none() => Err(e),
+            _ => Ok(emotes),
+        }
+    }
+
+    // only what's loaded already, never sends a request
+    pub fn cached_global(&self) -> Option<Arc<Vec<Emote>>> {
+        self.global.lock().unwrap().clone()
+    }
+
+    pub fn cached_channel(&self, channel: &str) -> Option<Arc<Vec<Emote>>> {
+        self.channels
+            .lock()
+            .unwrap()
+            .get(channel)
+            .map(|cached| Arc::clone(&cached.emotes))
+    }
+
+    pub async fn global(&self) -> Result<Arc<Vec<Emote>>, reqwest::Error> {
+        self.single_flight(None, || async {
+            if let Some(global) = self.cached_global() {
+                return Ok(global);
+            }
+
+            self.refresh_global().await
+        })
+        .await
+    }
+
+    pub async fn refresh_global(&self) -> Result<Arc<Vec<Emote>>, reqwest::Error> {
+        let previous = self.cached_global();
+        let global = Arc::new(
+            self.fetch_all(previous.as_deref().map(Vec::as_slice), None)
+                .await?,
+        );
+        *self.global.lock().unwrap() = Some(Arc::clone(&global));
+
+        Ok(global)
+    }
+
+    // emotes of a joined channel, they're kept fresh for as long as the bot stays there
+    pub async fn channel(
+        &self,
+        channel: &str,
+        channel_id: &str,
+    ) -> Result<Arc<Vec<Emote>>, reqwest::Error> {
+        self.single_flight(Some(channel.to_owned()), || async {
+            if let Some(emotes) = self.cached_channel(channel) {
+                return Ok(emotes);
+            }
+
+            self.refresh_channel(channel, channel_id).await
+        })
+        .await
+    }
+
+    // emotes of any channel, the joined ones come from their cache
+    pub async fn lookup(
+        &self,
+        channel: &str,
+        channel_id: &str,
+    ) -> Result<Arc<Vec<Emote>>, reqwest::Error> {
+        self.single_flight(Some(channel.to_owned()), || async {
+            if let Some(emotes) = self.cached_channel(channel) {
+                return Ok(emotes);
+            }
+            if let Some(cached) = self.lookups.lock().unwrap().get(channel) {
+                if cached.loaded_at.elapsed() < LOOKUP_TTL {
+                    return Ok(Arc::clone(&cached.emotes));
+                }
+            }
+
+            let emotes = Arc::new(self.fetch_all(None, Some((channel, channel_id))).await?);
+
+            let mut lookups = self.lookups.lock().unwrap();
+            lookups.retain(|_, cached| cached.loaded_at.elapsed() < LOOKUP_TTL);
+            if lookups.len() >= MAX_LOOKUPS {
+                let oldest = lookups
+                    .iter()
+                    .min_by_key(|(_, cached)| cached.loaded_at)
+                    .map(|(channel, _)| channel.clone());
+                if let Some(oldest) = oldest {
+                    lookups.remove(&oldest);
+                }
+            }
+            lookups.insert(
+                channel.to_owned(),
+                ChannelEmotes {
+                    channel_id: channel_id.to_owned(),
+                    emotes: Arc::clone(&emotes),
+                    loaded_at: Instant::now(),
+                },
+            );
+
+            Ok(emotes)
+        })
+        .await
+    }
+
+    pub async fn refresh_channel(
+        &self,
+        channel: &str,
+        channel_id: &str,
+    ) -> Result<Arc<Vec<Emote>>, reqwest::Error> {
+        let previous = self.cached_channel(channel);
+        let emotes = Arc::new(
+            self.fetch_all(
+                previous.as_deref().map(Vec::as_slice),
+                Some((channel, channel_id)),
+            )
+            .await?,
+        );
+
+        self.channels.lock().unwrap().insert(
+            channel.to_owned(),
+            ChannelEmotes {
+                channel_id: channel_id.to_owned(),
+                emotes: Arc::clone(&emotes),
+                loaded_at: Instant::now(),
+            },
+        );
+
+        Ok(emotes)
+    }
+
+    // Refreshes the lists of the channels that are still joined, the ones of channels that
+    // were left are dropped
+    pub fn start_refresh_loop(
+        emotes: Arc<Emotes>,
+        joined: Arc<tokio::sync::Mutex<HashSet<String>>>,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(EMOTE_REFRESH_INTERVAL).await;
+
+                if let Err(e) = emotes.refresh_global().await {
+                    eprintln!("{}: Error refreshing global emotes: {}", Utc::now(), e);
+                }
+
+                emotes
+                    .lookups
+                    .lock()
+                    .unwrap()
+                    .retain(|_, cached| cached.loaded_at.elapsed() < LOOKUP_TTL);
+
+                let joined = joined.lock().await.clone();
+                let channels: Vec<(String, String)> = {
+                    let mut cached = emotes.channels.lock().unwrap();
+                    cached.retain(|channel, _| joined.contains(channel));
+                    cached
+                        .iter()
+                        .map(|(channel, cached)| (channel.clone(), cached.channel_id.clone()))
+                        .collect()
+                };
+                for (channel, channel_id) in channels {
+                    if let Err(e) = emotes.refresh_channel(&channel, &channel_id).await {
+                        eprintln!(
+                            "{}: Error refreshing emotes for #{}: {}",
+                            Utc::now(),
+                            channel,
+                            e
+                        );
+                    }
+                }
+            }
+        });
+    }
+}
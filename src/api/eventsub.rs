@@ -0,0 +1,580 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::convert::Infallible;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac, NewMac};
+use hyper::header::HeaderMap;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::Deserialize;
+use sha2::Sha256;
+use tokio::sync::{broadcast, Notify};
+
+use super::helix::{Helix, HelixError};
+use super::usercache::UserCache;
+
+// twitch redelivers messages it didn't get a 2xx for, ids are remembered for that long at most
+const SEEN_MESSAGE_IDS: usize = 1000;
+
+// messages older than this are refused so captured requests can't be replayed
+const MAX_MESSAGE_AGE: i64 = 600;
+
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(600);
+
+pub struct EventSubConfig {
+    pub secret: String,
+
+    // public url twitch sends events to, it has to end up at listen_addr
+    pub callback_url: String,
+    pub listen_addr: SocketAddr,
+
+    // channel.follow needs a moderator of the channel, follows aren't subscribed to without it
+    pub bot_user_id: Option<String>,
+}
+
+impl EventSubConfig {
+    // None when EventSub isn't set up, stream status is then only polled
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            secret: env::var("BORROWBOT_EVENTSUB_SECRET").ok()?,
+            callback_url: env::var("BORROWBOT_EVENTSUB_CALLBACK").ok()?,
+            listen_addr: env::var("BORROWBOT_EVENTSUB_ADDR")
+                .unwrap_or_else(|_| "0.0.0.0:8080".to_owned())
+                .parse()
+                .expect("BORROWBOT_EVENTSUB_ADDR isn't a valid socket address"),
+            bot_user_id: env::var("BORROWBOT_USER_ID").ok(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TwitchEvent {
+    StreamOnline {
+        broadcaster_id: String,
+        broadcaster_login: String,
+    },
+    StreamOffline {
+        broadcaster_id: String,
+        broadcaster_login: String,
+    },
+    ChannelUpdate {
+        broadcaster_id: String,
+        broadcaster_login: String,
+        title: String,
+        category_name: String,
+    },
+    ChannelFollow {
+        broadcaster_login: String,
+        user_id: String,
+        user_login: String,
+        followed_at: DateTime<Utc>,
+    },
+    ChannelRaid {
+        from_broadcaster_login: String,
+        to_broadcaster_login: String,
+        viewers: i64,
+    },
+
+    // twitch stopped delivering a subscription, e.g. because the broadcaster got banned
+    Revoked {
+        subscription_type: String,
+        status: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct BroadcasterEvent {
+    broadcaster_user_id: String,
+    broadcaster_user_login: String,
+}
+
+#[derive(Deserialize)]
+struct ChannelUpdateEvent {
+    broadcaster_user_id: String,
+    broadcaster_user_login: String,
+    title: String,
+    category_name: String,
+}
+
+#[derive(Deserialize)]
+struct ChannelFollowEvent {
+    broadcaster_user_login: String,
+    user_id: String,
+    user_login: String,
+    followed_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct ChannelRaidEvent {
+    from_broadcaster_user_login: String,
+    to_broadcaster_user_login: String,
+    viewers: i64,
+}
+
+#[derive(Deserialize)]
+struct MessageSubscription {
+    #[serde(rename = "type")]
+    type_field: String,
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    subscription: MessageSubscription,
+    challenge: Option<String>,
+    event: Option<serde_json::Value>,
+}
+
+// None for subscription types the bot doesn't know about
+fn parse_event(
+    type_field: &str,
+    event: serde_json::Value,
+) -> serde_json::Result<Option<TwitchEvent>> {
+    let event = match type_field {
+        "stream.online" => {
+            let event: BroadcasterEvent = serde_json::from_value(event)?;
+            TwitchEvent::StreamOnline {
+                broadcaster_id: event.broadcaster_user_id,
+                broadcaster_login: event.broadcaster_user_login,
+            }
+        }
+        "stream.offline" => {
+            let event: BroadcasterEvent = serde_json::from_value(event)?;
+            TwitchEvent::StreamOffline {
+                broadcaster_id: event.broadcaster_user_id,
+                broadcaster_login: event.broadcaster_user_login,
+            }
+        }
+        "channel.update" => {
+            let event: ChannelUpdateEvent = serde_json::from_value(event)?;
+            TwitchEvent::ChannelUpdate {
+                broadcaster_id: event.broadcaster_user_id,
+                broadcaster_login: event.broadcaster_user_login,
+                title: event.title,
+                category_name: event.category_name,
+            }
+        }
+        "channel.follow" => {
+            let event: ChannelFollowEvent = serde_json::from_value(event)?;
+            TwitchEvent::ChannelFollow {
+                broadcaster_login: event.broadcaster_user_login,
+                user_id: event.user_id,
+                user_login: event.user_login,
+                followed_at: event.followed_at,
+            }
+        }
+        "channel.raid" => {
+            let event: ChannelRaidEvent = serde_json::from_value(event)?;
+            TwitchEvent::ChannelRaid {
+                from_broadcaster_login: event.from_broadcaster_user_login,
+                to_broadcaster_login: event.to_broadcaster_user_login,
+                viewers: event.viewers,
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(event))
+}
+
+fn hmac(secret: &str, message_id: &str, timestamp: &str, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("any key length works");
+    mac.update(message_id.as_bytes());
+    mac.update(timestamp.as_bytes());
+    mac.update(body);
+    mac
+}
+
+// The Twitch-Eventsub-Message-Signature header twitch sends along with a message
+pub fn signature(secret: &str, message_id: &str, timestamp: &str, body: &[u8]) -> String {
+    let tag = hmac(secret, message_id, timestamp, body)
+        .finalize()
+        .into_bytes();
+    let hex: String = tag.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+    format!("sha256={}", hex)
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// Message ids already handled, oldest first so the set doesn't grow forever
+#[derive(Default)]
+struct SeenMessages {
+    ids: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl SeenMessages {
+    // false if the id was seen before
+    fn insert(&mut self, id: &str) -> bool {
+        if !self.ids.insert(id.to_owned()) {
+            return false;
+        }
+
+        self.order.push_back(id.to_owned());
+        if self.order.len() > SEEN_MESSAGE_IDS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+
+        true
+    }
+}
+
+// Read-only data the HTTP server answers GET requests with, next to the webhooks
+#[async_trait]
+pub trait JsonRoutes: Send + Sync {
+    // the status and JSON body for a path
+    async fn get(&self, path: &str) -> (StatusCode, String);
+}
+
+// HTTP endpoint twitch delivers EventSub webhooks to, every verified event is handed to the
+// subscribers of `subscribe`
+pub struct EventSubReceiver {
+    secret: String,
+    listen_addr: SocketAddr,
+    seen: Mutex<SeenMessages>,
+    events: broadcast::Sender<TwitchEvent>,
+}
+
+impl EventSubReceiver {
+    pub fn new(secret: String, listen_addr: SocketAddr) -> Self {
+        let (events, _) = broadcast::channel(256);
+
+        Self {
+            secret,
+            listen_addr,
+            seen: Mutex::new(SeenMessages::default()),
+            events,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TwitchEvent> {
+        self.events.subscribe()
+    }
+
+    // Starts the HTTP server, returns the address it ended up listening on
+    pub fn serve(
+        receiver: Arc<EventSubReceiver>,
+        routes: Option<Arc<dyn JsonRoutes>>,
+    ) -> Result<SocketAddr, hyper::Error> {
+        let listen_addr = receiver.listen_addr;
+        let make_service = make_service_fn(move |_| {
+            let receiver = Arc::clone(&receiver);
+            let routes = routes.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let receiver = Arc::clone(&receiver);
+                    let routes = routes.clone();
+                    async move { Ok::<_, Infallible>(receiver.respond(request, routes).await) }
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&listen_addr)?.serve(make_service);
+        let local_addr = server.local_addr();
+        tokio::spawn(async move {
+            if let Err(e) = server.await {
+                eprintln!("{}: EventSub server stopped: {}", Utc::now(), e);
+            }
+        });
+
+        Ok(local_addr)
+    }
+
+    async fn respond(
+        &self,
+        request: Request<Body>,
+        routes: Option<Arc<dyn JsonRoutes>>,
+    ) -> Response<Body> {
+        if let (&Method::GET, Some(routes)) = (request.method(), routes) {
+            let (status, body) = routes.get(request.uri().path()).await;
+            return Response::builder()
+                .status(status)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap();
+        }
+
+        if request.method() != Method::POST {
+            return Response::builder()
+                .status(StatusCode::METHOD_NOT_ALLOWED)
+                .body(Body::empty())
+                .unwrap();
+        }
+
+        let headers = request.headers().clone();
+        let (status, body) = match hyper::body::to_bytes(request.into_body()).await {
+            Ok(body) => self.handle(&headers, &body),
+            Err(_) => (StatusCode::BAD_REQUEST, String::new()),
+        };
+
+        Response::builder()
+            .status(status)
+            .header("Content-Type", "text/plain")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    // Checks a delivery and dispatches it, returns what to answer twitch with
+    pub fn handle(&self, headers: &HeaderMap, body: &[u8]) -> (StatusCode, String) {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("")
+        };
+        let message_id = header("Twitch-Eventsub-Message-Id");
+        let timestamp = header("Twitch-Eventsub-Message-Timestamp");
+        let message_type = header("Twitch-Eventsub-Message-Type");
+
+        let tag = header("Twitch-Eventsub-Message-Signature")
+            .strip_prefix("sha256=")
+            .and_then(decode_hex);
+        let verified = match tag {
+            Some(tag) => hmac(&self.secret, message_id, timestamp, body)
+                .verify(&tag)
+                .is_ok(),
+            None => false,
+        };
+        let fresh = DateTime::parse_from_rfc3339(timestamp)
+            .map(|sent_at| {
+                (Utc::now() - sent_at.with_timezone(&Utc)).num_seconds() < MAX_MESSAGE_AGE
+            })
+            .unwrap_or(false);
+        if message_id.is_empty() || !verified || !fresh {
+            return (StatusCode::FORBIDDEN, String::new());
+        }
+
+        // twitch retries deliveries it isn't sure about, those are acknowledged but not handled again
+        if !self.seen.lock().unwrap().insert(message_id) {
+            return (StatusCode::NO_CONTENT, String::new());
+        }
+
+        let message: Message = match serde_json::from_slice(body) {
+            Ok(message) => message,
+            Err(e) => {
+                eprintln!(
+                    "{}: Malformed EventSub message {}: {}",
+                    Utc::now(),
+                    message_id,
+                    e
+                );
+                return (StatusCode::BAD_REQUEST, String::new());
+            }
+        };
+
+        match message_type {
+            "webhook_callback_verification" => match message.challenge {
+                Some(challenge) => (StatusCode::OK, challenge),
+                None => (StatusCode::BAD_REQUEST, String::new()),
+            },
+            "notification" => {
+                let event = message.event.unwrap_or(serde_json::Value::Null);
+                match parse_event(&message.subscription.type_field, event) {
+                    Ok(Some(event)) => {
+                        let _ = self.events.send(event);
+                    }
+                    Ok(None) => {}
+                    Err(e) => eprintln!(
+                        "{}: Couldn't read {} event: {}",
+                        Utc::now(),
+                        message.subscription.type_field,
+                        e
+                    ),
+                }
+                (StatusCode::NO_CONTENT, String::new())
+            }
+            "revocation" => {
+                eprintln!(
+                    "{}: EventSub subscription to {} revoked: {}",
+                    Utc::now(),
+                    message.subscription.type_field,
+                    message.subscription.status
+                );
+                let _ = self.events.send(TwitchEvent::Revoked {
+                    subscription_type: message.subscription.type_field,
+                    status: message.subscription.status,
+                });
+                (StatusCode::NO_CONTENT, String::new())
+            }
+            _ => (StatusCode::BAD_REQUEST, String::new()),
+        }
+    }
+}
+
+// what a subscription is identified by: its type and condition
+type SubscriptionKey = (String, BTreeMap<String, String>);
+
+// Keeps the EventSub subscriptions of our callback in line with the joined channels
+pub struct EventSubManager {
+    helix: Arc<Helix>,
+    users: Arc<UserCache>,
+    callback_url: String,
+    secret: String,
+    bot_user_id: Option<String>,
+    reconcile_requested: Notify,
+}
+
+impl EventSubManager {
+    pub fn new(helix: Arc<Helix>, users: Arc<UserCache>, config: &EventSubConfig) -> Self {
+        Self {
+            helix,
+            users,
+            callback_url: config.callback_url.clone(),
+            secret: config.secret.clone(),
+            bot_user_id: config.bot_user_id.clone(),
+            reconcile_requested: Notify::new(),
+        }
+    }
+
+    // type, version and condition of every subscription a channel needs
+    fn wanted_subscriptions(&self, broadcaster_id: &str) -> Vec<(SubscriptionKey, &'static str)> {
+        let broadcaster = |field: &str| {
+            let mut condition = BTreeMap::new();
+            condition.insert(field.to_owned(), broadcaster_id.to_owned());
+            condition
+        };
+
+        let mut wanted = vec![
+            (
+                (
+                    "stream.online".to_owned(),
+                    broadcaster("broadcaster_user_id"),
+                ),
+                "1",
+            ),
+            (
+                (
+                    "stream.offline".to_owned(),
+                    broadcaster("broadcaster_user_id"),
+                ),
+                "1",
+            ),
+            (
+                (
+                    "channel.update".to_owned(),
+                    broadcaster("broadcaster_user_id"),
+                ),
+                "2",
+            ),
+            (
+                (
+                    "channel.raid".to_owned(),
+                    broadcaster("to_broadcaster_user_id"),
+                ),
+                "1",
+            ),
+        ];
+        if let Some(bot_user_id) = &self.bot_user_id {
+            let mut condition = broadcaster("broadcaster_user_id");
+            condition.insert("moderator_user_id".to_owned(), bot_user_id.clone());
+            wanted.push((("channel.follow".to_owned(), condition), "2"));
+        }
+
+        wanted
+    }
+
+    // Creates whatever is missing for the channels and removes subscriptions that failed or
+    // belong to channels that were left
+    pub async fn reconcile(&self, channels: &HashSet<String>) -> Result<(), HelixError> {
+        let logins: Vec<&str> = channels.iter().map(|channel| channel.as_str()).collect();
+        let mut wanted: HashMap<SubscriptionKey, &'static str> = HashMap::new();
+        for user in self.users.get_by_logins(&logins).await? {
+            wanted.extend(self.wanted_subscriptions(&user.id));
+        }
+
+        for subscription in self.helix.get_eventsub_subscriptions().await? {
+            if subscription.transport.callback.as_deref() != Some(self.callback_url.as_str()) {
+                continue;
+            }
+
+            // twitch lists the condition fields that weren't set as empty strings
+            let key = (
+                subscription.type_field.clone(),
+                subscription
+                    .condition
+                    .into_iter()
+                    .filter(|(_, value)| !value.is_empty())
+                    .collect(),
+            );
+            let healthy = subscription.status == "enabled"
+                || subscription.status == "webhook_callback_verification_pending";
+            if healthy && wanted.remove(&key).is_some() {
+                continue;
+            }
+
+            self.helix
+                .delete_eventsub_subscription(&subscription.id)
+                .await?;
+        }
+
+        for ((type_field, condition), version) in wanted {
+            let condition = serde_json::to_value(&condition).expect("string map is valid json");
+            if let Err(e) = self
+                .helix
+                .create_eventsub_subscription(
+                    &type_field,
+                    version,
+                    condition,
+                    &self.callback_url,
+                    &self.secret,
+                )
+                .await
+            {
+                // one channel refusing a subscription shouldn't hold up the others
+                eprintln!(
+                    "{}: Couldn't subscribe to {}: {}",
+                    Utc::now(),
+                    type_field,
+                    e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    // asks the reconcile loop to run now instead of waiting for the next interval
+    pub fn request_reconcile(&self) {
+        self.reconcile_requested.notify_one();
+    }
+
+    pub fn start_reconcile_loop(
+        manager: Arc<EventSubManager>,
+        channels: Arc<tokio::sync::Mutex<HashSet<String>>>,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                let current_channels = channels.lock().await.clone();
+                if let Err(e) = manager.reconcile(&current_channels).await {
+                    eprintln!(
+                        "{}: Error reconciling EventSub subscriptions: {}",
+                        Utc::now(),
+                        e
+                    );
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(RECONCILE_INTERVAL) => {}
+                    _ = manager.reconcile_requested.notified() => {}
+                }
+            }
+        });
+    }
+}
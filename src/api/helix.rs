@@ -1,18 +1,33 @@
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use reqwest::{header::HeaderMap, Client};
+use chrono::{DateTime, TimeZone, Utc};
+use rand::Rng;
+use reqwest::{header::HeaderMap, Client, Method, Response, StatusCode};
 use serde::Deserialize;
+use tokio::sync::RwLock;
 
 #[allow(dead_code)]
 #[derive(Deserialize)]
 struct AppAccessToken {
     access_token: String,
     refresh_token: Option<String>,
-    expires_in: usize,
+    expires_in: u64,
     scope: Option<Vec<String>>,
     token_type: String,
 }
 
+#[allow(dead_code)]
+#[derive(Deserialize)]
+struct ValidateTokenResponse {
+    client_id: String,
+    scopes: Option<Vec<String>>,
+    expires_in: i64,
+}
+
 #[derive(Deserialize)]
 pub struct GetUsersResponse {
     data: Vec<User>,
@@ -35,52 +50,348 @@ pub struct User {
     pub created_at: String,
 }
 
+#[derive(Deserialize)]
+struct GetStreamsResponse {
+    data: Vec<Stream>,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Deserialize)]
+pub struct Stream {
+    pub id: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub game_id: String,
+    pub game_name: String,
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub title: String,
+    pub viewer_count: i32,
+    pub started_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct GetChannelInformationResponse {
+    data: Vec<ChannelInformation>,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Deserialize)]
+pub struct ChannelInformation {
+    pub broadcaster_id: String,
+    pub broadcaster_login: String,
+    pub broadcaster_name: String,
+    pub game_id: String,
+    pub game_name: String,
+    pub title: String,
+}
+
+#[derive(Deserialize)]
+struct GetChannelFollowersResponse {
+    data: Vec<Follower>,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Deserialize)]
+pub struct Follower {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub followed_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct Pagination {
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EventSubSubscriptionsResponse {
+    data: Vec<EventSubSubscription>,
+    pagination: Option<Pagination>,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct EventSubTransport {
+    pub method: String,
+    pub callback: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct EventSubSubscription {
+    pub id: String,
+    pub status: String,
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub version: String,
+    pub condition: HashMap<String, String>,
+    pub transport: EventSubTransport,
+}
+
+const DEFAULT_API_URL: &str = "https://api.twitch.tv/helix";
+const DEFAULT_AUTH_URL: &str = "https://id.twitch.tv/oauth2";
+
+// twitch asks for tokens to be validated every hour
+const TOKEN_VALIDATE_INTERVAL: Duration = Duration::from_secs(3600);
+
+// tokens expiring within this many seconds get replaced ahead of time
+const TOKEN_REFRESH_MARGIN: i64 = 24 * 3600;
+
+// token requests that take longer than this are given up on, a refresh gets retried next hour
+const TOKEN_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// transient failures (5xx, 429, connection problems) are retried this many times
+const MAX_RETRIES: u32 = 3;
+
+// doubled on every retry, with up to half of it added as jitter
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+// the users and streams endpoints take at most 100 logins and ids combined
+const MAX_USERS_PER_REQUEST: usize = 100;
+
+// upper bound for waiting on an exhausted rate limit bucket, in case the reset header is off
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub enum HelixError {
+    NotFound,
+
+    // twitch still rejected the request after getting a fresh token
+    Unauthorized,
+
+    // still rate limited after retrying, holds when the bucket refills if twitch told us
+    RateLimited(Option<DateTime<Utc>>),
+
+    // any other unsuccessful status, 5xx only after the retries ran out
+    Status(StatusCode),
+
+    // the request couldn't be sent or the response couldn't be read
+    Transport(reqwest::Error),
+
+    // the endpoint needs a user access token and none was configured
+    MissingUserToken,
+}
+
+impl fmt::Display for HelixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HelixError::NotFound => write!(f, "helix resource not found"),
+            HelixError::Unauthorized => write!(f, "helix rejected the access token"),
+            HelixError::RateLimited(Some(reset_at)) => {
+                write!(f, "helix rate limit exceeded until {}", reset_at)
+            }
+            HelixError::RateLimited(None) => write!(f, "helix rate limit exceeded"),
+            HelixError::Status(status) => write!(f, "helix responded with {}", status),
+            HelixError::Transport(e) => write!(f, "helix request failed: {}", e),
+            HelixError::MissingUserToken => write!(f, "no helix user access token configured"),
+        }
+    }
+}
+
+impl std::error::Error for HelixError {}
+
+impl From<reqwest::Error> for HelixError {
+    fn from(e: reqwest::Error) -> Self {
+        HelixError::Transport(e)
+    }
+}
+
+pub struct HelixConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub access_token: Option<String>,
+
+    // token of the bot's own account, for endpoints that don't take app access tokens like
+    // the follower list, which also needs the bot to moderate the channel
+    pub user_token: Option<String>,
+
+    // base urls, can be pointed at the twitch cli's mock api or a local stub
+    pub api_url: String,
+    pub auth_url: String,
+}
+
+impl HelixConfig {
+    pub fn from_env() -> Self {
+        Self {
+            client_id: env::var("BORROWBOT_CLIENT_ID")
+                .expect("Couldn't find env var for bot client id"),
+            client_secret: env::var("BORROWBOT_CLIENT_SECRET")
+                .expect("Couldn't find env var for bot client secret"),
+            access_token: env::var("BORROWBOT_ACCESS_TOKEN").ok(),
+            user_token: env::var("BORROWBOT_USER_TOKEN").ok(),
+            api_url: env::var("BORROWBOT_HELIX_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_owned()),
+            auth_url: env::var("BORROWBOT_TWITCH_AUTH_URL")
+                .unwrap_or_else(|_| DEFAULT_AUTH_URL.to_owned()),
+        }
+    }
+}
+
+struct TokenState {
+    access_token: String,
+
+    // None if twitch didn't give the token an expiry
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl TokenState {
+    fn new(access_token: String, expires_in: i64) -> Self {
+        let expires_at = if expires_in > 0 {
+            Some(Utc::now() + chrono::Duration::seconds(expires_in))
+        } else {
+            None
+        };
+
+        Self {
+            access_token,
+            expires_at,
+        }
+    }
+
+    fn expires_soon(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => (expires_at - Utc::now()).num_seconds() < TOKEN_REFRESH_MARGIN,
+            None => false,
+        }
+    }
+}
+
+// What the Ratelimit-* headers of the last response said about our token bucket
+#[derive(Default)]
+struct RateLimit {
+    remaining: Option<u32>,
+    reset_at: Option<DateTime<Utc>>,
+}
+
+impl RateLimit {
+    fn update(&mut self, headers: &HeaderMap) {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<i64>().ok())
+        };
+
+        if let Some(remaining) = header("Ratelimit-Remaining") {
+            self.remaining = Some(remaining.max(0) as u32);
+        }
+        // an out of range reset would panic in Utc.timestamp, so it's ignored
+        if let Some(reset) =
+            header("Ratelimit-Reset").and_then(|reset| Utc.timestamp_opt(reset, 0).single())
+        {
+            self.reset_at = Some(reset);
+        }
+    }
+
+    // how long to hold off before sending anything, None if there are points left
+    fn wait_time(&self) -> Option<Duration> {
+        if self.remaining != Some(0) {
+            return None;
+        }
+
+        let wait = (self.reset_at? - Utc::now()).to_std().ok()?;
+        Some(wait.min(MAX_RATE_LIMIT_WAIT))
+    }
+}
+
+// Which of our tokens a request is sent with
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Auth {
+    App,
+    User,
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+    let delay = RETRY_BASE_DELAY * 2u32.pow(attempt.saturating_sub(1));
+    let jitter = rand::thread_rng().gen_range(0..=delay.as_millis() as u64 / 2);
+    delay + Duration::from_millis(jitter)
+}
+
 pub struct Helix {
     client: Client,
-    pub access_token: String,
+    client_id: String,
+    client_secret: String,
+    api_url: String,
+    auth_url: String,
+
+    // can't be refreshed by us, twitch rejecting it is reported right away
+    user_token: Option<String>,
+
+    // who the user token belongs to, looked up the first time it's needed
+    token_user_id: Mutex<Option<String>>,
+
+    // swapped out as a whole when refreshed, requests read the current token when they are sent
+    token: RwLock<TokenState>,
+    rate_limit: Mutex<RateLimit>,
 }
 
 impl Helix {
-    pub async fn new() -> Result<Self, reqwest::Error> {
-        let client_id =
-            env::var("BORROWBOT_CLIENT_ID").expect("Couldn't find env var for bot client id");
-        let client_secret = env::var("BORROWBOT_CLIENT_SECRET")
-            .expect("Couldn't find env var for bot client secret");
-        let access_token = match env::var("BORROWBOT_ACCESS_TOKEN") {
-            Ok(token) => Some(token),
-            _ => None,
-        };
+    pub async fn new() -> Result<Self, HelixError> {
+        Self::with_config(HelixConfig::from_env()).await
+    }
 
-        let access_token = match access_token {
-            Some(token) => token,
-            None => Self::get_access_token(&client_id[..], &client_secret[..])
-                .await
-                .expect("Error getting new access token"),
-        };
+    pub async fn with_config(config: HelixConfig) -> Result<Self, HelixError> {
+        let HelixConfig {
+            client_id,
+            client_secret,
+            access_token,
+            user_token,
+            api_url,
+            auth_url,
+        } = config;
 
         let mut headers = HeaderMap::new();
         headers.insert("Client-id", client_id.parse().unwrap());
-        headers.insert(
-            "Authorization",
-            format!("Bearer {}", access_token).parse().unwrap(),
-        );
-
         let client = Client::builder().default_headers(headers).build()?;
 
+        // a token given through the environment is only trusted if twitch still accepts it
+        let validated = match access_token {
+            Some(token) => Self::validate_token(&client, &auth_url, &token)
+                .await?
+                .map(|expires_in| TokenState::new(token, expires_in)),
+            None => None,
+        };
+
+        let token = match validated {
+            Some(token) => token,
+            None => {
+                let token = Self::get_access_token(&auth_url, &client_id, &client_secret).await?;
+                TokenState::new(token.access_token, token.expires_in as i64)
+            }
+        };
+
         Ok(Self {
             client,
-            access_token,
+            client_id,
+            client_secret,
+            api_url,
+            auth_url,
+            user_token,
+            token_user_id: Mutex::new(None),
+            token: RwLock::new(token),
+            rate_limit: Mutex::new(RateLimit::default()),
         })
     }
 
-    pub async fn get_access_token(
+    pub async fn access_token(&self) -> String {
+        self.token.read().await.access_token.clone()
+    }
+
+    pub async fn token_expires_at(&self) -> Option<DateTime<Utc>> {
+        self.token.read().await.expires_at
+    }
+
+    async fn get_access_token(
+        auth_url: &str,
         client_id: &str,
         client_secret: &str,
-    ) -> Result<String, reqwest::Error> {
-        let client = Client::new();
+    ) -> Result<AppAccessToken, reqwest::Error> {
+        let client = Client::builder().timeout(TOKEN_REQUEST_TIMEOUT).build()?;
 
         let resp = client
-            .post("https://id.twitch.tv/oauth2/token")
+            .post(format!("{}/token", auth_url))
             .query(&[
                 ("client_id", client_id),
                 ("client_secret", client_secret),
@@ -88,27 +399,380 @@ impl Helix {
             ])
             .send()
             .await?
+            .error_for_status()?
             .json::<AppAccessToken>()
             .await?;
 
-        eprintln!("Generated new access_token: {}", resp.access_token);
-        Ok(resp.access_token)
+        eprintln!(
+            "{}: Generated new access token, expires in {}s",
+            Utc::now(),
+            resp.expires_in
+        );
+        Ok(resp)
     }
 
-    pub async fn get_user_by_login(&self, login: &str) -> Result<Option<User>, reqwest::Error> {
-        let mut resp = self
-            .client
-            .get("https://api.twitch.tv/helix/users")
-            .query(&[("login", login)])
+    // Returns the seconds left until the token expires, or None if twitch says it's invalid
+    async fn validate_token(
+        client: &Client,
+        auth_url: &str,
+        token: &str,
+    ) -> Result<Option<i64>, reqwest::Error> {
+        let resp = client
+            .get(format!("{}/validate", auth_url))
+            .header("Authorization", format!("OAuth {}", token))
             .send()
-            .await?
-            .json::<GetUsersResponse>()
             .await?;
 
-        if resp.data.is_empty() {
+        if resp.status() == StatusCode::UNAUTHORIZED {
             return Ok(None);
         }
 
-        Ok(Some(resp.data.remove(0)))
+        let resp = resp
+            .error_for_status()?
+            .json::<ValidateTokenResponse>()
+            .await?;
+        Ok(Some(resp.expires_in))
+    }
+
+    // Replaces the token unless another task already replaced `stale_token` in the meantime.
+    // The new token is fetched without holding the lock so requests aren't stuck behind it
+    async fn refresh_access_token(&self, stale_token: &str) -> Result<(), HelixError> {
+        if self.token.read().await.access_token != stale_token {
+            return Ok(());
+        }
+
+        let new_token =
+            Self::get_access_token(&self.auth_url, &self.client_id, &self.client_secret).await?;
+
+        let mut token = self.token.write().await;
+        if token.access_token == stale_token {
+            *token = TokenState::new(new_token.access_token, new_token.expires_in as i64);
+        }
+
+        Ok(())
+    }
+
+    // validates the token every hour and replaces it before it expires or once it stops working
+    pub fn start_token_refresh_loop(helix: Arc<Helix>) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(TOKEN_VALIDATE_INTERVAL).await;
+
+                let current_token = helix.access_token().await;
+                let needs_refresh = match Self::validate_token(
+                    &helix.client,
+                    &helix.auth_url,
+                    &current_token,
+                )
+                .await
+                {
+                    Ok(Some(expires_in)) => {
+                        // twitch knows best when the token expires, keep our estimate in sync
+                        let mut token = helix.token.write().await;
+                        if token.access_token == current_token {
+                            *token = TokenState::new(current_token.clone(), expires_in);
+                        }
+                        token.expires_soon()
+                    }
+                    Ok(None) => true,
+                    Err(e) => {
+                        eprintln!("{}: Error validating access token: {}", Utc::now(), e);
+                        false
+                    }
+                };
+
+                if needs_refresh {
+                    if let Err(e) = helix.refresh_access_token(&current_token).await {
+                        eprintln!("{}: Error refreshing access token: {}", Utc::now(), e);
+                    }
+                }
+            }
+        });
+    }
+
+    // Waits out an exhausted rate limit bucket before a request is sent
+    async fn wait_for_rate_limit(&self) {
+        let wait = self.rate_limit.lock().unwrap().wait_time();
+        if let Some(wait) = wait {
+            eprintln!(
+                "{}: Helix rate limit reached, waiting {}ms",
+                Utc::now(),
+                wait.as_millis()
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    async fn get(&self, endpoint: &str, query: &[(&str, &str)]) -> Result<Response, HelixError> {
+        self.request(Method::GET, endpoint, query, None).await
+    }
+
+    async fn get_as_user(
+        &self,
+        endpoint: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Response, HelixError> {
+        self.send(Auth::User, Method::GET, endpoint, query, None)
+            .await
+    }
+
+    async fn request(
+        &self,
+        method: Method,
+        endpoint: &str,
+        query: &[(&str, &str)],
+        body: Option<&serde_json::Value>,
+    ) -> Result<Response, HelixError> {
+        self.send(Auth::App, method, endpoint, query, body).await
+    }
+
+    // Request against the helix api. Transient failures are retried with backoff, and if
+    // twitch rejects the app token it is refreshed and the request is sent once more
+    async fn send(
+        &self,
+        auth: Auth,
+        method: Method,
+        endpoint: &str,
+        query: &[(&str, &str)],
+        body: Option<&serde_json::Value>,
+    ) -> Result<Response, HelixError> {
+        let url = format!("{}/{}", self.api_url, endpoint);
+        let mut attempt = 0;
+        let mut refreshed_token = false;
+
+        loop {
+            self.wait_for_rate_limit().await;
+
+            let token = match auth {
+                Auth::App => self.access_token().await,
+                Auth::User => self
+                    .user_token
+                    .clone()
+                    .ok_or(HelixError::MissingUserToken)?,
+            };
+            let mut request = self
+                .client
+                .request(method.clone(), &url)
+                .query(query)
+                .bearer_auth(&token);
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+
+            let resp = match request.send().await {
+                Ok(resp) => resp,
+                Err(e) if (e.is_connect() || e.is_timeout()) && attempt < MAX_RETRIES => {
+                    attempt += 1;
+                    tokio::time::sleep(retry_delay(attempt)).await;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let (reset_at, bucket_empty) = {
+                let mut rate_limit = self.rate_limit.lock().unwrap();
+                rate_limit.update(resp.headers());
+                (rate_limit.reset_at, rate_limit.wait_time().is_some())
+            };
+
+            match resp.status() {
+                status if status.is_success() => return Ok(resp),
+                StatusCode::UNAUTHORIZED if auth == Auth::App && !refreshed_token => {
+                    refreshed_token = true;
+                    self.refresh_access_token(&token).await?;
+                }
+                StatusCode::UNAUTHORIZED => return Err(HelixError::Unauthorized),
+                StatusCode::NOT_FOUND => return Err(HelixError::NotFound),
+                StatusCode::TOO_MANY_REQUESTS if attempt < MAX_RETRIES => {
+                    attempt += 1;
+                    // with a known reset time the wait happens before the next attempt
+                    if !bucket_empty {
+                        tokio::time::sleep(retry_delay(attempt)).await;
+                    }
+                }
+                StatusCode::TOO_MANY_REQUESTS => return Err(HelixError::RateLimited(reset_at)),
+                status if status.is_server_error() && attempt < MAX_RETRIES => {
+                    attempt += 1;
+                    tokio::time::sleep(retry_delay(attempt)).await;
+                }
+                status => return Err(HelixError::Status(status)),
+            }
+        }
+    }
+
+    // Looks up any mix of logins and ids, split into requests of at most 100 since that's all
+    // helix accepts at once. Users that don't exist are left out of the result
+    pub async fn get_users(&self, logins: &[&str], ids: &[&str]) -> Result<Vec<User>, HelixError> {
+        let query: Vec<(&str, &str)> = logins
+            .iter()
+            .map(|login| ("login", *login))
+            .chain(ids.iter().map(|id| ("id", *id)))
+            .collect();
+
+        let mut users = Vec::new();
+        for chunk in query.chunks(MAX_USERS_PER_REQUEST) {
+            let resp = self
+                .get("users", chunk)
+                .await?
+                .json::<GetUsersResponse>()
+                .await?;
+            users.extend(resp.data);
+        }
+
+        Ok(users)
+    }
+
+    pub async fn get_user_by_login(&self, login: &str) -> Result<Option<User>, HelixError> {
+        Ok(self.get_users(&[login], &[]).await?.into_iter().next())
+    }
+
+    // Only channels that are currently live show up in the result
+    pub async fn get_streams(&self, logins: &[&str]) -> Result<Vec<Stream>, HelixError> {
+        let query: Vec<(&str, &str)> = logins.iter().map(|login| ("user_login", *login)).collect();
+
+        let mut streams = Vec::new();
+        for chunk in query.chunks(MAX_USERS_PER_REQUEST) {
+            let resp = self
+                .get("streams", chunk)
+                .await?
+                .json::<GetStreamsResponse>()
+                .await?;
+            streams.extend(resp.data);
+        }
+
+        Ok(streams)
+    }
+
+    // title and category of a channel, whether it's live or not
+    pub async fn get_channel_information(
+        &self,
+        broadcaster_id: &str,
+    ) -> Result<Option<ChannelInformation>, HelixError> {
+        let resp = self
+            .get("channels", &[("broadcaster_id", broadcaster_id)])
+            .await?
+            .json::<GetChannelInformationResponse>()
+            .await?;
+
+        Ok(resp.data.into_iter().next())
+    }
+
+    // When `user_id` followed the channel, None if they don't. Twitch only shows this to
+    // moderators of the channel, so it's asked with the bot's user token
+    pub async fn get_channel_follower(
+        &self,
+        broadcaster_id: &str,
+        user_id: &str,
+    ) -> Result<Option<Follower>, HelixError> {
+        let resp = self
+            .get_as_user(
+                "channels/followers",
+                &[("broadcaster_id", broadcaster_id), ("user_id", user_id)],
+            )
+            .await?
+            .json::<GetChannelFollowersResponse>()
+            .await?;
+
+        Ok(resp.data.into_iter().next())
+    }
+
+    // the account the user token belongs to, which is the one whispers are sent from
+    async fn token_user_id(&self) -> Result<String, HelixError> {
+        if let Some(id) = self.token_user_id.lock().unwrap().clone() {
+            return Ok(id);
+        }
+
+        let user = self
+            .get_as_user("users", &[])
+            .await?
+            .json::<GetUsersResponse>()
+            .await?
+            .data
+            .into_iter()
+            .next()
+            .ok_or(HelixError::NotFound)?;
+        *self.token_user_id.lock().unwrap() = Some(user.id.clone());
+
+        Ok(user.id)
+    }
+
+    pub async fn send_whisper(&self, to_user_id: &str, message: &str) -> Result<(), HelixError> {
+        let from_user_id = self.token_user_id().await?;
+        let body = serde_json::json!({ "message": message });
+
+        self.send(
+            Auth::User,
+            Method::POST,
+            "whispers",
+            &[("from_user_id", &from_user_id), ("to_user_id", to_user_id)],
+            Some(&body),
+        )
+        .await?;
+        Ok(())
+    }
+
+    // every subscription of this client, across all pages
+    pub async fn get_eventsub_subscriptions(
+        &self,
+    ) -> Result<Vec<EventSubSubscription>, HelixError> {
+        let mut subscriptions = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut query = Vec::new();
+            if let Some(cursor) = &cursor {
+                query.push(("after", cursor.as_str()));
+            }
+
+            let resp = self
+                .get("eventsub/subscriptions", &query)
+                .await?
+                .json::<EventSubSubscriptionsResponse>()
+                .await?;
+            subscriptions.extend(resp.data);
+
+            cursor = resp
+                .pagination
+                .and_then(|pagination| pagination.cursor)
+                .filter(|cursor| !cursor.is_empty());
+            if cursor.is_none() {
+                return Ok(subscriptions);
+            }
+        }
+    }
+
+    pub async fn create_eventsub_subscription(
+        &self,
+        type_field: &str,
+        version: &str,
+        condition: serde_json::Value,
+        callback: &str,
+        secret: &str,
+    ) -> Result<(), HelixError> {
+        let body = serde_json::json!({
+            "type": type_field,
+            "version": version,
+            "condition": condition,
+            "transport": {
+                "method": "webhook",
+                "callback": callback,
+                "secret": secret,
+            },
+        });
+
+        self.request(Method::POST, "eventsub/subscriptions", &[], Some(&body))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_eventsub_subscription(&self, id: &str) -> Result<(), HelixError> {
+        self.request(
+            Method::DELETE,
+            "eventsub/subscriptions",
+            &[("id", id)],
+            None,
+        )
+        .await?;
+        Ok(())
     }
 }
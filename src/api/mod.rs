@@ -1,15 +1,29 @@
 pub mod banphrase;
+pub mod emotes;
+pub mod eventsub;
 pub mod helix;
 pub mod supinic;
+pub mod usercache;
 
 use std::sync::Arc;
 
+use banphrase::Banphrase;
+use emotes::{EmoteConfig, Emotes};
+use eventsub::{EventSubConfig, EventSubManager, EventSubReceiver};
 use helix::Helix;
 use supinic::Supinic;
+use usercache::UserCache;
 
 pub struct APIController {
     helix: Arc<Helix>,
     supinic: Arc<Supinic>,
+    banphrase: Arc<Banphrase>,
+    users: Arc<UserCache>,
+    emotes: Arc<Emotes>,
+
+    // only there when EventSub is configured
+    eventsub_receiver: Option<Arc<EventSubReceiver>>,
+    eventsub_manager: Option<Arc<EventSubManager>>,
 }
 
 impl APIController {
@@ -21,8 +35,45 @@ impl APIController {
         );
 
         let supinic = Arc::new(Supinic::new());
+        let banphrase = Arc::new(Banphrase::new());
 
-        Self { helix, supinic }
+        let api = Self::new(helix, supinic, banphrase);
+        match EventSubConfig::from_env() {
+            Some(config) => api.with_eventsub(&config),
+            None => api,
+        }
+    }
+
+    pub fn new(helix: Arc<Helix>, supinic: Arc<Supinic>, banphrase: Arc<Banphrase>) -> Self {
+        let users = Arc::new(UserCache::new(Arc::clone(&helix)));
+
+        Self {
+            helix,
+            supinic,
+            banphrase,
+            users,
+            emotes: Arc::new(Emotes::new(EmoteConfig::from_env())),
+            eventsub_receiver: None,
+            eventsub_manager: None,
+        }
+    }
+
+    pub fn with_emotes(mut self, config: EmoteConfig) -> Self {
+        self.emotes = Arc::new(Emotes::new(config));
+        self
+    }
+
+    pub fn with_eventsub(mut self, config: &EventSubConfig) -> Self {
+        self.eventsub_receiver = Some(Arc::new(EventSubReceiver::new(
+            config.secret.clone(),
+            config.listen_addr,
+        )));
+        self.eventsub_manager = Some(Arc::new(EventSubManager::new(
+            Arc::clone(&self.helix),
+            Arc::clone(&self.users),
+            config,
+        )));
+        self
     }
 
     pub fn helix(&self) -> Arc<Helix> {
@@ -32,4 +83,24 @@ impl APIController {
     pub fn supinic(&self) -> Arc<Supinic> {
         Arc::clone(&self.supinic)
     }
+
+    pub fn banphrase(&self) -> Arc<Banphrase> {
+        Arc::clone(&self.banphrase)
+    }
+
+    pub fn users(&self) -> Arc<UserCache> {
+        Arc::clone(&self.users)
+    }
+
+    pub fn emotes(&self) -> Arc<Emotes> {
+        Arc::clone(&self.emotes)
+    }
+
+    pub fn eventsub_receiver(&self) -> Option<Arc<EventSubReceiver>> {
+        self.eventsub_receiver.clone()
+    }
+
+    pub fn eventsub_manager(&self) -> Option<Arc<EventSubManager>> {
+        self.eventsub_manager.clone()
+    }
 }
@@ -4,8 +4,17 @@ use std::sync::Arc;
 use reqwest::header::HeaderMap;
 use reqwest::Client;
 
+const DEFAULT_SUPINIC_URL: &str = "https://supinic.com/api";
+
 pub struct Supinic {
     client: Arc<Client>,
+    api_url: Arc<String>,
+}
+
+impl Default for Supinic {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Supinic {
@@ -18,7 +27,13 @@ impl Supinic {
             eprintln!("Failed to find supinic key env var");
             "".to_owned()
         });
+        let api_url =
+            env::var("SUPINIC_API_URL").unwrap_or_else(|_| DEFAULT_SUPINIC_URL.to_owned());
+
+        Self::with_config(&user_id, &api_key, api_url)
+    }
 
+    pub fn with_config(user_id: &str, api_key: &str, api_url: String) -> Self {
         let mut headers = HeaderMap::new();
         headers.insert(
             "Authorization",
@@ -26,34 +41,36 @@ impl Supinic {
         );
         headers.insert(
             "User-Agent",
-            format!(
-                "BorrowBot - made by @1xelerate using Rust. \
+            "BorrowBot - made by @1xelerate using Rust. \
             Source Code: https://github.com/bleusakura/BorrowBot"
-            )
-            .parse()
-            .unwrap(),
+                .parse()
+                .unwrap(),
         );
 
         let client = Arc::new(Client::builder().default_headers(headers).build().unwrap());
 
-        Self { client }
+        Self {
+            client,
+            api_url: Arc::new(api_url),
+        }
     }
 
     pub async fn start_supinic_ping_loop(&self) {
         let client = Arc::clone(&self.client);
+        let api_url = Arc::clone(&self.api_url);
         tokio::spawn(async move {
             loop {
-                if let Err(_) = Supinic::ping_supinic(&client).await {
-                    format!("{}: Error pinging supinic", chrono::Utc::now());
+                if Supinic::ping_supinic(&client, &api_url).await.is_err() {
+                    eprintln!("{}: Error pinging supinic", chrono::Utc::now());
                 }
                 tokio::time::sleep(std::time::Duration::from_secs(1800)).await;
             }
         });
     }
 
-    pub async fn ping_supinic(client: &Arc<Client>) -> Result<(), reqwest::Error> {
+    pub async fn ping_supinic(client: &Arc<Client>, api_url: &str) -> Result<(), reqwest::Error> {
         client
-            .put("https://supinic.com/api/bot-program/bot/active")
+            .put(format!("{}/bot-program/bot/active", api_url))
             .send()
             .await?;
 
@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::time::Instant;
+use twitch_irc::message::PrivmsgMessage;
+
+use super::helix::{Helix, HelixError, User};
+
+// how long a looked up or seen user is trusted before asking helix again
+const USER_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+#[derive(Clone)]
+pub struct CachedUser {
+    pub id: String,
+    pub login: String,
+    pub display_name: String,
+
+    // None for users only seen in chat so far, chat messages don't carry it
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl From<User> for CachedUser {
+    fn from(user: User) -> Self {
+        Self {
+            created_at: DateTime::parse_from_rfc3339(&user.created_at)
+                .ok()
+                .map(|created_at| created_at.with_timezone(&Utc)),
+            id: user.id,
+            login: user.login,
+            display_name: user.display_name,
+        }
+    }
+}
+
+#[derive(Default)]
+struct CacheState {
+    // id -> user and when it was last refreshed
+    users: HashMap<String, (CachedUser, Instant)>,
+    logins: HashMap<String, String>,
+
+    // ids seen in chat that still need their full helix record
+    pending: HashSet<String>,
+}
+
+impl CacheState {
+    fn insert(&mut self, user: CachedUser) {
+        if let Some((old, _)) = self.users.get(&user.id) {
+            if old.login != user.login {
+                self.logins.remove(&old.login);
+            }
+        }
+
+        self.logins.insert(user.login.clone(), user.id.clone());
+        self.users.insert(user.id.clone(), (user, Instant::now()));
+    }
+
+    fn get_by_id(&self, id: &str) -> Option<&CachedUser> {
+        self.users
+            .get(id)
+            .filter(|(_, refreshed)| refreshed.elapsed() < USER_CACHE_TTL)
+            .map(|(user, _)| user)
+    }
+
+    fn get_by_login(&self, login: &str) -> Option<&CachedUser> {
+        self.get_by_id(self.logins.get(login)?)
+    }
+
+    // expired users would be asked for again anyway, so there is no point in keeping them
+    fn prune(&mut self) {
+        self.users
+            .retain(|_, (_, refreshed)| refreshed.elapsed() < USER_CACHE_TTL);
+        let users = &self.users;
+        self.logins.retain(|_, id| users.contains_key(id));
+    }
+}
+
+// Shared by every command that needs to resolve users, so repeated lookups of the same
+// people don't each cost a helix request
+pub struct UserCache {
+    helix: Arc<Helix>,
+    state: Mutex<CacheState>,
+}
+
+impl UserCache {
+    pub fn new(helix: Arc<Helix>) -> Self {
+        Self {
+            helix,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    // every chat message tells us a user's current login and display name for free
+    pub fn learn(&self, msg: &PrivmsgMessage) {
+        let mut state = self.state.lock().unwrap();
+        let created_at = match state.get_by_id(&msg.sender.id) {
+            Some(user) => user.created_at,
+            None => None,
+        };
+        if created_at.is_none() {
+            state.pending.insert(msg.sender.id.clone());
+        }
+
+        state.insert(CachedUser {
+            id: msg.sender.id.clone(),
+            login: msg.sender.login.clone(),
+            display_name: msg.sender.name.clone(),
+            created_at,
+        });
+    }
+
+    pub async fn get_by_login(&self, login: &str) -> Result<Option<CachedUser>, HelixError> {
+        let login = login.to_lowercase();
+        Ok(self.get_by_logins(&[&login]).await?.into_iter().next())
+    }
+
+    // cached users are answered right away, the rest are looked up in as few requests as possible
+    pub async fn get_by_logins(&self, logins: &[&str]) -> Result<Vec<CachedUser>, HelixError> {
+        let mut found = Vec::new();
+        let mut missing = Vec::new();
+        {
+            let state = self.state.lock().unwrap();
+            for login in logins {
+                match state.get_by_login(&login.to_lowercase()) {
+                    Some(user) => found.push(user.clone()),
+                    None => missing.push(login.to_lowercase()),
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            let missing: Vec<&str> = missing.iter().map(|login| login.as_str()).collect();
+            found.extend(self.fetch(&missing, &[]).await?);
+        }
+
+        Ok(found)
+    }
+
+    pub async fn get_by_ids(&self, ids: &[&str]) -> Result<Vec<CachedUser>, HelixError> {
+        let mut found = Vec::new();
+        let mut missing = Vec::new();
+        {
+            let state = self.state.lock().unwrap();
+            for id in ids {
+                match state.get_by_id(id) {
+                    Some(user) => found.push(user.clone()),
+                    None => missing.push(*id),
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            found.extend(self.fetch(&[], &missing).await?);
+        }
+
+        Ok(found)
+    }
+
+    // Takes up to `max` ids of chatters whose helix record hasn't been fetched yet
+    pub fn take_pending(&self, max: usize) -> Vec<String> {
+        let mut state = self.state.lock().unwrap();
+        let ids: Vec<String> = state.pending.iter().take(max).cloned().collect();
+        for id in &ids {
+            state.pending.remove(id);
+        }
+
+        ids
+    }
+
+    // puts ids taken with take_pending back when their lookup failed, to be tried again later
+    pub fn requeue(&self, ids: Vec<String>) {
+        self.state.lock().unwrap().pending.extend(ids);
+    }
+
+    pub fn prune(&self) {
+        self.state.lock().unwrap().prune();
+    }
+
+    // always asks helix, the result replaces whatever was cached
+    pub async fn fetch(
+        &self,
+        logins: &[&str],
+        ids: &[&str],
+    ) -> Result<Vec<CachedUser>, HelixError> {
+        let users: Vec<CachedUser> = self
+            .helix
+            .get_users(logins, ids)
+            .await?
+            .into_iter()
+            .map(CachedUser::from)
+            .collect();
+
+        let mut state = self.state.lock().unwrap();
+        for user in &users {
+            state.pending.remove(&user.id);
+            state.insert(user.clone());
+        }
+
+        Ok(users)
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashSet;
 use std::env;
 use std::sync::Arc;
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use tokio::sync::Mutex;
@@ -8,20 +9,50 @@ use twitch_irc::login::StaticLoginCredentials;
 use twitch_irc::message::ServerMessage;
 use twitch_irc::{ClientConfig, SecureTCPTransport, TwitchIRCClient};
 
+use crate::afk::AfkTracker;
+use crate::api::emotes::Emotes;
+use crate::api::eventsub::{EventSubManager, EventSubReceiver};
+use crate::api::helix::Helix;
 use crate::api::APIController;
 use crate::commandhandler::CommandHandler;
-use crate::database::DBController;
+use crate::counters::Counters;
+use crate::database::{DBController, Database, LogRepository};
+use crate::emotestats::{self, EmoteStats};
+use crate::games::Games;
 use crate::logging::LogController;
-use crate::messenger::Messenger;
+use crate::markov::Markov;
+use crate::messenger::{ChatClient, Messenger};
+use crate::notifications;
+use crate::points::Economy;
+use crate::polls::Polls;
+use crate::profiles::ProfileRoutes;
+use crate::reminders::Reminders;
+use crate::streams::{StreamTracker, EVENTSUB_STREAM_POLL_INTERVAL, STREAM_POLL_INTERVAL};
+use crate::timers::TimerTracker;
+use crate::types::{parse_uid, CommandResponse, DataDir, UserContext};
+
+// how often chatters learned from chat get their helix record fetched and saved
+const USER_ENRICHMENT_INTERVAL: Duration = Duration::from_secs(30);
 
 pub struct BorrowBot {
     irc_stream: Arc<Mutex<tokio::sync::mpsc::UnboundedReceiver<ServerMessage>>>,
-    db: Arc<DBController>,
-    logs: Arc<LogController>,
+    db: Arc<dyn Database>,
+    logs: Arc<dyn LogRepository>,
     api: Arc<APIController>,
     commands: Arc<CommandHandler>,
     messenger: Arc<Messenger>,
     current_channels: Arc<Mutex<HashSet<String>>>,
+    streams: Arc<StreamTracker>,
+    reminders: Arc<Reminders>,
+    afk: Arc<AfkTracker>,
+    timers: Arc<TimerTracker>,
+    markov: Arc<Markov>,
+    emote_stats: Arc<EmoteStats>,
+    counters: Arc<Counters>,
+    economy: Arc<Economy>,
+    polls: Arc<Polls>,
+    games: Arc<Games>,
+    data_dir: DataDir,
     pub start_time: DateTime<Utc>,
 }
 
@@ -39,9 +70,60 @@ impl BorrowBot {
         let db = Arc::new(DBController::new().await);
         let logs = Arc::new(LogController::new().await);
         let api = Arc::new(APIController::init().await);
+
+        Self::from_parts(irc_stream, Arc::new(irc_client), db, logs, api).await
+    }
+
+    // builds the bot around already constructed backends, which lets it run against
+    // something other than the real databases
+    pub async fn from_parts(
+        irc_stream: tokio::sync::mpsc::UnboundedReceiver<ServerMessage>,
+        irc_client: Arc<dyn ChatClient>,
+        db: Arc<dyn Database>,
+        logs: Arc<dyn LogRepository>,
+        api: Arc<APIController>,
+    ) -> Self {
         let commands = Arc::new(CommandHandler::new(Arc::clone(&db)).await);
-        let messenger = Arc::new(Messenger::new(irc_client));
-        let current_channels = Arc::new(Mutex::new(db.get_current_channels().await));
+        let messenger = Arc::new(Messenger::new(irc_client, api.banphrase()));
+        let current_channels = Arc::new(Mutex::new(
+            db.get_current_channels()
+                .await
+                .expect("Couldn't load the joined channels"),
+        ));
+        let streams = Arc::new(
+            StreamTracker::new(Arc::clone(&db), api.helix())
+                .await
+                .expect("Couldn't load the open stream sessions"),
+        );
+        let reminders = Arc::new(
+            Reminders::new(Arc::clone(&db))
+                .await
+                .expect("Couldn't load the pending reminders"),
+        );
+        let afk = Arc::new(
+            AfkTracker::new(Arc::clone(&db))
+                .await
+                .expect("Couldn't load the afk statuses"),
+        );
+        let timers = Arc::new(
+            TimerTracker::new(Arc::clone(&db))
+                .await
+                .expect("Couldn't load the timers"),
+        );
+        let markov = Arc::new(
+            Markov::new(Arc::clone(&logs))
+                .await
+                .expect("Couldn't load the markov opt-outs"),
+        );
+        let emote_stats = Arc::new(EmoteStats::new(Arc::clone(&logs)));
+        let counters = Arc::new(
+            Counters::new(Arc::clone(&db))
+                .await
+                .expect("Couldn't load the counters"),
+        );
+        let economy = Arc::new(Economy::new(Arc::clone(&db)));
+        let polls = Arc::new(Polls::new());
+        let games = Arc::new(Games::new(Arc::clone(&db)));
         let start_time = Utc::now();
 
         Self {
@@ -52,10 +134,27 @@ impl BorrowBot {
             commands,
             messenger,
             current_channels,
+            streams,
+            reminders,
+            afk,
+            timers,
+            markov,
+            emote_stats,
+            counters,
+            economy,
+            polls,
+            games,
+            data_dir: DataDir::from_env(),
             start_time,
         }
     }
 
+    // reads and writes operator files somewhere other than BORROWBOT_DATA_DIR
+    pub fn with_data_dir(mut self, data_dir: DataDir) -> Self {
+        self.data_dir = data_dir;
+        self
+    }
+
     pub fn messenger(&self) -> Arc<Messenger> {
         Arc::clone(&self.messenger)
     }
@@ -64,11 +163,11 @@ impl BorrowBot {
         Arc::clone(&self.irc_stream)
     }
 
-    pub fn db(&self) -> Arc<DBController> {
+    pub fn db(&self) -> Arc<dyn Database> {
         Arc::clone(&self.db)
     }
 
-    pub fn logs(&self) -> Arc<LogController> {
+    pub fn logs(&self) -> Arc<dyn LogRepository> {
         Arc::clone(&self.logs)
     }
 
@@ -84,6 +183,90 @@ impl BorrowBot {
         Arc::clone(&self.current_channels)
     }
 
+    // Saves display names and account creation dates of chatters, fetched in batches
+    fn start_user_enrichment_loop(bot: Arc<BorrowBot>) {
+        tokio::spawn(async move {
+            let users = bot.api().users();
+            loop {
+                tokio::time::sleep(USER_ENRICHMENT_INTERVAL).await;
+                users.prune();
+
+                let pending = users.take_pending(100);
+                if pending.is_empty() {
+                    continue;
+                }
+
+                let ids: Vec<&str> = pending.iter().map(|id| id.as_str()).collect();
+                let fetched = match users.fetch(&[], &ids).await {
+                    Ok(fetched) => fetched,
+                    Err(e) => {
+                        eprintln!("{}: Error fetching chatters from helix: {}", Utc::now(), e);
+                        users.requeue(pending);
+                        continue;
+                    }
+                };
+
+                for user in fetched {
+                    let uid = match parse_uid(&user.id) {
+                        Ok(uid) => uid,
+                        Err(_) => continue,
+                    };
+                    if let Err(e) = bot
+                        .db()
+                        .update_user_details(uid, &user.login, &user.display_name, user.created_at)
+                        .await
+                    {
+                        eprintln!("Error saving details of {}: {}", user.login, e);
+                    }
+                }
+            }
+        });
+    }
+
+    pub fn streams(&self) -> Arc<StreamTracker> {
+        Arc::clone(&self.streams)
+    }
+
+    pub fn reminders(&self) -> Arc<Reminders> {
+        Arc::clone(&self.reminders)
+    }
+
+    pub fn afk(&self) -> Arc<AfkTracker> {
+        Arc::clone(&self.afk)
+    }
+
+    pub fn timers(&self) -> Arc<TimerTracker> {
+        Arc::clone(&self.timers)
+    }
+
+    pub fn markov(&self) -> Arc<Markov> {
+        Arc::clone(&self.markov)
+    }
+
+    pub fn emote_stats(&self) -> Arc<EmoteStats> {
+        Arc::clone(&self.emote_stats)
+    }
+
+    pub fn counters(&self) -> Arc<Counters> {
+        Arc::clone(&self.counters)
+    }
+
+    pub fn economy(&self) -> Arc<Economy> {
+        Arc::clone(&self.economy)
+    }
+
+    pub fn polls(&self) -> Arc<Polls> {
+        Arc::clone(&self.polls)
+    }
+
+    pub fn games(&self) -> Arc<Games> {
+        Arc::clone(&self.games)
+    }
+
+    pub fn data_dir(&self) -> &DataDir {
+        &self.data_dir
+    }
+
     pub async fn run(bot_self: Arc<BorrowBot>) {
         let bot = Arc::clone(&bot_self);
         bot.messenger().sender_loop();
@@ -91,7 +274,19 @@ impl BorrowBot {
         let join_handle = tokio::spawn(async move {
             while let Some(raw_message) = bot.stream().lock().await.recv().await {
                 if let ServerMessage::Privmsg(msg) = raw_message {
-                    bot.logs().log_message(&msg).await;
+                    bot.api().users().learn(&msg);
+
+                    if let Err(e) = bot.logs().log_message(&msg).await {
+                        eprintln!("Error logging message in {}: {}", msg.channel_login, e);
+                    }
+                    emotestats::record_message(&bot, &msg);
+                    Markov::learn(&bot, &msg);
+                    Reminders::deliver_waiting(&bot, &msg);
+                    AfkTracker::on_message(&bot, &msg);
+                    bot.timers().count_line(&msg.channel_login.to_lowercase());
+                    Economy::on_message(&bot, &msg);
+                    Polls::on_message(&bot, &msg);
+                    Games::on_message(&bot, &msg).await;
 
                     if msg.message_text.starts_with("&") {
                         let bot = Arc::clone(&bot);
@@ -100,8 +295,23 @@ impl BorrowBot {
                         let commands = bot.commands();
 
                         tokio::spawn(async move {
-                            let user_context = Arc::new(db.get_user_or_insert(&msg).await);
-                            let command_response = commands.execute(bot, &user_context, &msg).await;
+                            let (user_context, command_response) = match db
+                                .get_user_or_insert(&msg)
+                                .await
+                            {
+                                Ok(user_context) => {
+                                    let command_response =
+                                        commands.execute(bot, &user_context, &msg).await;
+                                    (user_context, command_response)
+                                }
+                                Err(e) => {
+                                    eprintln!("Error looking up {}: {}", msg.sender.login, e);
+                                    (
+                                        UserContext::new(0, msg.sender.login.clone(), 0),
+                                        CommandResponse::new(e.user_message().to_owned(), false),
+                                    )
+                                }
+                            };
                             messenger
                                 .chat_response(&msg, &user_context, &command_response)
                                 .await;
@@ -125,6 +335,37 @@ impl BorrowBot {
         drop(current_channels_guard);
 
         bot_self.api().supinic().start_supinic_ping_loop().await;
+        Helix::start_token_refresh_loop(bot_self.api().helix());
+        Self::start_user_enrichment_loop(Arc::clone(&bot_self));
+        notifications::start_notifier(Arc::clone(&bot_self));
+        Emotes::start_refresh_loop(bot_self.api().emotes(), bot_self.current_channels());
+        EmoteStats::start(Arc::clone(&bot_self));
+        Reminders::start_scheduler(Arc::clone(&bot_self));
+        TimerTracker::start(Arc::clone(&bot_self));
+        Economy::start(Arc::clone(&bot_self));
+        Polls::start(Arc::clone(&bot_self));
+        Games::start(Arc::clone(&bot_self));
+        let stream_poll_interval = match (
+            bot_self.api().eventsub_receiver(),
+            bot_self.api().eventsub_manager(),
+        ) {
+            (Some(receiver), Some(manager)) => {
+                StreamTracker::follow_events(bot_self.streams(), receiver.subscribe());
+                let routes = Arc::new(ProfileRoutes::new(Arc::clone(&bot_self)));
+                match EventSubReceiver::serve(Arc::clone(&receiver), Some(routes)) {
+                    Ok(addr) => eprintln!("{}: Receiving EventSub on {}", Utc::now(), addr),
+                    Err(e) => eprintln!("{}: Couldn't start EventSub server: {}", Utc::now(), e),
+                }
+                EventSubManager::start_reconcile_loop(manager, bot_self.current_channels());
+                EVENTSUB_STREAM_POLL_INTERVAL
+            }
+            _ => STREAM_POLL_INTERVAL,
+        };
+        StreamTracker::start_polling(
+            bot_self.streams(),
+            bot_self.current_channels(),
+            stream_poll_interval,
+        );
 
         join_handle.await.unwrap();
     }
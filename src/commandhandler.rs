@@ -1,30 +1,76 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use twitch_irc::message::PrivmsgMessage;
 
 use crate::bot::BorrowBot;
-use crate::commands::Command;
-use crate::database::DBController;
+use crate::commands::{self, Command};
+use crate::database::{DBError, Database};
+use crate::error::BotError;
 use crate::types::{CommandResponse, PermissionLevel, UserContext};
 
+// seconds a chatter waits between showing the same counter
+const COUNTER_COOLDOWN: u64 = 5;
+
 pub struct CommandHandler {
     pub command_list: HashMap<String, Command>,
     user_cooldowns: Arc<RwLock<Vec<(i32, String)>>>,
+
+    // (channel, command) pairs that stay quiet
+    disabled: RwLock<HashSet<(String, String)>>,
+    db: Arc<dyn Database>,
 }
 
 impl CommandHandler {
-    pub async fn new(db: Arc<DBController>) -> Self {
-        let command_list = db.get_current_commands().await;
+    pub async fn new(db: Arc<dyn Database>) -> Self {
+        let command_list = db
+            .get_current_commands()
+            .await
+            .expect("Couldn't load the command list");
         let user_cooldowns = Arc::new(RwLock::new(Vec::new()));
+        let disabled = db
+            .get_disabled_commands()
+            .await
+            .expect("Couldn't load the disabled commands");
 
         CommandHandler {
             command_list,
             user_cooldowns,
+            disabled: RwLock::new(disabled),
+            db,
         }
     }
 
+    pub fn is_disabled(&self, channel: &str, command: &str) -> bool {
+        self.disabled
+            .read()
+            .unwrap()
+            .contains(&(channel.to_owned(), command.to_owned()))
+    }
+
+    // false if the command already was in that state
+    pub async fn set_disabled(
+        &self,
+        channel: &str,
+        command: &str,
+        disabled: bool,
+    ) -> Result<bool, DBError> {
+        let changed = self
+            .db
+            .set_command_disabled(channel, command, disabled)
+            .await?;
+
+        let key = (channel.to_owned(), command.to_owned());
+        if disabled {
+            self.disabled.write().unwrap().insert(key);
+        } else {
+            self.disabled.write().unwrap().remove(&key);
+        }
+
+        Ok(changed)
+    }
+
     pub async fn execute(
         &self,
         bot: Arc<BorrowBot>,
@@ -35,6 +81,10 @@ impl CommandHandler {
         let command_name = &split.next().unwrap()[1..];
 
         if let Some(command) = self.command_list.get(command_name) {
+            if self.is_disabled(&msg.channel_login.to_lowercase(), command_name) {
+                return CommandResponse::new("".to_owned(), false);
+            }
+
             if !user_context
                 .permissions
                 .satisfies(command.permission_needed)
@@ -54,9 +104,16 @@ impl CommandHandler {
                 .unwrap()
                 .contains(&(user_context.uid, command_name.to_owned()))
             {
-                let response = command
-                    .lookup_and_run(command_name, msg, split, bot, user_context)
-                    .await;
+                let response = match command
+                    .lookup_and_run(command_name, msg, split, Arc::clone(&bot), user_context)
+                    .await
+                {
+                    Ok(response) => response,
+                    Err(e) => {
+                        Self::log_command_error(&bot, command_name, user_context, msg, &e).await;
+                        CommandResponse::new(e.user_message().to_owned(), false)
+                    }
+                };
 
                 if user_context.permissions != PermissionLevel::Superuser {
                     self.start_user_cooldown(
@@ -67,13 +124,53 @@ impl CommandHandler {
                     .await;
                 }
 
-                return response;
+                response
             } else {
                 CommandResponse {
                     response: "".to_owned(),
                     questionable_output: false,
                 }
             }
+        } else if bot
+            .counters()
+            .exists(&msg.channel_login.to_lowercase(), command_name)
+        {
+            // counters are commands of their own, only chatters showing them get a cooldown
+            if self
+                .user_cooldowns
+                .read()
+                .unwrap()
+                .contains(&(user_context.uid, command_name.to_owned()))
+            {
+                return CommandResponse::new("".to_owned(), false);
+            }
+
+            let response = match commands::run_counter(
+                command_name,
+                msg,
+                split,
+                Arc::clone(&bot),
+                user_context,
+            )
+            .await
+            {
+                Ok(response) => response,
+                Err(e) => {
+                    Self::log_command_error(&bot, command_name, user_context, msg, &e).await;
+                    CommandResponse::new(e.user_message().to_owned(), false)
+                }
+            };
+
+            if user_context.permissions == PermissionLevel::User {
+                self.start_user_cooldown(
+                    user_context.uid,
+                    command_name.to_owned(),
+                    COUNTER_COOLDOWN,
+                )
+                .await;
+            }
+
+            response
         } else {
             CommandResponse {
                 response: "".to_owned(),
@@ -82,6 +179,31 @@ impl CommandHandler {
         }
     }
 
+    async fn log_command_error(
+        bot: &BorrowBot,
+        command_name: &str,
+        user_context: &UserContext,
+        msg: &PrivmsgMessage,
+        error: &BotError,
+    ) {
+        eprintln!(
+            "{}: &{} failed in #{} for {}: {}",
+            chrono::Utc::now(),
+            command_name,
+            msg.channel_login,
+            user_context.login,
+            error
+        );
+
+        if let Err(e) = bot
+            .logs()
+            .log_error(command_name, &msg.channel_login, &user_context.login, error)
+            .await
+        {
+            eprintln!("Couldn't write to the error log: {}", e);
+        }
+    }
+
     // pushes the user's id and the command as a tuple into a vector within a RwLock, once the
     // cooldown period is up for the command we will remove that tuple from the vector
     // to indicate that user is not longer on cooldown for that command
@@ -1,11 +1,33 @@
 use std::collections::HashSet;
 use std::sync::Arc;
 
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use reqwest::StatusCode;
 use twitch_irc::message::PrivmsgMessage;
 
+use crate::afk::{AfkKind, AfkStatus};
+use crate::api::emotes::Emote;
+use crate::api::helix::HelixError;
+use crate::api::usercache::CachedUser;
 use crate::bot::BorrowBot;
-use crate::types::{CommandResponse, PermissionLevel, UserContext};
+use crate::counters::{Counter, CounterChange, DEFAULT_TEMPLATE, MAX_COUNTER_NAME_LENGTH};
+use crate::error::BotError;
+use crate::games::emoteguess::EmoteGuess;
+use crate::games::hangman::{self, Hangman, MAX_MISSES};
+use crate::games::trivia::{self, TriviaGame, DEFAULT_ROUNDS, MAX_ROUNDS};
+use crate::games::Game;
+use crate::markov;
+use crate::notifications::NotificationKind;
+use crate::points::{parse_amount, spin_slots, Duel, Economy, DUEL_TIMEOUT};
+use crate::polls::{refund_bets, Bet, Poll, MAX_POLL_OPTIONS};
+use crate::profiles::UserProfile;
+use crate::quotes::{self, Quote, MAX_QUOTE_LENGTH};
+use crate::reminders::{parse_duration, Reminder};
+use crate::streams::StreamSession;
+use crate::timers::{Timer, TimerCondition, TimerSchedule};
+use crate::types::{parse_uid, CommandResponse, PermissionLevel, UserContext};
 
 pub struct Command {
     pub about: String,
@@ -31,7 +53,7 @@ impl Command {
         params: std::str::Split<'_, char>,
         source_bot: Arc<BorrowBot>,
         user_context: &UserContext,
-    ) -> CommandResponse {
+    ) -> Result<CommandResponse, BotError> {
         match source_function {
             "help" => help(params, source_bot, user_context).await,
             "ping" => ping(params, source_bot, user_context).await,
@@ -45,7 +67,45 @@ impl Command {
             "say" => say(params, source_bot, user_context).await,
             "lastmessage" => lastmessage(privmsg, params, source_bot, user_context).await,
             "randmessage" => randmessage(privmsg, params, source_bot, user_context).await,
-            _ => CommandResponse::new("".to_owned(), false),
+            "title" => title(privmsg, params, source_bot, user_context).await,
+            "game" => game(privmsg, params, source_bot, user_context).await,
+            "uptime" => uptime(privmsg, params, source_bot, user_context).await,
+            "lastseen" => lastseen(params, source_bot, user_context).await,
+            "notify" => notify(privmsg, params, source_bot, user_context).await,
+            "unnotify" => unnotify(privmsg, params, source_bot, user_context).await,
+            "accountage" => accountage(params, source_bot, user_context).await,
+            "followage" => followage(privmsg, params, source_bot, user_context).await,
+            "user" => user(params, source_bot, user_context).await,
+            "emote" => emote(privmsg, params, source_bot, user_context).await,
+            "randomemote" => randomemote(privmsg, params, source_bot, user_context).await,
+            "emotecount" => emotecount(privmsg, params, source_bot, user_context).await,
+            "topemotes" => topemotes(privmsg, params, source_bot, user_context).await,
+            "unusedemotes" => unusedemotes(privmsg, params, source_bot, user_context).await,
+            "remind" => remind(privmsg, params, source_bot, user_context).await,
+            "unremind" => unremind(params, source_bot, user_context).await,
+            "afk" | "gn" | "brb" => afk(source_function, params, source_bot, user_context).await,
+            "disable" => toggle(privmsg, params, source_bot, true).await,
+            "enable" => toggle(privmsg, params, source_bot, false).await,
+            "timer" => timer(privmsg, params, source_bot, user_context).await,
+            "markov" => markov(privmsg, params, source_bot, user_context).await,
+            "addquote" => addquote(privmsg, params, source_bot, user_context).await,
+            "quote" => quote(privmsg, params, source_bot, user_context).await,
+            "delquote" => delquote(privmsg, params, source_bot, user_context).await,
+            "quotes" => quotes(privmsg, params, source_bot, user_context).await,
+            "counter" => counter(privmsg, params, source_bot, user_context).await,
+            "points" => points(privmsg, params, source_bot, user_context).await,
+            "give" => give(privmsg, params, source_bot, user_context).await,
+            "roulette" => roulette(privmsg, params, source_bot, user_context).await,
+            "slots" => slots(privmsg, params, source_bot, user_context).await,
+            "duel" => duel(privmsg, params, source_bot, user_context).await,
+            "toppoints" => toppoints(privmsg, params, source_bot, user_context).await,
+            "resetpoints" => resetpoints(privmsg, params, source_bot, user_context).await,
+            "poll" => poll(privmsg, params, source_bot, user_context).await,
+            "bet" => bet(privmsg, params, source_bot, user_context).await,
+            "trivia" => trivia(privmsg, params, source_bot, user_context).await,
+            "hangman" => hangman(privmsg, params, source_bot, user_context).await,
+            "emoteguess" => emoteguess(privmsg, params, source_bot, user_context).await,
+            _ => Ok(CommandResponse::new("".to_owned(), false)),
         }
     }
 }
@@ -54,7 +114,7 @@ async fn help(
     mut params: std::str::Split<'_, char>,
     bot: Arc<BorrowBot>,
     _: &UserContext,
-) -> CommandResponse {
+) -> Result<CommandResponse, BotError> {
     let target_command = params.next().unwrap_or("").to_lowercase();
     let command_list = &bot.commands().command_list;
 
@@ -67,7 +127,7 @@ async fn help(
         }
     } else {
         let mut response = String::from("List of available commands: ");
-        for (command_name, _) in command_list {
+        for command_name in command_list.keys() {
             response.push_str(command_name);
             response.push_str(", ");
         }
@@ -76,17 +136,17 @@ async fn help(
         response
     };
 
-    CommandResponse {
+    Ok(CommandResponse {
         response,
         questionable_output: false,
-    }
+    })
 }
 
 async fn ping(
     _: std::str::Split<'_, char>,
     bot: Arc<BorrowBot>,
     _: &UserContext,
-) -> CommandResponse {
+) -> Result<CommandResponse, BotError> {
     let uptime = chrono::Utc::now() - bot.start_time;
 
     let days = uptime.num_days();
@@ -94,58 +154,76 @@ async fn ping(
     let minutes = uptime.num_minutes() - ((days * 1440) + (hours * 60));
     let seconds = uptime.num_seconds() - ((days * 86400) + (hours * 3600) + (minutes * 60));
 
-    let response = format!(
+    let mut response = format!(
         "Pong! Uptime: {}d, {}h, {}m, {}s",
         days, hours, minutes, seconds
     );
+    let unreachable: Vec<&str> = [
+        ("database", bot.db().is_healthy()),
+        ("logs", bot.logs().is_healthy()),
+    ]
+    .iter()
+    .filter(|(_, healthy)| !healthy)
+    .map(|(name, _)| *name)
+    .collect();
+    if !unreachable.is_empty() {
+        response.push_str(&format!(
+            " | can't reach the {}",
+            unreachable.join(" or the ")
+        ));
+    }
 
-    CommandResponse {
+    Ok(CommandResponse {
         response,
         questionable_output: false,
-    }
+    })
 }
 
-async fn bot(_: std::str::Split<'_, char>, _: Arc<BorrowBot>, _: &UserContext) -> CommandResponse {
+async fn bot(
+    _: std::str::Split<'_, char>,
+    _: Arc<BorrowBot>,
+    _: &UserContext,
+) -> Result<CommandResponse, BotError> {
     let response = String::from(
         "Bot made my 1xelerate. \
         Written in Rust with Tokio, Postgresql, and Rander's Twitch IRC library.",
     );
 
-    CommandResponse {
+    Ok(CommandResponse {
         response,
         questionable_output: false,
-    }
+    })
 }
 
 async fn greeting(
     _: std::str::Split<'_, char>,
     _: Arc<BorrowBot>,
     user_context: &UserContext,
-) -> CommandResponse {
+) -> Result<CommandResponse, BotError> {
     let response = match user_context.permissions {
         PermissionLevel::Superuser => "Greetings superuser".to_owned(),
         PermissionLevel::Moderator => "Hello moderator".to_owned(),
         PermissionLevel::User => "What's good".to_owned(),
     };
 
-    CommandResponse {
+    Ok(CommandResponse {
         response,
         questionable_output: false,
-    }
+    })
 }
 
 async fn expensive(
     _: std::str::Split<'_, char>,
     _: Arc<BorrowBot>,
     _: &UserContext,
-) -> CommandResponse {
+) -> Result<CommandResponse, BotError> {
     tokio::time::sleep(std::time::Duration::from_secs(5)).await;
     let response = "Test expensive command finished".to_owned();
 
-    CommandResponse {
+    Ok(CommandResponse {
         response,
         questionable_output: false,
-    }
+    })
 }
 
 // raw manipulation of data columns and value inside postgres database
@@ -154,106 +232,84 @@ async fn setpermissions(
     mut params: std::str::Split<'_, char>,
     bot: Arc<BorrowBot>,
     _: &UserContext,
-) -> CommandResponse {
+) -> Result<CommandResponse, BotError> {
     let target_user = params.next().unwrap_or("").to_lowercase();
     if target_user.is_empty() {
-        return CommandResponse {
+        return Ok(CommandResponse {
             response: "Please provide a username after the set command!".to_owned(),
             questionable_output: false,
-        };
+        });
     }
 
     let target_value: i32 = params.next().unwrap_or("").parse().unwrap_or(-1);
     if target_value == -1 {
-        return CommandResponse {
+        return Ok(CommandResponse {
             response: "Error parsing value to be set, please give an integer after the username!"
                 .to_owned(),
             questionable_output: false,
-        };
+        });
     }
 
-    let response = match bot
+    let rows = bot
         .db()
-        .try_set_column_by_name(
-            &target_user.to_owned(),
-            &"permissions".to_owned(),
-            &target_value,
-        )
-        .await
-    {
-        Ok(rows) => {
-            if rows == 0 {
-                return CommandResponse {
-                    response: "Sorry, that user wasn't found in my database!".to_owned(),
-                    questionable_output: false,
-                };
-            }
+        .set_permissions_by_name(&target_user, target_value)
+        .await?;
+    if rows == 0 {
+        return Ok(CommandResponse {
+            response: "Sorry, that user wasn't found in my database!".to_owned(),
+            questionable_output: false,
+        });
+    }
 
-            format!(
-                "Succesfully set {}'s {} column to {}",
-                &target_user, "permissions", &target_value
-            )
-        }
-        Err(err) => {
-            println!("{:?}", err);
-            format!(
-                "Error setting column {} to value {}",
-                "permissions", &target_value
-            )
-        }
-    };
+    let response = format!(
+        "Succesfully set {}'s {} column to {}",
+        &target_user, "permissions", &target_value
+    );
 
-    CommandResponse {
+    Ok(CommandResponse {
         response,
         questionable_output: false,
-    }
+    })
 }
 
 async fn join(
     mut params: std::str::Split<'_, char>,
     bot: Arc<BorrowBot>,
     _: &UserContext,
-) -> CommandResponse {
+) -> Result<CommandResponse, BotError> {
     let target_channel = params.next().unwrap_or("").to_lowercase();
     if target_channel.is_empty() {
-        return CommandResponse {
+        return Ok(CommandResponse {
             response: "Please provide a channel to join".to_owned(),
             questionable_output: false,
-        };
+        });
     }
 
-    if let Ok(resp) = bot
+    if bot
         .api()
-        .helix()
-        .get_user_by_login(&target_channel[..])
-        .await
+        .users()
+        .get_by_login(&target_channel[..])
+        .await?
+        .is_none()
     {
-        if let None = resp {
-            return CommandResponse {
-                response: "Sorry, I couldn't find that channel".to_owned(),
-                questionable_output: false,
-            };
-        }
-    } else {
-        return CommandResponse {
-            response: "Unable to verify if that channel exists, join aborted; Twitch API error"
-                .to_owned(),
+        return Ok(CommandResponse {
+            response: "Sorry, I couldn't find that channel".to_owned(),
             questionable_output: false,
-        };
+        });
     }
 
     bot.db()
         .modify_or_insert_joined_value(&target_channel, true)
-        .await;
+        .await?;
 
     let current_channels_mutex = bot.current_channels();
     let mut current_channels_guard = current_channels_mutex.lock().await;
 
     if (*current_channels_guard).contains(&target_channel) {
-        return CommandResponse {
+        return Ok(CommandResponse {
             response: "I've already joined that channel".to_owned(),
             questionable_output: false,
-        };
+        });
     }
 
     (*current_channels_guard).insert(target_channel.clone());
@@ -261,6 +317,9 @@ async fn join(
         .client()
         .set_wanted_channels((*current_channels_guard).clone());
     drop(current_channels_guard);
+    if let Some(manager) = bot.api().eventsub_manager() {
+        manager.request_reconcile();
+    }
 
     let mut new_joined_channel = HashSet::new();
     new_joined_channel.insert(target_channel.clone());
@@ -268,38 +327,38 @@ async fn join(
         .send_join_messages(&new_joined_channel)
         .await;
 
-    CommandResponse {
+    Ok(CommandResponse {
         response: "Succesfully joined channel".to_owned(),
         questionable_output: false,
-    }
+    })
 }
 
 async fn leave(
     mut params: std::str::Split<'_, char>,
     bot: Arc<BorrowBot>,
     _: &UserContext,
-) -> CommandResponse {
+) -> Result<CommandResponse, BotError> {
     // TODO: VERIFY IF CHANNEL EXISTS?
     let target_channel = params.next().unwrap_or("").to_lowercase();
     if target_channel.is_empty() {
-        return CommandResponse {
+        return Ok(CommandResponse {
             response: "Please provide a channel to leave".to_owned(),
             questionable_output: false,
-        };
+        });
     }
 
     bot.db()
         .modify_or_insert_joined_value(&target_channel, false)
-        .await;
+        .await?;
 
     let current_channels_mutex = bot.current_channels();
     let mut current_channels_guard = current_channels_mutex.lock().await;
 
     if !(*current_channels_guard).contains(&target_channel) {
-        return CommandResponse {
+        return Ok(CommandResponse {
             response: "I'm not currently in that channel!".to_owned(),
             questionable_output: false,
-        };
+        });
     }
 
     (*current_channels_guard).remove(&target_channel);
@@ -307,59 +366,57 @@ async fn leave(
         .client()
         .set_wanted_channels((*current_channels_guard).clone());
     drop(current_channels_guard);
+    if let Some(manager) = bot.api().eventsub_manager() {
+        manager.request_reconcile();
+    }
 
     // leave message?
 
-    CommandResponse {
+    Ok(CommandResponse {
         response: "Succesfully left channel".to_owned(),
         questionable_output: false,
-    }
+    })
 }
 
 async fn uid(
     mut params: std::str::Split<'_, char>,
     bot: Arc<BorrowBot>,
     user_context: &UserContext,
-) -> CommandResponse {
+) -> Result<CommandResponse, BotError> {
     let target_user = params.next().unwrap_or("").to_lowercase();
     if target_user.is_empty() {
-        return CommandResponse {
+        return Ok(CommandResponse {
             response: format!("{}", user_context.uid),
             questionable_output: false,
-        };
+        });
     }
 
-    let response = if let Ok(resp) = bot.api().helix().get_user_by_login(&target_user[..]).await {
-        if let Some(user) = resp {
-            format!("{}", user.id)
-        } else {
-            "Sorry, I couldn't find user".to_owned()
-        }
-    } else {
-        "Unable to fetch uid; Twitch API error".to_owned()
+    let response = match bot.api().users().get_by_login(&target_user[..]).await? {
+        Some(user) => user.id.to_string(),
+        None => "Sorry, I couldn't find user".to_owned(),
     };
 
-    CommandResponse {
+    Ok(CommandResponse {
         response,
         questionable_output: false,
-    }
+    })
 }
 
 async fn say(
     params: std::str::Split<'_, char>,
     _: Arc<BorrowBot>,
     _: &UserContext,
-) -> CommandResponse {
+) -> Result<CommandResponse, BotError> {
     let mut phrase = String::new();
     for word in params {
         phrase.push_str(word);
         phrase.push(' ');
     }
 
-    CommandResponse {
+    Ok(CommandResponse {
         response: phrase,
         questionable_output: true,
-    }
+    })
 }
 
 async fn lastmessage(
@@ -367,7 +424,7 @@ async fn lastmessage(
     mut params: std::str::Split<'_, char>,
     bot: Arc<BorrowBot>,
     user: &UserContext,
-) -> CommandResponse {
+) -> Result<CommandResponse, BotError> {
     let mut target_user = params.next().unwrap_or("").to_lowercase();
     if target_user.is_empty() {
         target_user = user.login.clone();
@@ -380,8 +437,8 @@ async fn lastmessage(
 
     if let Some((timestamp, message)) = bot
         .logs()
-        .get_last_message_from_username(&target_channel, &target_user)
-        .await
+        .get_last_message_from_username(&target_channel, &target_user, 0)
+        .await?
     {
         let naive = NaiveDateTime::from_timestamp(timestamp, 0);
         let datetime: DateTime<Utc> = DateTime::from_utc(naive, Utc);
@@ -391,16 +448,16 @@ async fn lastmessage(
             target_user,
             message
         );
-        CommandResponse {
+        Ok(CommandResponse {
             response: message,
             questionable_output: true,
-        }
+        })
     } else {
-        CommandResponse {
+        Ok(CommandResponse {
             response: "Sorry, I didn't find any logs for that user in the selected channel!"
                 .to_owned(),
             questionable_output: false,
-        }
+        })
     }
 }
 
@@ -409,7 +466,7 @@ async fn randmessage(
     mut params: std::str::Split<'_, char>,
     bot: Arc<BorrowBot>,
     _: &UserContext,
-) -> CommandResponse {
+) -> Result<CommandResponse, BotError> {
     let target_user = params.next().unwrap_or("").to_lowercase();
 
     let mut target_channel = params.next().unwrap_or("").to_lowercase();
@@ -419,7 +476,7 @@ async fn randmessage(
 
     if target_user.is_empty() || target_user == "_" {
         if let Some((timestamp, username, message)) =
-            bot.logs().get_random_message(&target_channel).await
+            bot.logs().get_random_message(&target_channel).await?
         {
             let naive = NaiveDateTime::from_timestamp(timestamp, 0);
             let datetime: DateTime<Utc> = DateTime::from_utc(naive, Utc);
@@ -429,23 +486,23 @@ async fn randmessage(
                 username,
                 message
             );
-            CommandResponse {
+            Ok(CommandResponse {
                 response: message,
                 questionable_output: true,
-            }
+            })
         } else {
-            CommandResponse {
+            Ok(CommandResponse {
                 response:
                     "Sorry, something went wrong retrieving a random log from the current channel :("
                         .to_owned(),
                 questionable_output: false,
-            }
+            })
         }
     } else {
         if let Some((timestamp, message)) = bot
             .logs()
             .get_random_message_from_username(&target_channel, &target_user)
-            .await
+            .await?
         {
             let naive = NaiveDateTime::from_timestamp(timestamp, 0);
             let datetime: DateTime<Utc> = DateTime::from_utc(naive, Utc);
@@ -455,16 +512,2510 @@ async fn randmessage(
                 target_user,
                 message
             );
-            CommandResponse {
+            Ok(CommandResponse {
                 response: message,
                 questionable_output: true,
-            }
+            })
         } else {
-            CommandResponse {
+            Ok(CommandResponse {
                 response: "Sorry, I don't have logs of that user in the channel specified"
                     .to_owned(),
                 questionable_output: false,
+            })
+        }
+    }
+}
+
+// short duration for chat like "1d 4h 12m", seconds are only shown under a minute
+pub fn format_duration(duration: chrono::Duration) -> String {
+    let days = duration.num_days();
+    let hours = duration.num_hours() - (days * 24);
+    let minutes = duration.num_minutes() - (duration.num_hours() * 60);
+
+    let mut parts = Vec::new();
+    if days > 0 {
+        parts.push(format!("{}d", days));
+    }
+    if hours > 0 {
+        parts.push(format!("{}h", hours));
+    }
+    if minutes > 0 {
+        parts.push(format!("{}m", minutes));
+    }
+    if parts.is_empty() {
+        parts.push(format!("{}s", duration.num_seconds().max(0)));
+    }
+
+    parts.join(" ")
+}
+
+// the channel given as the first parameter, or the one the command was used in
+fn target_channel(privmsg: &PrivmsgMessage, params: &mut std::str::Split<'_, char>) -> String {
+    let target_channel = params.next().unwrap_or("").to_lowercase();
+    if target_channel.is_empty() {
+        privmsg.channel_login.to_lowercase()
+    } else {
+        target_channel
+    }
+}
+
+// (title, category, live) of a channel, joined channels that are live don't need a helix call
+async fn channel_status(
+    bot: &BorrowBot,
+    channel: &str,
+) -> Result<Option<(String, String, bool)>, BotError> {
+    if let Some(session) = bot.streams().live_session(channel).await {
+        return Ok(Some((session.title, session.category, true)));
+    }
+
+    let user = match bot.api().users().get_by_login(channel).await? {
+        Some(user) => user,
+        None => return Ok(None),
+    };
+
+    Ok(bot
+        .api()
+        .helix()
+        .get_channel_information(&user.id)
+        .await?
+        .map(|info| (info.title, info.game_name, false)))
+}
+
+// the channel's current session, from the tracker if it's joined and from helix otherwise
+async fn live_session(bot: &BorrowBot, channel: &str) -> Result<Option<StreamSession>, BotError> {
+    if bot.current_channels().lock().await.contains(channel) {
+        return Ok(bot.streams().live_session(channel).await);
+    }
+
+    Ok(bot
+        .api()
+        .helix()
+        .get_streams(&[channel])
+        .await?
+        .into_iter()
+        .find(|stream| stream.type_field == "live")
+        .map(|stream| StreamSession {
+            id: 0,
+            channel: channel.to_owned(),
+            stream_id: stream.id,
+            started_at: stream.started_at,
+            ended_at: None,
+            title: stream.title,
+            category: stream.game_name,
+        }))
+}
+
+async fn title(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let target_channel = target_channel(privmsg, &mut params);
+
+    match channel_status(&bot, &target_channel).await? {
+        Some((title, _, _)) => Ok(CommandResponse {
+            response: format!("{}'s title: {}", target_channel, title),
+            questionable_output: true,
+        }),
+        None => Ok(CommandResponse {
+            response: "Sorry, I couldn't find that channel".to_owned(),
+            questionable_output: false,
+        }),
+    }
+}
+
+async fn game(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let target_channel = target_channel(privmsg, &mut params);
+
+    let response = match channel_status(&bot, &target_channel).await? {
+        Some((_, category, _)) if category.is_empty() => {
+            format!("{} doesn't have a category set", target_channel)
+        }
+        Some((_, category, true)) => format!("{} is streaming {}", target_channel, category),
+        Some((_, category, false)) => {
+            format!(
+                "{} is offline, their category is {}",
+                target_channel, category
+            )
+        }
+        None => "Sorry, I couldn't find that channel".to_owned(),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn uptime(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let target_channel = target_channel(privmsg, &mut params);
+
+    let response = match live_session(&bot, &target_channel).await? {
+        Some(session) => format!(
+            "{} has been live for {}",
+            target_channel,
+            format_duration(Utc::now() - session.started_at)
+        ),
+        None => match bot.db().get_last_stream_session(&target_channel).await? {
+            Some(StreamSession {
+                ended_at: Some(ended_at),
+                ..
+            }) => format!(
+                "{} is offline, their last stream ended {} ago",
+                target_channel,
+                format_duration(Utc::now() - ended_at)
+            ),
+            _ => format!("{} is offline", target_channel),
+        },
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn lastseen(
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let kind = params.next().unwrap_or("").to_lowercase();
+    let target_channel = params.next().unwrap_or("").to_lowercase();
+    if kind != "live" || target_channel.is_empty() {
+        return Ok(CommandResponse {
+            response: "Usage: &lastseen live <channel>".to_owned(),
+            questionable_output: false,
+        });
+    }
+
+    let response = match live_session(&bot, &target_channel).await? {
+        Some(session) => format!(
+            "{} is live right now, for {} so far",
+            target_channel,
+            format_duration(Utc::now() - session.started_at)
+        ),
+        None => match bot.db().get_last_stream_session(&target_channel).await? {
+            Some(StreamSession {
+                started_at,
+                ended_at: Some(ended_at),
+                category,
+                ..
+            }) => format!(
+                "{} was last live {} ago, streaming {} for {}",
+                target_channel,
+                format_duration(Utc::now() - ended_at),
+                category,
+                format_duration(ended_at - started_at)
+            ),
+            _ => format!("I haven't seen {} live yet", target_channel),
+        },
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn notify(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let kind = match NotificationKind::parse(&params.next().unwrap_or("").to_lowercase()) {
+        Some(kind) => kind,
+        None => {
+            return Ok(CommandResponse {
+                response: "Usage: &notify <live|title>".to_owned(),
+                questionable_output: false,
+            })
+        }
+    };
+
+    let channel = privmsg.channel_login.to_lowercase();
+    let added = bot
+        .db()
+        .add_notification(&channel, user_context.uid, kind)
+        .await?;
+
+    let response = match (added, kind) {
+        (false, _) => "You're already getting those pings".to_owned(),
+        (true, NotificationKind::Live) => format!("I'll ping you when {} goes live", channel),
+        (true, NotificationKind::Title) => format!(
+            "I'll ping you when {} changes the title or category",
+            channel
+        ),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn unnotify(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let kind = params.next().unwrap_or("").to_lowercase();
+    let kind = if kind.is_empty() {
+        None
+    } else {
+        match NotificationKind::parse(&kind) {
+            Some(kind) => Some(kind),
+            None => {
+                return Ok(CommandResponse {
+                    response: "Usage: &unnotify [live|title]".to_owned(),
+                    questionable_output: false,
+                })
             }
         }
+    };
+
+    let removed = bot
+        .db()
+        .remove_notifications(
+            &privmsg.channel_login.to_lowercase(),
+            user_context.uid,
+            kind,
+        )
+        .await?;
+
+    let response = if removed == 0 {
+        "You weren't getting any of those pings".to_owned()
+    } else {
+        "Okay, no more pings".to_owned()
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+// the same time of day `months` calendar months later, clamped to the end of shorter months
+fn add_months(date: DateTime<Utc>, months: i32) -> DateTime<Utc> {
+    let total = date.year() * 12 + date.month0() as i32 + months;
+    let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+    let day = (28..=date.day())
+        .rev()
+        .find(|day| NaiveDate::from_ymd_opt(year, month, *day).is_some())
+        .unwrap_or_else(|| date.day());
+
+    DateTime::from_utc(
+        NaiveDate::from_ymd(year, month, day).and_time(date.time()),
+        Utc,
+    )
+}
+
+// long duration for chat like "3 years, 2 months", only the two largest units are shown
+fn human_duration(from: DateTime<Utc>, to: DateTime<Utc>) -> String {
+    let mut months = (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32;
+    while months > 0 && add_months(from, months) > to {
+        months -= 1;
+    }
+    let rest = to - add_months(from, months.max(0));
+
+    let units = [
+        (i64::from(months.max(0) / 12), "year"),
+        (i64::from(months.max(0) % 12), "month"),
+        (rest.num_days(), "day"),
+        (rest.num_hours() % 24, "hour"),
+        (rest.num_minutes() % 60, "minute"),
+    ];
+    let parts: Vec<String> = units
+        .iter()
+        .skip_while(|(amount, _)| *amount <= 0)
+        .take(2)
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(amount, unit)| match amount {
+            1 => format!("1 {}", unit),
+            _ => format!("{} {}s", amount, unit),
+        })
+        .collect();
+
+    if parts.is_empty() {
+        "less than a minute".to_owned()
+    } else {
+        parts.join(", ")
+    }
+}
+
+// Explains why helix has nothing on a login. Twitch leaves banned and deleted accounts out
+// of its responses, so anyone we've seen before must be one of those
+async fn missing_user_response(bot: &BorrowBot, login: &str) -> Result<String, BotError> {
+    Ok(match bot.db().get_user_by_name(login).await? {
+        Some(_) => format!("{} seems to be banned or deleted", login),
+        None => format!("Sorry, I couldn't find user {}", login),
+    })
+}
+
+async fn accountage(
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let login = match params.next().unwrap_or("").to_lowercase() {
+        login if login.is_empty() => user_context.login.to_lowercase(),
+        login => login.trim_start_matches('@').to_owned(),
+    };
+
+    // users only seen in chat are cached without their creation date
+    let user = match bot.api().users().get_by_login(&login).await? {
+        Some(user) if user.created_at.is_none() => bot
+            .api()
+            .users()
+            .fetch(&[&login], &[])
+            .await?
+            .into_iter()
+            .next(),
+        user => user,
+    };
+
+    let response = match user {
+        Some(CachedUser {
+            login,
+            created_at: Some(created_at),
+            ..
+        }) => format!(
+            "{} created their account {} ago ({})",
+            login,
+            human_duration(created_at, Utc::now()),
+            created_at.format("%Y-%m-%d")
+        ),
+        _ => missing_user_response(&bot, &login).await?,
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn followage(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let login = match params.next().unwrap_or("").to_lowercase() {
+        login if login.is_empty() => user_context.login.to_lowercase(),
+        login => login.trim_start_matches('@').to_owned(),
+    };
+    let channel = target_channel(privmsg, &mut params);
+    let channel = channel.trim_start_matches('@');
+
+    let users = bot.api().users().get_by_logins(&[&login, channel]).await?;
+    let find = |login: &str| users.iter().find(|user| user.login == login);
+    let (user, broadcaster) = match (find(&login), find(channel)) {
+        (Some(user), Some(broadcaster)) => (user, broadcaster),
+        (None, _) => {
+            return Ok(CommandResponse {
+                response: missing_user_response(&bot, &login).await?,
+                questionable_output: false,
+            })
+        }
+        (_, None) => {
+            return Ok(CommandResponse {
+                response: missing_user_response(&bot, channel).await?,
+                questionable_output: false,
+            })
+        }
+    };
+
+    let response = match bot
+        .api()
+        .helix()
+        .get_channel_follower(&broadcaster.id, &user.id)
+        .await
+    {
+        Ok(Some(follower)) => format!(
+            "{} has been following {} for {} (since {})",
+            user.login,
+            broadcaster.login,
+            human_duration(follower.followed_at, Utc::now()),
+            follower.followed_at.format("%Y-%m-%d")
+        ),
+        Ok(None) => format!("{} isn't following {}", user.login, broadcaster.login),
+        // twitch only lists followers to the channel's moderators
+        Err(HelixError::Status(StatusCode::FORBIDDEN)) => format!(
+            "Sorry, I need to be a moderator in {} to see its followers",
+            broadcaster.login
+        ),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+// room left for the "@login, " the response gets prefixed with
+const MAX_RESPONSE_LENGTH: usize = 450;
+
+async fn user(
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let login = match params.next().unwrap_or("").to_lowercase() {
+        login if login.is_empty() => user_context.login.to_lowercase(),
+        login => login.trim_start_matches('@').to_owned(),
+    };
+
+    let profile = match UserProfile::lookup(&bot, &login).await? {
+        Some(profile) => profile,
+        None => {
+            return Ok(CommandResponse {
+                response: format!("Sorry, I couldn't find user {}", login),
+                questionable_output: false,
+            })
+        }
+    };
+
+    let mut response = profile.describe();
+    if response.chars().count() > MAX_RESPONSE_LENGTH {
+        response = response.chars().take(MAX_RESPONSE_LENGTH - 1).collect();
+        response.push('…');
+    }
+
+    // display names and descriptions are written by the user
+    Ok(CommandResponse {
+        response,
+        questionable_output: true,
+    })
+}
+
+// the channel's third party emotes followed by the global ones, None if the channel doesn't exist
+async fn channel_emotes(bot: &BorrowBot, channel: &str) -> Result<Option<Vec<Emote>>, BotError> {
+    let channel_id = match bot.api().users().get_by_login(channel).await? {
+        Some(user) => user.id,
+        None => return Ok(None),
+    };
+
+    let emotes = bot.api().emotes();
+    let joined = bot.current_channels().lock().await.contains(channel);
+    let mut available = if joined {
+        emotes.channel(channel, &channel_id).await?.to_vec()
+    } else {
+        emotes.lookup(channel, &channel_id).await?.to_vec()
+    };
+    available.extend(emotes.global().await?.iter().cloned());
+
+    Ok(Some(available))
+}
+
+async fn emote(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let name = params.next().unwrap_or("").to_owned();
+    if name.is_empty() {
+        return Ok(CommandResponse {
+            response: "Usage: &emote <name> [channel]".to_owned(),
+            questionable_output: false,
+        });
     }
+    let channel = target_channel(privmsg, &mut params);
+
+    let available = match channel_emotes(&bot, &channel).await? {
+        Some(available) => available,
+        None => {
+            return Ok(CommandResponse {
+                response: "Sorry, I couldn't find that channel".to_owned(),
+                questionable_output: false,
+            })
+        }
+    };
+
+    // an exact match wins, otherwise any capitalization will do
+    let mut matches: Vec<&Emote> = available.iter().filter(|e| e.name == name).collect();
+    if matches.is_empty() {
+        matches = available
+            .iter()
+            .filter(|e| e.name.to_lowercase() == name.to_lowercase())
+            .collect();
+    }
+    if matches.is_empty() {
+        return Ok(CommandResponse {
+            response: format!(
+                "{} doesn't have a 7TV, BTTV or FFZ emote called that",
+                channel
+            ),
+            questionable_output: false,
+        });
+    }
+
+    let response = matches
+        .iter()
+        .map(|emote| {
+            let scope = if emote.global {
+                format!("global {}", emote.provider)
+            } else {
+                format!("{} #{}", emote.provider, channel)
+            };
+            match &emote.owner {
+                Some(owner) => format!(
+                    "{} is a {} emote by {} {}",
+                    emote.name,
+                    scope,
+                    owner,
+                    emote.url()
+                ),
+                None => format!("{} is a {} emote {}", emote.name, scope, emote.url()),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" | ");
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: true,
+    })
+}
+
+async fn randomemote(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let channel = target_channel(privmsg, &mut params);
+    let available = match channel_emotes(&bot, &channel).await? {
+        Some(available) => available,
+        None => {
+            return Ok(CommandResponse {
+                response: "Sorry, I couldn't find that channel".to_owned(),
+                questionable_output: false,
+            })
+        }
+    };
+
+    // the channel's own emotes are more fun, globals are only picked if it has none
+    let channel_only: Vec<&Emote> = available.iter().filter(|e| !e.global).collect();
+    let pool: Vec<&Emote> = if channel_only.is_empty() {
+        available.iter().collect()
+    } else {
+        channel_only
+    };
+
+    let response = match pool.choose(&mut rand::thread_rng()) {
+        Some(emote) => format!("{} ({})", emote.name, emote.provider),
+        None => format!(
+            "Sorry, {} doesn't have any 7TV, BTTV or FFZ emotes",
+            channel
+        ),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: true,
+    })
+}
+
+async fn emotecount(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let emote = params.next().unwrap_or("");
+    if emote.is_empty() {
+        return Ok(CommandResponse {
+            response: "Usage: &emotecount <emote>".to_owned(),
+            questionable_output: false,
+        });
+    }
+
+    let channel = privmsg.channel_login.to_lowercase();
+    bot.emote_stats().flush().await?;
+    let response = match bot.logs().get_emote_count(&channel, emote).await? {
+        Some((1, last_used)) => format!(
+            "{} has been used once in #{}, on {}",
+            emote, channel, last_used
+        ),
+        Some((uses, last_used)) => format!(
+            "{} has been used {} times in #{}, last on {}",
+            emote, uses, channel, last_used
+        ),
+        None => format!("{} hasn't been used in #{} yet", emote, channel),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: true,
+    })
+}
+
+async fn topemotes(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let today = Utc::now().naive_utc().date();
+    let (since, period) = match params.next().unwrap_or("").to_lowercase().as_str() {
+        "" | "all" => (None, "of all time"),
+        "day" => (Some(today), "today"),
+        "week" => (Some(today - chrono::Duration::days(6)), "this week"),
+        "month" => (Some(today - chrono::Duration::days(29)), "this month"),
+        "year" => (Some(today - chrono::Duration::days(364)), "this year"),
+        _ => {
+            return Ok(CommandResponse {
+                response: "Usage: &topemotes [day|week|month|year|all]".to_owned(),
+                questionable_output: false,
+            })
+        }
+    };
+
+    let channel = privmsg.channel_login.to_lowercase();
+    bot.emote_stats().flush().await?;
+    let top = bot.logs().get_top_emotes(&channel, since, 10).await?;
+    let response = if top.is_empty() {
+        format!("Nobody has used any emotes in #{} {}", channel, period)
+    } else {
+        format!(
+            "Top emotes in #{} {}: {}",
+            channel,
+            period,
+            top.iter()
+                .map(|(emote, uses)| format!("{} ({})", emote, uses))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: true,
+    })
+}
+
+async fn unusedemotes(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let days = match params.next().unwrap_or("") {
+        "" => 30,
+        days => match days.parse::<i64>() {
+            Ok(days) if days > 0 => days,
+            _ => {
+                return Ok(CommandResponse {
+                    response: "Usage: &unusedemotes [days]".to_owned(),
+                    questionable_output: false,
+                })
+            }
+        },
+    };
+
+    let channel = privmsg.channel_login.to_lowercase();
+    let emotes = bot
+        .api()
+        .emotes()
+        .channel(&channel, &privmsg.channel_id)
+        .await?;
+    bot.emote_stats().flush().await?;
+    let last_used = bot.logs().get_emote_last_used(&channel).await?;
+
+    // emotes that never showed up at all count as unused too
+    let cutoff = Utc::now().naive_utc().date() - chrono::Duration::days(days);
+    let unused: Vec<String> = emotes
+        .iter()
+        .filter(|emote| {
+            last_used
+                .get(&emote.name)
+                .is_none_or(|last_used| *last_used < cutoff)
+        })
+        .map(|emote| format!("{} ({})", emote.name, emote.provider))
+        .collect();
+
+    if unused.is_empty() {
+        return Ok(CommandResponse {
+            response: format!(
+                "Every 7TV, BTTV and FFZ emote of #{} was used in the last {} days",
+                channel, days
+            ),
+            questionable_output: false,
+        });
+    }
+
+    let header = format!("{} unused for {} days:", unused.len(), days);
+    let mut response = header;
+    for (listed, emote) in unused.iter().enumerate() {
+        let remaining = unused.len() - listed;
+        let more = format!(" and {} more", remaining);
+        if response.chars().count() + emote.chars().count() + 2 + more.len() > MAX_RESPONSE_LENGTH {
+            response.push_str(&more);
+            break;
+        }
+        response.push_str(if listed == 0 { " " } else { ", " });
+        response.push_str(emote);
+    }
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: true,
+    })
+}
+
+// reminders a user can have waiting at once
+const MAX_PENDING_REMINDERS: i64 = 10;
+
+const MAX_REMINDER_LENGTH: usize = 400;
+
+async fn remind(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let usage = Ok(CommandResponse {
+        response: "Usage: &remind <user|me> [in <time>] <message>".to_owned(),
+        questionable_output: false,
+    });
+
+    let target = params
+        .next()
+        .unwrap_or("")
+        .trim_start_matches('@')
+        .to_lowercase();
+    let mut words: Vec<&str> = params.filter(|word| !word.is_empty()).collect();
+
+    // "in" only starts a delay if a duration follows, "&remind bob in the kitchen" is a message
+    let delay = match words.as_slice() {
+        ["in", time, _, ..] => parse_duration(time),
+        _ => None,
+    };
+    if delay.is_some() {
+        words.drain(..2);
+    }
+    let message = words.join(" ");
+
+    let own_login = user_context.login.to_lowercase();
+    let to_self = target == "me" || target == own_login;
+    if target.is_empty() || message.is_empty() || (to_self && delay.is_none()) {
+        return usage;
+    }
+    if delay.is_some_and(|delay| delay > chrono::Duration::days(365)) {
+        return Ok(CommandResponse {
+            response: "I can't remind anyone more than a year ahead".to_owned(),
+            questionable_output: false,
+        });
+    }
+    if message.chars().count() > MAX_REMINDER_LENGTH {
+        return Ok(CommandResponse {
+            response: "That reminder is too long".to_owned(),
+            questionable_output: false,
+        });
+    }
+
+    if bot.db().count_pending_reminders(user_context.uid).await? >= MAX_PENDING_REMINDERS {
+        return Ok(CommandResponse {
+            response: format!(
+                "You already have {} reminders waiting, cancel one with &unremind <id>",
+                MAX_PENDING_REMINDERS
+            ),
+            questionable_output: false,
+        });
+    }
+
+    let (target_uid, target_login) = if to_self {
+        (user_context.uid, own_login)
+    } else {
+        match bot.api().users().get_by_login(&target).await? {
+            Some(user) => (parse_uid(&user.id)?, user.login),
+            None => {
+                return Ok(CommandResponse {
+                    response: missing_user_response(&bot, &target).await?,
+                    questionable_output: false,
+                })
+            }
+        }
+    };
+
+    let created_at = Utc::now();
+    let id = bot
+        .reminders()
+        .add(&Reminder {
+            id: 0,
+            author_uid: user_context.uid,
+            author_login: user_context.login.to_lowercase(),
+            target_uid,
+            target_login: target_login.clone(),
+            channel: privmsg.channel_login.to_lowercase(),
+            message,
+            created_at,
+            due_at: delay.map(|delay| created_at + delay),
+        })
+        .await?;
+
+    let response = match delay {
+        Some(delay) if to_self => {
+            format!("I'll remind you in {} (id {})", format_duration(delay), id)
+        }
+        Some(delay) => format!(
+            "I'll remind {} in {} (id {})",
+            target_login,
+            format_duration(delay),
+            id
+        ),
+        None => format!(
+            "I'll remind {} when they next chat (id {})",
+            target_login, id
+        ),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn unremind(
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let id = match params.next().unwrap_or("").parse::<i32>() {
+        Ok(id) => id,
+        Err(_) => {
+            return Ok(CommandResponse {
+                response: "Usage: &unremind <id>".to_owned(),
+                questionable_output: false,
+            })
+        }
+    };
+
+    // superusers can clean up anyone's reminders
+    let author = if user_context.permissions == PermissionLevel::Superuser {
+        None
+    } else {
+        Some(user_context.uid)
+    };
+
+    let response = if bot.reminders().cancel(id, author).await? {
+        format!("Cancelled reminder {}", id)
+    } else {
+        format!("You don't have a reminder with id {}", id)
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+const MAX_AFK_REASON_LENGTH: usize = 200;
+
+// &afk, &gn and &brb, the command used decides how the status is described
+async fn afk(
+    command: &str,
+    params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let kind = AfkKind::parse(command).unwrap_or(AfkKind::Afk);
+    let reason: String = params
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .chars()
+        .take(MAX_AFK_REASON_LENGTH)
+        .collect();
+
+    bot.afk()
+        .set(AfkStatus {
+            uid: user_context.uid,
+            login: user_context.login.to_lowercase(),
+            kind,
+            reason: reason.clone(),
+            since: Utc::now(),
+        })
+        .await?;
+
+    let response = if reason.is_empty() {
+        format!("You're now {}", kind.describe())
+    } else {
+        format!("You're now {}: {}", kind.describe(), reason)
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: !reason.is_empty(),
+    })
+}
+
+// &disable and &enable, for the channel they're used in
+async fn toggle(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    disable: bool,
+) -> Result<CommandResponse, BotError> {
+    let name = params
+        .next()
+        .unwrap_or("")
+        .trim_start_matches('&')
+        .to_lowercase();
+    let usage = if disable {
+        "Usage: &disable <command>"
+    } else {
+        "Usage: &enable <command>"
+    };
+
+    let response = if name.is_empty() {
+        usage.to_owned()
+    } else if !bot.commands().command_list.contains_key(&name) {
+        "Sorry, I don't know that command".to_owned()
+    } else if name == "disable" || name == "enable" {
+        "Sorry, that one can't be turned off".to_owned()
+    } else {
+        let channel = privmsg.channel_login.to_lowercase();
+        let changed = bot
+            .commands()
+            .set_disabled(&channel, &name, disable)
+            .await?;
+        match (changed, disable) {
+            (true, true) => format!("Disabled &{} in #{}", name, channel),
+            (true, false) => format!("Enabled &{} in #{}", name, channel),
+            (false, true) => format!("&{} is already disabled in #{}", name, channel),
+            (false, false) => format!("&{} isn't disabled in #{}", name, channel),
+        }
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+const MAX_TIMERS_PER_CHANNEL: usize = 20;
+
+// recurring timers can't go out more often than this
+const MIN_TIMER_INTERVAL: i64 = 60;
+
+// neither intervals nor delays can be longer than a year
+const MAX_TIMER_DAYS: i64 = 365;
+
+const TIMER_USAGE: &str = "Usage: &timer add <name> <interval> [--lines N] [--live|--offline] \
+    <message>, &timer once <name> <delay> [--live|--offline] <message>, \
+    &timer <list|pause|resume|delete> [name]";
+
+// The timer described by the rest of a "&timer add" or "&timer once", or what's wrong with it
+fn parse_timer(
+    once: bool,
+    channel: &str,
+    name: &str,
+    params: std::str::Split<'_, char>,
+) -> Result<Timer, String> {
+    let mut words = params.filter(|word| !word.is_empty());
+
+    let time = words.next().unwrap_or("");
+    let duration = match parse_duration(time) {
+        Some(duration) => duration,
+        None if time.is_empty() => return Err(TIMER_USAGE.to_owned()),
+        None => {
+            return Err(format!(
+                "Sorry, I don't understand \"{}\", try something like 20m or 1h30m",
+                time
+            ))
+        }
+    };
+    if !once && duration < chrono::Duration::seconds(MIN_TIMER_INTERVAL) {
+        return Err("Timers can't repeat more often than once a minute".to_owned());
+    }
+    if duration > chrono::Duration::days(MAX_TIMER_DAYS) {
+        return Err("Timers can't be more than a year apart or away".to_owned());
+    }
+
+    let mut min_lines = 0;
+    let mut condition = TimerCondition::Always;
+    let mut message = Vec::new();
+    while let Some(word) = words.next() {
+        if !message.is_empty() || !word.starts_with("--") {
+            message.push(word);
+            continue;
+        }
+        match word {
+            "--lines" => match words.next().and_then(|lines| lines.parse::<i32>().ok()) {
+                Some(lines) if lines >= 0 => min_lines = lines,
+                _ => return Err("--lines needs a number".to_owned()),
+            },
+            "--live" => condition = TimerCondition::Live,
+            "--offline" => condition = TimerCondition::Offline,
+            option => return Err(format!("Sorry, I don't know the option {}", option)),
+        }
+    }
+
+    let message = message.join(" ");
+    if message.is_empty() {
+        return Err(TIMER_USAGE.to_owned());
+    }
+    if message.chars().count() > MAX_RESPONSE_LENGTH {
+        return Err("That message is too long".to_owned());
+    }
+
+    let now = Utc::now();
+    Ok(Timer {
+        id: 0,
+        channel: channel.to_owned(),
+        name: name.to_owned(),
+        message,
+        schedule: if once {
+            TimerSchedule::Once(now + duration)
+        } else {
+            TimerSchedule::Every(duration)
+        },
+        min_lines,
+        condition,
+        paused: false,
+        created_at: now,
+        last_sent_at: None,
+    })
+}
+
+async fn timer(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let channel = privmsg.channel_login.to_lowercase();
+    let action = params.next().unwrap_or("").to_lowercase();
+    let name = params.next().unwrap_or("").to_lowercase();
+    let timers = bot.timers();
+
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    let missing = format!("There's no timer called {} in #{}", name, channel);
+
+    let response = match action.as_str() {
+        "list" => {
+            let listed: Vec<String> = timers
+                .list(&channel)
+                .iter()
+                .map(|timer| format!("{} ({})", timer.name, timer.describe()))
+                .collect();
+            if listed.is_empty() {
+                format!("There are no timers in #{}", channel)
+            } else {
+                let response = format!("Timers in #{}: {}", channel, listed.join(", "));
+                if response.chars().count() > MAX_RESPONSE_LENGTH {
+                    let mut truncated: String =
+                        response.chars().take(MAX_RESPONSE_LENGTH - 1).collect();
+                    truncated.push('…');
+                    truncated
+                } else {
+                    response
+                }
+            }
+        }
+        "pause" | "resume" if valid_name => {
+            let paused = action == "pause";
+            match (timers.set_paused(&channel, &name, paused).await?, paused) {
+                (false, _) => missing,
+                (true, true) => format!("Paused timer {}", name),
+                (true, false) => format!("Resumed timer {}", name),
+            }
+        }
+        "delete" if valid_name => {
+            if timers.delete(&channel, &name).await? {
+                format!("Deleted timer {}", name)
+            } else {
+                missing
+            }
+        }
+        "add" | "once" if valid_name => {
+            if timers.get(&channel, &name).is_some() {
+                format!("There already is a timer called {} in #{}", name, channel)
+            } else if timers.list(&channel).len() >= MAX_TIMERS_PER_CHANNEL {
+                format!(
+                    "#{} already has {} timers, delete one first",
+                    channel, MAX_TIMERS_PER_CHANNEL
+                )
+            } else {
+                match parse_timer(action == "once", &channel, &name, params) {
+                    Ok(timer) => {
+                        let description = timer.describe();
+                        timers.add(timer).await?;
+                        format!("Added timer {} ({})", name, description)
+                    }
+                    Err(response) => response,
+                }
+            }
+        }
+        _ => TIMER_USAGE.to_owned(),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn markov(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let mut first = params.next().unwrap_or("");
+
+    if first == "optout" || first == "optin" {
+        let opted_out = first == "optout";
+        let changed = bot
+            .markov()
+            .set_opted_out(user_context.uid, opted_out)
+            .await?;
+        let response = match (opted_out, changed) {
+            (true, true) => "I won't learn from your messages anymore",
+            (true, false) => "You're already opted out",
+            (false, true) => "I'll learn from your messages again",
+            (false, false) => "You weren't opted out",
+        };
+
+        return Ok(CommandResponse {
+            response: response.to_owned(),
+            questionable_output: false,
+        });
+    }
+
+    // "@alice" picks the user's own model instead of the channel's
+    let (model, source) = match first.strip_prefix('@') {
+        Some(login) if !login.is_empty() => {
+            let user = match bot.api().users().get_by_login(login).await? {
+                Some(user) => user,
+                None => {
+                    return Ok(CommandResponse {
+                        response: missing_user_response(&bot, login).await?,
+                        questionable_output: false,
+                    })
+                }
+            };
+            let uid = parse_uid(&user.id)?;
+            if bot.markov().is_opted_out(uid) {
+                return Ok(CommandResponse {
+                    response: format!("{} opted out of markov", user.login),
+                    questionable_output: false,
+                });
+            }
+
+            first = params.next().unwrap_or("");
+            (markov::user_model(uid), user.login)
+        }
+        _ => {
+            let channel = privmsg.channel_login.to_lowercase();
+            (channel.clone(), format!("#{}", channel))
+        }
+    };
+    let seed = Some(first).filter(|seed| !seed.is_empty());
+
+    let response = match markov::generate(&*bot.logs(), &model, seed).await? {
+        Some(mut sentence) => {
+            if sentence.chars().count() > MAX_RESPONSE_LENGTH {
+                sentence = sentence.chars().take(MAX_RESPONSE_LENGTH - 1).collect();
+                sentence.push('…');
+            }
+            sentence
+        }
+        None => match seed {
+            Some(seed) => format!("I've never seen {} say {}", source, seed),
+            None => format!("I haven't learned anything from {} yet", source),
+        },
+    };
+
+    // whatever chat said can come back out
+    Ok(CommandResponse {
+        response,
+        questionable_output: true,
+    })
+}
+
+// how far back &addquote <user> ~N can reach
+const MAX_QUOTE_OFFSET: i64 = 100;
+
+async fn addquote(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let usage = Ok(CommandResponse {
+        response: "Usage: &addquote <user> <text>, or &addquote <user> ~N to quote their Nth last \
+            message here"
+            .to_owned(),
+        questionable_output: false,
+    });
+    let quoted = params
+        .next()
+        .unwrap_or("")
+        .trim_start_matches('@')
+        .to_lowercase();
+    let words: Vec<&str> = params.filter(|word| !word.is_empty()).collect();
+    if quoted.is_empty() || words.is_empty() {
+        return usage;
+    }
+    let channel = privmsg.channel_login.to_lowercase();
+
+    let text = match words.as_slice() {
+        [offset] if offset.starts_with('~') => {
+            let offset = match &offset[1..] {
+                "" => 1,
+                n => match n.parse::<i64>() {
+                    Ok(n) if (1..=MAX_QUOTE_OFFSET).contains(&n) => n,
+                    _ => return usage,
+                },
+            };
+            // quoting yourself, the &addquote itself is already logged
+            let skip = if quoted == user_context.login.to_lowercase() {
+                offset
+            } else {
+                offset - 1
+            };
+            match bot
+                .logs()
+                .get_last_message_from_username(&channel, &quoted, skip)
+                .await?
+            {
+                Some((_, message)) => message,
+                None => {
+                    return Ok(CommandResponse {
+                        response: format!(
+                            "Sorry, I didn't find that message from {} in #{}",
+                            quoted, channel
+                        ),
+                        questionable_output: false,
+                    })
+                }
+            }
+        }
+        _ => words.join(" "),
+    };
+    if text.chars().count() > MAX_QUOTE_LENGTH {
+        return Ok(CommandResponse {
+            response: "That quote is too long".to_owned(),
+            questionable_output: false,
+        });
+    }
+
+    let id = bot
+        .db()
+        .add_quote(&Quote {
+            id: 0,
+            channel,
+            quoted,
+            text,
+            added_by: user_context.login.to_lowercase(),
+            added_at: Utc::now(),
+        })
+        .await?;
+
+    Ok(CommandResponse {
+        response: format!("Added quote #{}", id),
+        questionable_output: false,
+    })
+}
+
+async fn quote(
+    privmsg: &PrivmsgMessage,
+    params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let words: Vec<&str> = params.filter(|word| !word.is_empty()).collect();
+    let channel = privmsg.channel_login.to_lowercase();
+    let db = bot.db();
+
+    let (found, missing) = match words.as_slice() {
+        [] => (
+            db.get_random_quote(&channel, None, None).await?,
+            format!("#{} has no quotes yet", channel),
+        ),
+        [word] if word.trim_start_matches('#').parse::<i32>().is_ok() => {
+            let id: i32 = word.trim_start_matches('#').parse().unwrap_or_default();
+            (
+                db.get_quote(&channel, id).await?,
+                format!("There's no quote #{} in #{}", id, channel),
+            )
+        }
+        // a single word is who said it if anyone by that name was quoted, a search otherwise
+        [word] => {
+            let login = word.trim_start_matches('@').to_lowercase();
+            let found = match db.get_random_quote(&channel, Some(&login), None).await? {
+                Some(quote) => Some(quote),
+                None => db.get_random_quote(&channel, None, Some(word)).await?,
+            };
+            (
+                found,
+                format!("No quotes in #{} are from or mention {}", channel, word),
+            )
+        }
+        _ => {
+            let search = words.join(" ");
+            (
+                db.get_random_quote(&channel, None, Some(&search)).await?,
+                format!("No quotes in #{} mention {}", channel, search),
+            )
+        }
+    };
+
+    let response = match found {
+        Some(quote) => {
+            let mut response = quote.describe();
+            if response.chars().count() > MAX_RESPONSE_LENGTH {
+                response = response.chars().take(MAX_RESPONSE_LENGTH - 1).collect();
+                response.push('…');
+            }
+            response
+        }
+        None => missing,
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: true,
+    })
+}
+
+async fn delquote(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let id = match params
+        .next()
+        .unwrap_or("")
+        .trim_start_matches('#')
+        .parse::<i32>()
+    {
+        Ok(id) => id,
+        Err(_) => {
+            return Ok(CommandResponse {
+                response: "Usage: &delquote <id>".to_owned(),
+                questionable_output: false,
+            })
+        }
+    };
+    let channel = privmsg.channel_login.to_lowercase();
+
+    let response = if bot.db().delete_quote(&channel, id).await? {
+        format!("Deleted quote #{}", id)
+    } else {
+        format!("There's no quote #{} in #{}", id, channel)
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+// Moves a channel's quotes to and from JSON files in the data directory's quotes folder, where
+// the bot's operator can pick them up or drop in another bot's export
+async fn quotes(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let channel = privmsg.channel_login.to_lowercase();
+    let action = params.next().unwrap_or("");
+    let mut name = params.next().unwrap_or("").to_owned();
+    if action == "export" && name.is_empty() {
+        name = channel.clone();
+    }
+    if !name.contains('.') {
+        name.push_str(".json");
+    }
+    let shown = format!("quotes/{}", name);
+
+    // the files are small and sit in the data directory, so they're read and written in place
+    // instead of going through the blocking pool
+    let response = match (action, bot.data_dir().file("quotes", &name)) {
+        ("import" | "export", None) => "Sorry, that's not a file name I can use".to_owned(),
+        ("export", Some(path)) => {
+            let exported = bot.db().get_quotes(&channel).await?;
+            let written = match path.parent() {
+                Some(dir) => std::fs::create_dir_all(dir),
+                None => Ok(()),
+            };
+            let written = match written {
+                Ok(_) => std::fs::write(&path, quotes::to_json(&exported)),
+                Err(e) => Err(e),
+            };
+            match written {
+                Ok(_) => format!(
+                    "Exported {} quotes from #{} to {}",
+                    exported.len(),
+                    channel,
+                    shown
+                ),
+                Err(e) => {
+                    eprintln!("Error exporting quotes to {}: {}", path.display(), e);
+                    format!("Sorry, I couldn't write {}", shown)
+                }
+            }
+        }
+        ("import", Some(path)) => match std::fs::read_to_string(&path) {
+            Err(e) => {
+                eprintln!("Error importing quotes from {}: {}", path.display(), e);
+                format!("Sorry, I couldn't read {}", shown)
+            }
+            Ok(json) => {
+                match quotes::from_json(&json, &channel, &user_context.login.to_lowercase()) {
+                    Err(e) => {
+                        eprintln!("Error importing quotes from {}: {}", path.display(), e);
+                        format!("Sorry, {} isn't a list of quotes", shown)
+                    }
+                    Ok(imported) if imported.is_empty() => {
+                        format!("There are no quotes in {}", shown)
+                    }
+                    Ok(imported) => format!(
+                        "Imported {} quotes into #{}",
+                        bot.db().import_quotes(&imported).await?,
+                        channel
+                    ),
+                }
+            }
+        },
+        _ => "Usage: &quotes export, or &quotes import <file>".to_owned(),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+const MAX_COUNTERS_PER_CHANNEL: usize = 50;
+
+// biggest step or value a counter can be given at once
+const MAX_COUNTER_CHANGE: i64 = 1_000_000;
+
+// changes shown by &counter history
+const COUNTER_HISTORY_LENGTH: i64 = 5;
+
+const COUNTER_USAGE: &str = "Usage: &counter add <name> [template], &counter delete <name>, \
+    &counter list or &counter history <name>";
+
+async fn counter(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let channel = privmsg.channel_login.to_lowercase();
+    let counters = bot.counters();
+    let action = params.next().unwrap_or("");
+    let name = params
+        .next()
+        .unwrap_or("")
+        .trim_start_matches('&')
+        .to_lowercase();
+    let valid_name = !name.is_empty()
+        && name.chars().count() <= MAX_COUNTER_NAME_LENGTH
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let missing = format!("There's no counter called &{} in #{}", name, channel);
+
+    let response = match action {
+        "list" => {
+            let names = counters.list(&channel);
+            if names.is_empty() {
+                format!("There are no counters in #{}", channel)
+            } else {
+                let names: Vec<String> = names.iter().map(|name| format!("&{}", name)).collect();
+                format!("Counters in #{}: {}", channel, names.join(", "))
+            }
+        }
+        "delete" if valid_name => {
+            if counters.delete(&channel, &name).await? {
+                format!("Deleted &{}", name)
+            } else {
+                missing
+            }
+        }
+        "history" if valid_name => {
+            if !counters.exists(&channel, &name) {
+                missing
+            } else {
+                let history = bot
+                    .db()
+                    .get_counter_history(&channel, &name, COUNTER_HISTORY_LENGTH)
+                    .await?;
+                if history.is_empty() {
+                    format!("&{} hasn't changed yet", name)
+                } else {
+                    let changes: Vec<String> = history
+                        .iter()
+                        .map(|event| {
+                            format!(
+                                "{} by {} {} ago (now {})",
+                                event.change.describe(),
+                                event.changed_by,
+                                format_duration(Utc::now() - event.changed_at),
+                                event.value
+                            )
+                        })
+                        .collect();
+                    format!("&{}: {}", name, changes.join(", "))
+                }
+            }
+        }
+        "add" if valid_name => {
+            let template = params
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<&str>>()
+                .join(" ");
+            let template = if template.is_empty() {
+                DEFAULT_TEMPLATE.to_owned()
+            } else {
+                template
+            };
+
+            if bot.commands().command_list.contains_key(&name) {
+                format!("&{} is already a command", name)
+            } else if counters.exists(&channel, &name) {
+                format!(
+                    "There already is a counter called &{} in #{}",
+                    name, channel
+                )
+            } else if counters.list(&channel).len() >= MAX_COUNTERS_PER_CHANNEL {
+                format!(
+                    "#{} already has {} counters, delete one first",
+                    channel, MAX_COUNTERS_PER_CHANNEL
+                )
+            } else if template.chars().count() > MAX_RESPONSE_LENGTH {
+                "That template is too long".to_owned()
+            } else {
+                counters
+                    .add(&Counter {
+                        channel: channel.clone(),
+                        name: name.clone(),
+                        template,
+                        value: 0,
+                        created_at: Utc::now(),
+                    })
+                    .await?;
+                format!(
+                    "Added &{}, moderators change it with &{} +1, -1, set <n> or reset",
+                    name, name
+                )
+            }
+        }
+        _ => COUNTER_USAGE.to_owned(),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+// "+", "-5", "set 3" or "reset", anything else just shows the counter
+fn parse_counter_change(params: &[&str]) -> Option<CounterChange> {
+    let amount = |n: &str| {
+        n.parse::<i64>()
+            .ok()
+            .filter(|n| n.abs() <= MAX_COUNTER_CHANGE)
+    };
+
+    match params {
+        ["+"] => Some(CounterChange::Add(1)),
+        ["-"] => Some(CounterChange::Add(-1)),
+        ["reset"] => Some(CounterChange::Set(0)),
+        ["set", value] => amount(value).map(CounterChange::Set),
+        [step] if step.starts_with('+') || step.starts_with('-') => {
+            amount(step).map(CounterChange::Add)
+        }
+        _ => None,
+    }
+}
+
+// &<counter name>, shows the counter or changes it when a moderator gives it a change
+pub async fn run_counter(
+    name: &str,
+    privmsg: &PrivmsgMessage,
+    params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let channel = privmsg.channel_login.to_lowercase();
+    let params: Vec<&str> = params.filter(|word| !word.is_empty()).collect();
+    let db = bot.db();
+
+    let change = parse_counter_change(&params);
+    if change.is_some()
+        && !user_context
+            .permissions
+            .satisfies(PermissionLevel::Moderator)
+    {
+        return Ok(CommandResponse {
+            response: format!("Sorry, only moderators can change &{}", name),
+            questionable_output: false,
+        });
+    }
+    let changed_to = match change {
+        Some(change) => {
+            db.change_counter(&channel, name, change, &user_context.login.to_lowercase())
+                .await?
+        }
+        None => None,
+    };
+
+    // a change answers with the value it made, even if another one landed right after
+    let response = match db.get_counter(&channel, name).await? {
+        Some(counter) => {
+            let counter = Counter {
+                value: changed_to.unwrap_or(counter.value),
+                ..counter
+            };
+            bot.counters().fill(&channel, &counter.render()).await?
+        }
+        None => "".to_owned(),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+// chatters shown by &toppoints
+const TOP_POINTS_LENGTH: i64 = 5;
+
+// The amount a chatter bets or gives out of their balance, or what to tell them instead
+fn points_amount(amount: &str, balance: i64, usage: &str) -> Result<i64, String> {
+    match parse_amount(amount, balance) {
+        _ if balance <= 0 => {
+            Err("You don't have any points yet, chat a bit to earn some".to_owned())
+        }
+        Some(amount) if amount > balance => Err(format!("You only have {} points", balance)),
+        Some(amount) => Ok(amount),
+        None => Err(usage.to_owned()),
+    }
+}
+
+// every economy command goes quiet where &points is disabled
+fn economy_disabled(bot: &BorrowBot, privmsg: &PrivmsgMessage) -> bool {
+    !Economy::is_enabled(bot, &privmsg.channel_login.to_lowercase())
+}
+
+async fn points(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let channel = privmsg.channel_login.to_lowercase();
+    let target = params
+        .next()
+        .unwrap_or("")
+        .trim_start_matches('@')
+        .to_lowercase();
+
+    let response = if target.is_empty() || target == user_context.login.to_lowercase() {
+        let balance = bot.db().get_points(&channel, user_context.uid).await?;
+        format!("You have {} points", balance)
+    } else {
+        match bot.api().users().get_by_login(&target).await? {
+            Some(user) => {
+                let balance = bot.db().get_points(&channel, parse_uid(&user.id)?).await?;
+                format!("{} has {} points", user.login, balance)
+            }
+            None => missing_user_response(&bot, &target).await?,
+        }
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn give(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    if economy_disabled(&bot, privmsg) {
+        return Ok(CommandResponse::new("".to_owned(), false));
+    }
+    let usage = "Usage: &give <user> <amount|all>";
+    let channel = privmsg.channel_login.to_lowercase();
+    let target = params
+        .next()
+        .unwrap_or("")
+        .trim_start_matches('@')
+        .to_lowercase();
+    let amount = params.next().unwrap_or("");
+    if target.is_empty() || amount.is_empty() {
+        return Ok(CommandResponse::new(usage.to_owned(), false));
+    }
+    if target == user_context.login.to_lowercase() {
+        return Ok(CommandResponse::new(
+            "You can't give points to yourself".to_owned(),
+            false,
+        ));
+    }
+
+    let db = bot.db();
+    let balance = db.get_points(&channel, user_context.uid).await?;
+    let amount = match points_amount(amount, balance, usage) {
+        Ok(amount) => amount,
+        Err(response) => return Ok(CommandResponse::new(response, false)),
+    };
+    let user = match bot.api().users().get_by_login(&target).await? {
+        Some(user) => user,
+        None => {
+            return Ok(CommandResponse::new(
+                missing_user_response(&bot, &target).await?,
+                false,
+            ))
+        }
+    };
+
+    let response = match db
+        .transfer_points(
+            &channel,
+            user_context.uid,
+            parse_uid(&user.id)?,
+            &user.login,
+            amount,
+            0,
+        )
+        .await?
+    {
+        Some((left, _)) => format!(
+            "Gave {} points to {}, you have {} left",
+            amount, user.login, left
+        ),
+        // something else spent them in the meantime
+        None => "You don't have that many points anymore".to_owned(),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn roulette(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    if economy_disabled(&bot, privmsg) {
+        return Ok(CommandResponse::new("".to_owned(), false));
+    }
+    let channel = privmsg.channel_login.to_lowercase();
+    let db = bot.db();
+    let balance = db.get_points(&channel, user_context.uid).await?;
+    let bet = match points_amount(
+        params.next().unwrap_or(""),
+        balance,
+        "Usage: &roulette <amount|all>",
+    ) {
+        Ok(bet) => bet,
+        Err(response) => return Ok(CommandResponse::new(response, false)),
+    };
+
+    let won = rand::thread_rng().gen_bool(0.5);
+    let delta = if won { bet } else { -bet };
+    let response = match db
+        .wager_points(&channel, user_context.uid, bet, delta)
+        .await?
+    {
+        Some(balance) if won => format!("You won {} points and now have {}", bet, balance),
+        Some(balance) => format!("You lost {} points and now have {}", bet, balance),
+        None => "You don't have that many points anymore".to_owned(),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn slots(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    if economy_disabled(&bot, privmsg) {
+        return Ok(CommandResponse::new("".to_owned(), false));
+    }
+    let channel = privmsg.channel_login.to_lowercase();
+    let db = bot.db();
+    let balance = db.get_points(&channel, user_context.uid).await?;
+    let bet = match points_amount(
+        params.next().unwrap_or(""),
+        balance,
+        "Usage: &slots <amount|all>",
+    ) {
+        Ok(bet) => bet,
+        Err(response) => return Ok(CommandResponse::new(response, false)),
+    };
+
+    let (reels, payout) = spin_slots(bet);
+    let response = match db
+        .wager_points(&channel, user_context.uid, bet, payout - bet)
+        .await?
+    {
+        Some(balance) => {
+            let outcome = if payout > bet {
+                format!("Jackpot! You won {} points", payout - bet)
+            } else if payout == bet {
+                format!("You got your {} points back", bet)
+            } else {
+                format!("You lost {} points", bet)
+            };
+            format!(
+                "[ {} ] {} and now have {}",
+                reels.join(" | "),
+                outcome,
+                balance
+            )
+        }
+        None => "You don't have that many points anymore".to_owned(),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn duel(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    if economy_disabled(&bot, privmsg) {
+        return Ok(CommandResponse::new("".to_owned(), false));
+    }
+    let usage = "Usage: &duel <user> <amount>, or &duel accept / &duel decline";
+    let channel = privmsg.channel_login.to_lowercase();
+    let economy = bot.economy();
+    let db = bot.db();
+    let own_login = user_context.login.to_lowercase();
+    let target = params
+        .next()
+        .unwrap_or("")
+        .trim_start_matches('@')
+        .to_lowercase();
+
+    let response = match target.as_str() {
+        "accept" => match economy.take_challenge(&channel, user_context.uid) {
+            None => "You don't have a duel waiting".to_owned(),
+            Some(duel) => {
+                let challenger = (duel.challenger_uid, duel.challenger_login.as_str());
+                let challenged = (user_context.uid, own_login.as_str());
+                let (winner, loser) = if rand::thread_rng().gen_bool(0.5) {
+                    (challenger, challenged)
+                } else {
+                    (challenged, challenger)
+                };
+
+                // the winner has to be able to cover the bet too, it's checked in the transfer
+                match db
+                    .transfer_points(
+                        &channel,
+                        loser.0,
+                        winner.0,
+                        winner.1,
+                        duel.amount,
+                        duel.amount,
+                    )
+                    .await?
+                {
+                    Some(_) => format!(
+                        "{} won the duel against {} and takes {} points",
+                        winner.1, loser.1, duel.amount
+                    ),
+                    None => format!(
+                        "One of you doesn't have {} points anymore, the duel is off",
+                        duel.amount
+                    ),
+                }
+            }
+        },
+        "decline" => match economy.take_challenge(&channel, user_context.uid) {
+            None => "You don't have a duel waiting".to_owned(),
+            Some(duel) => format!("You turned down {}'s duel", duel.challenger_login),
+        },
+        "" => usage.to_owned(),
+        _ if target == own_login => "You can't duel yourself".to_owned(),
+        _ => {
+            let balance = db.get_points(&channel, user_context.uid).await?;
+            match points_amount(params.next().unwrap_or(""), balance, usage) {
+                Err(response) => response,
+                Ok(amount) => match bot.api().users().get_by_login(&target).await? {
+                    None => missing_user_response(&bot, &target).await?,
+                    Some(user) => {
+                        economy.challenge(
+                            &channel,
+                            parse_uid(&user.id)?,
+                            Duel {
+                                challenger_uid: user_context.uid,
+                                challenger_login: own_login.clone(),
+                                amount,
+                                challenged_at: tokio::time::Instant::now(),
+                            },
+                        );
+                        format!(
+                            "You challenged {} to a duel for {} points, {} has {} to answer with \
+                            &duel accept or &duel decline",
+                            user.login,
+                            amount,
+                            user.login,
+                            format_duration(chrono::Duration::seconds(
+                                DUEL_TIMEOUT.as_secs() as i64
+                            ))
+                        )
+                    }
+                },
+            }
+        }
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn toppoints(
+    privmsg: &PrivmsgMessage,
+    _params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    if economy_disabled(&bot, privmsg) {
+        return Ok(CommandResponse::new("".to_owned(), false));
+    }
+    let channel = privmsg.channel_login.to_lowercase();
+    let top = bot.db().get_top_points(&channel, TOP_POINTS_LENGTH).await?;
+
+    let response = if top.is_empty() {
+        format!("Nobody in #{} has any points yet", channel)
+    } else {
+        let places: Vec<String> = top
+            .iter()
+            .enumerate()
+            .map(|(i, (login, balance))| format!("{}. {} ({})", i + 1, login, balance))
+            .collect();
+        format!("Top points in #{}: {}", channel, places.join(", "))
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn resetpoints(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let channel = privmsg.channel_login.to_lowercase();
+    let target = params
+        .next()
+        .unwrap_or("")
+        .trim_start_matches('@')
+        .to_lowercase();
+
+    let response = match target.as_str() {
+        "" => "Usage: &resetpoints <user|all>".to_owned(),
+        "all" => format!(
+            "Reset {} balances in #{}",
+            bot.db().reset_points(&channel, None).await?,
+            channel
+        ),
+        _ => match bot.api().users().get_by_login(&target).await? {
+            None => missing_user_response(&bot, &target).await?,
+            Some(user) => {
+                let uid = parse_uid(&user.id)?;
+                if bot.db().reset_points(&channel, Some(uid)).await? > 0 {
+                    format!("Reset {}'s points in #{}", user.login, channel)
+                } else {
+                    format!("{} has no points in #{}", user.login, channel)
+                }
+            }
+        },
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+const POLL_USAGE: &str = "Usage: &poll \"question\" option 1 | option 2 [| ...] [--duration 2m] \
+    [--bets], or &poll end / &poll cancel";
+
+// how long a poll runs unless it's given a --duration
+const DEFAULT_POLL_DURATION: std::time::Duration = std::time::Duration::from_secs(60);
+
+// Splits `"question" a | b | c --duration 2m --bets` into a poll, or says what's wrong with it
+fn parse_poll(text: &str) -> Result<Poll, String> {
+    let rest = text.trim().strip_prefix('"').ok_or(POLL_USAGE)?;
+    let end = rest.find('"').ok_or(POLL_USAGE)?;
+    let question = rest[..end].trim().to_owned();
+
+    let mut duration = DEFAULT_POLL_DURATION;
+    let mut bets = false;
+    let mut words = Vec::new();
+    let mut tokens = rest[end + 1..].split(' ').filter(|token| !token.is_empty());
+    while let Some(token) = tokens.next() {
+        match token {
+            "--bets" => bets = true,
+            "--duration" => {
+                duration = tokens
+                    .next()
+                    .and_then(parse_duration)
+                    .and_then(|duration| duration.to_std().ok())
+                    .ok_or("Sorry, I don't understand that duration, try something like 2m")?
+            }
+            word => words.push(word),
+        }
+    }
+    let options: Vec<String> = words
+        .join(" ")
+        .split('|')
+        .map(|option| option.trim().to_owned())
+        .filter(|option| !option.is_empty())
+        .collect();
+
+    if question.is_empty() || options.len() < 2 {
+        return Err(POLL_USAGE.to_owned());
+    }
+    if options.len() > MAX_POLL_OPTIONS {
+        return Err(format!(
+            "Polls can have at most {} options",
+            MAX_POLL_OPTIONS
+        ));
+    }
+    if duration < std::time::Duration::from_secs(10)
+        || duration > std::time::Duration::from_secs(30 * 60)
+    {
+        return Err("Polls can run between 10s and 30m".to_owned());
+    }
+
+    Ok(Poll::new(question, options, duration, bets))
+}
+
+async fn poll(
+    privmsg: &PrivmsgMessage,
+    params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    _user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let channel = privmsg.channel_login.to_lowercase();
+    let polls = bot.polls();
+    let text = params.collect::<Vec<&str>>().join(" ");
+    let missing = format!("There's no poll running in #{}", channel);
+
+    let response = match text.trim() {
+        // the results follow as soon as the poll is checked next
+        "end" if polls.end_now(&channel) => "".to_owned(),
+        "cancel" => match polls.cancel(&channel) {
+            Some(poll) => {
+                if let Some(bets) = &poll.bets {
+                    refund_bets(&bot, &channel, bets).await;
+                }
+                "Cancelled the poll, bets were refunded".to_owned()
+            }
+            None => missing,
+        },
+        "end" => missing,
+        _ if polls.is_running(&channel) => format!(
+            "There's already a poll running in #{}, end it with &poll end",
+            channel
+        ),
+        text => match parse_poll(text) {
+            Err(response) => response,
+            Ok(poll) => {
+                let mut response = format!(
+                    "Poll started: {} | {} | type a number to vote, ends in {}",
+                    poll.question,
+                    poll.describe_options(),
+                    format_duration(chrono::Duration::seconds(
+                        poll.ends_at
+                            .duration_since(tokio::time::Instant::now())
+                            .as_secs() as i64
+                    ))
+                );
+                if poll.bets.is_some() {
+                    response.push_str(", bet points with &bet <option> <amount>");
+                }
+                if polls.start_poll(&channel, poll) {
+                    response
+                } else {
+                    // someone else's poll got in first
+                    format!(
+                        "There's already a poll running in #{}, end it with &poll end",
+                        channel
+                    )
+                }
+            }
+        },
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn bet(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    if economy_disabled(&bot, privmsg) {
+        return Ok(CommandResponse::new("".to_owned(), false));
+    }
+    let usage = "Usage: &bet <option> <amount|all>";
+    let channel = privmsg.channel_login.to_lowercase();
+    let polls = bot.polls();
+    if !polls.is_running(&channel) {
+        return Ok(CommandResponse::new(
+            format!("There's no poll running in #{}", channel),
+            false,
+        ));
+    }
+
+    let option = match params.next().unwrap_or("").parse::<usize>() {
+        Ok(option) if option >= 1 => option - 1,
+        _ => return Ok(CommandResponse::new(usage.to_owned(), false)),
+    };
+    let db = bot.db();
+    let balance = db.get_points(&channel, user_context.uid).await?;
+    let amount = match points_amount(params.next().unwrap_or(""), balance, usage) {
+        Ok(amount) => amount,
+        Err(response) => return Ok(CommandResponse::new(response, false)),
+    };
+
+    let bet = Bet {
+        login: user_context.login.to_lowercase(),
+        option,
+        amount,
+    };
+    if let Err(response) = polls.check_bet(&channel, user_context.uid, &bet) {
+        return Ok(CommandResponse::new(response, false));
+    }
+
+    // the bet is held until the poll ends, taking it out in one statement means the same points
+    // can't be bet and spent at once
+    if db
+        .wager_points(&channel, user_context.uid, amount, -amount)
+        .await?
+        .is_none()
+    {
+        return Ok(CommandResponse::new(
+            "You don't have that many points anymore".to_owned(),
+            false,
+        ));
+    }
+    let response = match polls.bet(&channel, user_context.uid, bet.clone()) {
+        Ok(response) => response,
+        Err(response) => {
+            // the poll ended or someone was faster in the meantime
+            db.add_points(&channel, user_context.uid, &bet.login, amount)
+                .await?;
+            response
+        }
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+// players shown by &<game> top
+const TOP_GAME_SCORES_LENGTH: i64 = 5;
+
+// Starts a game in the channel unless it's playing one already, returns what to answer
+fn start_game(bot: &BorrowBot, channel: &str, game: Box<dyn Game>, started: String) -> String {
+    match bot.games().start_game(channel, game) {
+        Ok(()) => started,
+        Err(running) => already_playing(channel, running),
+    }
+}
+
+fn already_playing(channel: &str, running: &str) -> String {
+    format!(
+        "There's already a game of {} running in #{}, stop it with &{} stop",
+        running, channel, running
+    )
+}
+
+// &<game> stop, top and score [user], which every game has. None for anything else
+async fn game_subcommand(
+    game: &str,
+    action: &str,
+    params: &mut std::str::Split<'_, char>,
+    bot: &BorrowBot,
+    channel: &str,
+    user_context: &UserContext,
+) -> Result<Option<String>, BotError> {
+    let response = match action {
+        "stop"
+            if !user_context
+                .permissions
+                .satisfies(PermissionLevel::Moderator) =>
+        {
+            format!("Sorry, only moderators can stop {}", game)
+        }
+        "stop" => match bot.games().stop(channel, game) {
+            Some(results) => results,
+            None => format!("There's no game of {} running in #{}", game, channel),
+        },
+        "top" => {
+            let top = bot
+                .db()
+                .get_top_game_scores(channel, game, TOP_GAME_SCORES_LENGTH)
+                .await?;
+            if top.is_empty() {
+                format!("Nobody has scored at {} in #{} yet", game, channel)
+            } else {
+                let places: Vec<String> = top
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (login, points))| format!("{}. {} ({})", i + 1, login, points))
+                    .collect();
+                format!(
+                    "Top {} players in #{}: {}",
+                    game,
+                    channel,
+                    places.join(", ")
+                )
+            }
+        }
+        "score" => {
+            let target = params
+                .next()
+                .unwrap_or("")
+                .trim_start_matches('@')
+                .to_lowercase();
+            if target.is_empty() || target == user_context.login.to_lowercase() {
+                let (points, _) = bot
+                    .db()
+                    .get_game_score(channel, game, user_context.uid)
+                    .await?;
+                format!("You have {} {} points", points, game)
+            } else {
+                match bot.api().users().get_by_login(&target).await? {
+                    Some(user) => {
+                        let (points, _) = bot
+                            .db()
+                            .get_game_score(channel, game, parse_uid(&user.id)?)
+                            .await?;
+                        format!("{} has {} {} points", user.login, points, game)
+                    }
+                    None => missing_user_response(bot, &target).await?,
+                }
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(response))
+}
+
+const TRIVIA_USAGE: &str = "Usage: &trivia start <pack> [questions], &trivia stop, &trivia packs, \
+    &trivia top or &trivia score [user]";
+
+async fn trivia(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let channel = privmsg.channel_login.to_lowercase();
+    let action = params.next().unwrap_or("");
+    let is_moderator = user_context
+        .permissions
+        .satisfies(PermissionLevel::Moderator);
+    if let Some(response) =
+        game_subcommand("trivia", action, &mut params, &bot, &channel, user_context).await?
+    {
+        return Ok(CommandResponse::new(response, false));
+    }
+
+    let response = match action {
+        "start" if !is_moderator => "Sorry, only moderators can start trivia".to_owned(),
+        "start" => match bot.games().running(&channel) {
+            Some(running) => already_playing(&channel, running),
+            None => {
+                let pack = params.next().unwrap_or("");
+                let rounds = match params.next() {
+                    None => Some(DEFAULT_ROUNDS),
+                    Some(rounds) => rounds
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|rounds| (1..=MAX_ROUNDS).contains(rounds)),
+                };
+                match (pack, rounds) {
+                    ("", _) => TRIVIA_USAGE.to_owned(),
+                    (_, None) => {
+                        format!("Games can have between 1 and {} questions", MAX_ROUNDS)
+                    }
+                    (pack, Some(rounds)) => match trivia::load_pack(bot.data_dir(), pack) {
+                        Err(response) => response,
+                        Ok(questions) => {
+                            let rounds = rounds.min(questions.len());
+                            let questions_text = match rounds {
+                                1 => "1 question".to_owned(),
+                                rounds => format!("{} questions", rounds),
+                            };
+                            start_game(
+                                &bot,
+                                &channel,
+                                Box::new(TriviaGame::new(questions, rounds)),
+                                format!(
+                                    "Starting trivia: {} from the {} pack, answer in chat!",
+                                    questions_text, pack
+                                ),
+                            )
+                        }
+                    },
+                }
+            }
+        },
+        "packs" => {
+            let packs = trivia::list_packs(bot.data_dir());
+            if packs.is_empty() {
+                "There are no trivia packs yet".to_owned()
+            } else {
+                format!("Trivia packs: {}", packs.join(", "))
+            }
+        }
+        _ => TRIVIA_USAGE.to_owned(),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn hangman(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let channel = privmsg.channel_login.to_lowercase();
+    let action = params.next().unwrap_or("");
+    if let Some(response) =
+        game_subcommand("hangman", action, &mut params, &bot, &channel, user_context).await?
+    {
+        return Ok(CommandResponse::new(response, false));
+    }
+
+    let response = match (action, bot.games().running(&channel)) {
+        ("", Some(running)) => already_playing(&channel, running),
+        ("", None) => match hangman::random_word(bot.data_dir()) {
+            Err(response) => response,
+            Ok(word) => {
+                let game = Hangman::new(word);
+                let started = format!(
+                    "Hangman! Guess a letter or the whole word: {} ({} misses allowed)",
+                    game.board(),
+                    MAX_MISSES
+                );
+                start_game(&bot, &channel, Box::new(game), started)
+            }
+        },
+        _ => "Usage: &hangman, &hangman stop, &hangman top or &hangman score [user]".to_owned(),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
+}
+
+async fn emoteguess(
+    privmsg: &PrivmsgMessage,
+    mut params: std::str::Split<'_, char>,
+    bot: Arc<BorrowBot>,
+    user_context: &UserContext,
+) -> Result<CommandResponse, BotError> {
+    let channel = privmsg.channel_login.to_lowercase();
+    let action = params.next().unwrap_or("");
+    if let Some(response) = game_subcommand(
+        "emoteguess",
+        action,
+        &mut params,
+        &bot,
+        &channel,
+        user_context,
+    )
+    .await?
+    {
+        return Ok(CommandResponse::new(response, false));
+    }
+
+    let response = match (action, bot.games().running(&channel)) {
+        ("", Some(running)) => already_playing(&channel, running),
+        ("", None) => {
+            let emotes = bot
+                .api()
+                .emotes()
+                .channel(&channel, &privmsg.channel_id)
+                .await?;
+            match emotes.choose(&mut rand::thread_rng()) {
+                None => format!(
+                    "#{} doesn't have any 7TV, BTTV or FFZ emotes to guess",
+                    channel
+                ),
+                Some(emote) => {
+                    let game = EmoteGuess::new(emote.clone());
+                    let started =
+                        format!("Guess the emote! It's {}, type it in chat", game.describe());
+                    start_game(&bot, &channel, Box::new(game), started)
+                }
+            }
+        }
+        _ => "Usage: &emoteguess, &emoteguess stop, &emoteguess top or &emoteguess score [user]"
+            .to_owned(),
+    };
+
+    Ok(CommandResponse {
+        response,
+        questionable_output: false,
+    })
 }
@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+
+use crate::database::{DBError, Database};
+
+// what a new counter says unless it's given something else
+pub const DEFAULT_TEMPLATE: &str = "{name}: {count}";
+
+// longest a counter's name can be, it's used as a command
+pub const MAX_COUNTER_NAME_LENGTH: usize = 25;
+
+// placeholder for another counter's value, "{counter:wins}"
+const COUNTER_PLACEHOLDER: &str = "{counter:";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CounterChange {
+    Add(i64),
+    Set(i64),
+}
+
+impl CounterChange {
+    pub fn parse(kind: &str, amount: i64) -> Option<Self> {
+        match kind {
+            "add" => Some(CounterChange::Add(amount)),
+            "set" => Some(CounterChange::Set(amount)),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CounterChange::Add(_) => "add",
+            CounterChange::Set(_) => "set",
+        }
+    }
+
+    pub fn amount(&self) -> i64 {
+        match *self {
+            CounterChange::Add(amount) | CounterChange::Set(amount) => amount,
+        }
+    }
+
+    // "+1", "-2", "set to 10" or "reset"
+    pub fn describe(&self) -> String {
+        match *self {
+            CounterChange::Add(amount) if amount >= 0 => format!("+{}", amount),
+            CounterChange::Add(amount) => amount.to_string(),
+            CounterChange::Set(0) => "reset".to_owned(),
+            CounterChange::Set(value) => format!("set to {}", value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Counter {
+    pub channel: String,
+    pub name: String,
+
+    // what the counter's command says, {count} and {name} are filled in
+    pub template: String,
+    pub value: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Counter {
+    pub fn render(&self) -> String {
+        self.template
+            .replace("{count}", &self.value.to_string())
+            .replace("{name}", &self.name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CounterEvent {
+    pub change: CounterChange,
+
+    // the counter's value right after
+    pub value: i64,
+    pub changed_by: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+// Which counters exist, kept in memory so any unknown &command can be checked against them
+// without a query. Values only live in the database, changes are done there in one statement
+// each so simultaneous ones can't get lost
+pub struct Counters {
+    db: Arc<dyn Database>,
+
+    // (channel, name)
+    names: RwLock<HashSet<(String, String)>>,
+}
+
+impl Counters {
+    pub async fn new(db: Arc<dyn Database>) -> Result<Self, DBError> {
+        let names = db.get_counter_names().await?;
+
+        Ok(Self {
+            db,
+            names: RwLock::new(names),
+        })
+    }
+
+    pub fn exists(&self, channel: &str, name: &str) -> bool {
+        self.names
+            .read()
+            .unwrap()
+            .contains(&(channel.to_owned(), name.to_owned()))
+    }
+
+    // the channel's counter names, alphabetically
+    pub fn list(&self, channel: &str) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .names
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(c, _)| c == channel)
+            .map(|(_, name)| name.clone())
+            .collect();
+        names.sort();
+
+        names
+    }
+
+    // false if the channel already has a counter by that name
+    pub async fn add(&self, counter: &Counter) -> Result<bool, DBError> {
+        let added = self.db.add_counter(counter).await?;
+        if added {
+            self.names
+                .write()
+                .unwrap()
+                .insert((counter.channel.clone(), counter.name.clone()));
+        }
+
+        Ok(added)
+    }
+
+    // false if the channel has no counter by that name
+    pub async fn delete(&self, channel: &str, name: &str) -> Result<bool, DBError> {
+        let deleted = self.db.delete_counter(channel, name).await?;
+        self.names
+            .write()
+            .unwrap()
+            .remove(&(channel.to_owned(), name.to_owned()));
+
+        Ok(deleted)
+    }
+
+    // Fills in every {counter:name} with that counter's current value. Placeholders for
+    // counters the channel doesn't have are left alone so whoever wrote them sees the typo
+    pub async fn fill(&self, channel: &str, text: &str) -> Result<String, DBError> {
+        let mut filled = String::new();
+        let mut rest = text;
+
+        while let Some(start) = rest.find(COUNTER_PLACEHOLDER) {
+            let after = &rest[start + COUNTER_PLACEHOLDER.len()..];
+            let end = match after.find('}') {
+                Some(end) => end,
+                None => break,
+            };
+            let name = &after[..end];
+
+            filled.push_str(&rest[..start]);
+            match self.db.get_counter(channel, name).await? {
+                Some(counter) => filled.push_str(&counter.value.to_string()),
+                None => filled.push_str(&rest[start..start + COUNTER_PLACEHOLDER.len() + end + 1]),
+            }
+            rest = &after[end + 1..];
+        }
+        filled.push_str(rest);
+
+        Ok(filled)
+    }
+}
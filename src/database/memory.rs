@@ -0,0 +1,1174 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use rand::seq::SliceRandom;
+use twitch_irc::message::PrivmsgMessage;
+
+use super::{
+    AfkRepository, ChannelRepository, CommandRepository, CounterRepository, DBError,
+    GameRepository, HealthCheck, LogRepository, NotificationRepository, PointsRepository,
+    QuoteRepository, ReminderRepository, StreamRepository, TimerRepository, UserRepository,
+};
+use crate::afk::AfkStatus;
+use crate::commands::Command;
+use crate::counters::{Counter, CounterChange, CounterEvent};
+use crate::emotestats::EmoteUsage;
+use crate::error::BotError;
+use crate::logging::UserActivity;
+use crate::notifications::NotificationKind;
+use crate::quotes::{number_quotes, Quote};
+use crate::reminders::Reminder;
+use crate::streams::StreamSession;
+use crate::timers::Timer;
+use crate::types::{parse_uid, PermissionLevel, UserContext};
+
+// display name, account creation date
+type UserDetails = (String, Option<DateTime<Utc>>);
+
+// session id, when, title, category
+type StreamChange = (i32, DateTime<Utc>, String, String);
+
+// login, points, times scored
+type GameScore = (String, i64, i32);
+
+pub struct LoggedMessage {
+    pub channel: String,
+    pub timestamp: i64,
+    pub uid: i32,
+    pub username: String,
+    pub message: String,
+}
+
+pub struct LoggedError {
+    pub command: String,
+    pub channel: String,
+    pub username: String,
+    pub error: String,
+}
+
+// Keeps everything in memory instead of Postgres, used to run the bot in tests.
+// Nothing here survives a restart.
+#[derive(Default)]
+pub struct MemoryDB {
+    // uid -> (username, permissions)
+    users: Mutex<HashMap<i32, (String, i32)>>,
+    user_details: Mutex<HashMap<i32, UserDetails>>,
+    channels: Mutex<HashMap<String, bool>>,
+    // name -> (about, permissions, user cooldown)
+    commands: Mutex<HashMap<String, (String, i32, u64)>>,
+    // (channel, command)
+    disabled_commands: Mutex<HashSet<(String, String)>>,
+    stream_sessions: Mutex<Vec<StreamSession>>,
+    stream_changes: Mutex<Vec<StreamChange>>,
+    // (channel, uid, kind) in the order they subscribed
+    notifications: Mutex<Vec<(String, i32, NotificationKind)>>,
+    reminders: Mutex<Vec<Reminder>>,
+    last_reminder_id: AtomicI32,
+    afk: Mutex<HashMap<i32, AfkStatus>>,
+    timers: Mutex<Vec<Timer>>,
+    last_timer_id: AtomicI32,
+    quotes: Mutex<Vec<Quote>>,
+    counters: Mutex<Vec<Counter>>,
+    // (channel, uid) -> (login, balance)
+    points: Mutex<HashMap<(String, i32), (String, i64)>>,
+    // (channel, game, uid) -> score
+    game_scores: Mutex<HashMap<(String, String, i32), GameScore>>,
+    // (channel, name, change) oldest first
+    counter_changes: Mutex<Vec<(String, String, CounterEvent)>>,
+    messages: Mutex<Vec<LoggedMessage>>,
+    // (channel, emote, provider, day) -> uses
+    emote_usage: Mutex<HashMap<(String, String, String, NaiveDate), i64>>,
+    // (model, prefix, next) -> count
+    markov: Mutex<HashMap<(String, String, String), i64>>,
+    markov_optouts: Mutex<HashSet<i32>>,
+    errors: Mutex<Vec<LoggedError>>,
+    unreachable: AtomicBool,
+}
+
+impl MemoryDB {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_command(&self, name: &str, about: &str, permissions: i32, user_cooldown: u64) {
+        self.commands.lock().unwrap().insert(
+            name.to_owned(),
+            (about.to_owned(), permissions, user_cooldown),
+        );
+    }
+
+    // pretends the database went down, only the health check notices
+    pub fn set_unreachable(&self, unreachable: bool) {
+        self.unreachable.store(unreachable, Ordering::Relaxed);
+    }
+
+    pub fn add_user(&self, uid: i32, username: &str, permissions: i32) {
+        self.users
+            .lock()
+            .unwrap()
+            .insert(uid, (username.to_lowercase(), permissions));
+    }
+
+    pub fn user_details(&self, uid: i32) -> Option<UserDetails> {
+        self.user_details.lock().unwrap().get(&uid).cloned()
+    }
+
+    pub fn stream_sessions(&self) -> Vec<StreamSession> {
+        self.stream_sessions.lock().unwrap().clone()
+    }
+
+    pub fn stream_change_count(&self) -> usize {
+        self.stream_changes.lock().unwrap().len()
+    }
+
+    pub fn logged_errors(&self) -> Vec<String> {
+        self.errors
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|e| format!("&{} #{} {}: {}", e.command, e.channel, e.username, e.error))
+            .collect()
+    }
+
+    pub fn message_count(&self) -> usize {
+        self.messages.lock().unwrap().len()
+    }
+}
+
+impl HealthCheck for MemoryDB {
+    fn is_healthy(&self) -> bool {
+        !self.unreachable.load(Ordering::Relaxed)
+    }
+}
+
+#[async_trait]
+impl UserRepository for MemoryDB {
+    async fn get_user_or_insert(&self, msg: &PrivmsgMessage) -> Result<UserContext, BotError> {
+        let uid = parse_uid(&msg.sender.id)?;
+        let mut users = self.users.lock().unwrap();
+        let (username, permissions) = users
+            .entry(uid)
+            .or_insert_with(|| (msg.sender.login.clone(), 0));
+
+        Ok(UserContext::new(uid, username.clone(), *permissions))
+    }
+
+    async fn get_user_by_uid(&self, uid: i32) -> Result<Option<UserContext>, DBError> {
+        Ok(self
+            .users
+            .lock()
+            .unwrap()
+            .get(&uid)
+            .map(|(username, permissions)| UserContext::new(uid, username.clone(), *permissions)))
+    }
+
+    async fn get_user_by_name(&self, name: &str) -> Result<Option<UserContext>, DBError> {
+        let name = name.to_lowercase();
+        Ok(self
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, (username, _))| *username == name)
+            .map(|(uid, (username, permissions))| {
+                UserContext::new(*uid, username.clone(), *permissions)
+            }))
+    }
+
+    async fn set_permissions_by_name(&self, name: &str, permissions: i32) -> Result<u64, DBError> {
+        let mut updated = 0;
+        for (username, user_permissions) in self.users.lock().unwrap().values_mut() {
+            if username == name {
+                *user_permissions = permissions;
+                updated += 1;
+            }
+        }
+
+        Ok(updated)
+    }
+
+    async fn update_user_details(
+        &self,
+        uid: i32,
+        username: &str,
+        display_name: &str,
+        created_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DBError> {
+        match self.users.lock().unwrap().get_mut(&uid) {
+            Some((user_username, _)) => *user_username = username.to_owned(),
+            None => return Ok(()),
+        }
+
+        let mut user_details = self.user_details.lock().unwrap();
+        let created_at = created_at.or_else(|| user_details.get(&uid).and_then(|(_, c)| *c));
+        user_details.insert(uid, (display_name.to_owned(), created_at));
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChannelRepository for MemoryDB {
+    async fn get_current_channels(&self) -> Result<HashSet<String>, DBError> {
+        Ok(self
+            .channels
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, joined)| **joined)
+            .map(|(channel, _)| channel.clone())
+            .collect())
+    }
+
+    async fn modify_or_insert_joined_value(
+        &self,
+        channel: &str,
+        new_joined_value: bool,
+    ) -> Result<(), DBError> {
+        self.channels
+            .lock()
+            .unwrap()
+            .insert(channel.to_owned(), new_joined_value);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CommandRepository for MemoryDB {
+    async fn get_current_commands(&self) -> Result<HashMap<String, Command>, DBError> {
+        Ok(self
+            .commands
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, (about, permissions, user_cooldown))| {
+                (
+                    name.clone(),
+                    Command::new(
+                        about.clone(),
+                        PermissionLevel::new(*permissions),
+                        *user_cooldown,
+                    ),
+                )
+            })
+            .collect())
+    }
+
+    async fn get_disabled_commands(&self) -> Result<HashSet<(String, String)>, DBError> {
+        Ok(self.disabled_commands.lock().unwrap().clone())
+    }
+
+    async fn set_command_disabled(
+        &self,
+        channel: &str,
+        command: &str,
+        disabled: bool,
+    ) -> Result<bool, DBError> {
+        let mut disabled_commands = self.disabled_commands.lock().unwrap();
+        let key = (channel.to_owned(), command.to_owned());
+        Ok(if disabled {
+            disabled_commands.insert(key)
+        } else {
+            disabled_commands.remove(&key)
+        })
+    }
+}
+
+#[async_trait]
+impl LogRepository for MemoryDB {
+    async fn log_message(&self, msg: &PrivmsgMessage) -> Result<(), BotError> {
+        let uid = parse_uid(&msg.sender.id)?;
+        self.messages.lock().unwrap().push(LoggedMessage {
+            channel: msg.channel_login.to_lowercase(),
+            timestamp: msg.server_timestamp.timestamp(),
+            uid,
+            username: msg.sender.login.clone(),
+            message: msg.message_text.clone(),
+        });
+
+        Ok(())
+    }
+
+    async fn log_error(
+        &self,
+        command: &str,
+        channel: &str,
+        username: &str,
+        error: &BotError,
+    ) -> Result<(), DBError> {
+        self.errors.lock().unwrap().push(LoggedError {
+            command: command.to_owned(),
+            channel: channel.to_owned(),
+            username: username.to_owned(),
+            error: error.to_string(),
+        });
+
+        Ok(())
+    }
+
+    async fn get_last_message_from_username(
+        &self,
+        channel: &str,
+        username: &str,
+        skip: i64,
+    ) -> Result<Option<(i64, String)>, BotError> {
+        let channel = channel.to_lowercase();
+        let username = username.to_lowercase();
+        Ok(self
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|m| m.channel == channel && m.username == username)
+            .nth(skip.max(0) as usize)
+            .map(|m| (m.timestamp, m.message.clone())))
+    }
+
+    async fn get_random_message_from_username(
+        &self,
+        channel: &str,
+        username: &str,
+    ) -> Result<Option<(i64, String)>, BotError> {
+        let channel = channel.to_lowercase();
+        let username = username.to_lowercase();
+        let messages = self.messages.lock().unwrap();
+        let candidates: Vec<&LoggedMessage> = messages
+            .iter()
+            .filter(|m| m.channel == channel && m.username == username)
+            .collect();
+
+        Ok(candidates
+            .choose(&mut rand::thread_rng())
+            .map(|m| (m.timestamp, m.message.clone())))
+    }
+
+    async fn get_random_message(
+        &self,
+        channel: &str,
+    ) -> Result<Option<(i64, String, String)>, BotError> {
+        let channel = channel.to_lowercase();
+        let messages = self.messages.lock().unwrap();
+        let candidates: Vec<&LoggedMessage> =
+            messages.iter().filter(|m| m.channel == channel).collect();
+
+        Ok(candidates
+            .choose(&mut rand::thread_rng())
+            .map(|m| (m.timestamp, m.username.clone(), m.message.clone())))
+    }
+
+    async fn get_user_activity(&self, uid: i32) -> Result<Option<UserActivity>, DBError> {
+        let sightings: Vec<(String, String, i64, i64)> = self
+            .messages
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| m.uid == uid)
+            .map(|m| {
+                (
+                    m.channel.clone(),
+                    m.username.clone(),
+                    m.timestamp,
+                    m.timestamp,
+                )
+            })
+            .collect();
+
+        Ok(UserActivity::from_sightings(&sightings))
+    }
+
+    async fn record_emote_usage(
+        &self,
+        channel: &str,
+        day: NaiveDate,
+        usage: &[EmoteUsage],
+    ) -> Result<(), DBError> {
+        let mut emote_usage = self.emote_usage.lock().unwrap();
+        for emote in usage {
+            *emote_usage
+                .entry((
+                    channel.to_owned(),
+                    emote.name.clone(),
+                    emote.provider.clone(),
+                    day,
+                ))
+                .or_insert(0) += emote.uses;
+        }
+
+        Ok(())
+    }
+
+    async fn get_emote_count(
+        &self,
+        channel: &str,
+        emote: &str,
+    ) -> Result<Option<(i64, NaiveDate)>, DBError> {
+        let emote_usage = self.emote_usage.lock().unwrap();
+        let days: Vec<(NaiveDate, i64)> = emote_usage
+            .iter()
+            .filter(|((c, e, _, _), _)| c == channel && e == emote)
+            .map(|((_, _, _, day), uses)| (*day, *uses))
+            .collect();
+
+        Ok(days
+            .iter()
+            .map(|(day, _)| *day)
+            .max()
+            .map(|last_used| (days.iter().map(|(_, uses)| uses).sum(), last_used)))
+    }
+
+    async fn get_top_emotes(
+        &self,
+        channel: &str,
+        since: Option<NaiveDate>,
+        limit: i64,
+    ) -> Result<Vec<(String, i64)>, DBError> {
+        let mut totals: HashMap<String, i64> = HashMap::new();
+        for ((c, emote, _, day), uses) in self.emote_usage.lock().unwrap().iter() {
+            if c == channel && since.is_none_or(|since| *day >= since) {
+                *totals.entry(emote.clone()).or_insert(0) += uses;
+            }
+        }
+
+        let mut totals: Vec<(String, i64)> = totals.into_iter().collect();
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        totals.truncate(limit as usize);
+
+        Ok(totals)
+    }
+
+    async fn get_emote_last_used(
+        &self,
+        channel: &str,
+    ) -> Result<HashMap<String, NaiveDate>, DBError> {
+        let mut last_used: HashMap<String, NaiveDate> = HashMap::new();
+        for ((c, emote, _, day), _) in self.emote_usage.lock().unwrap().iter() {
+            if c == channel {
+                let last = last_used.entry(emote.clone()).or_insert(*day);
+                *last = (*last).max(*day);
+            }
+        }
+
+        Ok(last_used)
+    }
+
+    async fn record_markov(
+        &self,
+        models: &[String],
+        transitions: &[(String, String, i64)],
+    ) -> Result<(), DBError> {
+        let mut markov = self.markov.lock().unwrap();
+        for model in models {
+            for (prefix, next, count) in transitions {
+                *markov
+                    .entry((model.clone(), prefix.clone(), next.clone()))
+                    .or_insert(0) += count;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_markov_transitions(
+        &self,
+        model: &str,
+        prefix: &str,
+    ) -> Result<Vec<(String, i64)>, DBError> {
+        Ok(self
+            .markov
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((m, p, _), _)| m == model && p == prefix)
+            .map(|((_, _, next), count)| (next.clone(), *count))
+            .collect())
+    }
+
+    async fn get_markov_prefixes(&self, model: &str, word: &str) -> Result<Vec<String>, DBError> {
+        let mut prefixes: Vec<String> = self
+            .markov
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|(m, p, _)| m == model && p.rsplit(' ').next() == Some(word))
+            .map(|(_, prefix, _)| prefix.clone())
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect();
+        prefixes.shuffle(&mut rand::thread_rng());
+
+        Ok(prefixes)
+    }
+
+    async fn delete_markov_model(&self, model: &str) -> Result<(), DBError> {
+        self.markov
+            .lock()
+            .unwrap()
+            .retain(|(m, _, _), _| m != model);
+        Ok(())
+    }
+
+    async fn get_markov_optouts(&self) -> Result<HashSet<i32>, DBError> {
+        Ok(self.markov_optouts.lock().unwrap().clone())
+    }
+
+    async fn set_markov_optout(&self, uid: i32, opted_out: bool) -> Result<bool, DBError> {
+        let mut optouts = self.markov_optouts.lock().unwrap();
+        Ok(if opted_out {
+            optouts.insert(uid)
+        } else {
+            optouts.remove(&uid)
+        })
+    }
+}
+
+#[async_trait]
+impl StreamRepository for MemoryDB {
+    async fn start_stream_session(
+        &self,
+        channel: &str,
+        stream_id: &str,
+        started_at: DateTime<Utc>,
+        title: &str,
+        category: &str,
+    ) -> Result<i32, DBError> {
+        let mut sessions = self.stream_sessions.lock().unwrap();
+        let id = sessions.len() as i32 + 1;
+        sessions.push(StreamSession {
+            id,
+            channel: channel.to_owned(),
+            stream_id: stream_id.to_owned(),
+            started_at,
+            ended_at: None,
+            title: title.to_owned(),
+            category: category.to_owned(),
+        });
+
+        Ok(id)
+    }
+
+    async fn end_stream_session(&self, id: i32, ended_at: DateTime<Utc>) -> Result<(), DBError> {
+        if let Some(session) = self
+            .stream_sessions
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|s| s.id == id)
+        {
+            session.ended_at = Some(ended_at);
+        }
+
+        Ok(())
+    }
+
+    async fn record_stream_change(
+        &self,
+        id: i32,
+        changed_at: DateTime<Utc>,
+        title: &str,
+        category: &str,
+    ) -> Result<(), DBError> {
+        self.stream_changes.lock().unwrap().push((
+            id,
+            changed_at,
+            title.to_owned(),
+            category.to_owned(),
+        ));
+        if let Some(session) = self
+            .stream_sessions
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|s| s.id == id)
+        {
+            session.title = title.to_owned();
+            session.category = category.to_owned();
+        }
+
+        Ok(())
+    }
+
+    async fn get_open_stream_sessions(&self) -> Result<Vec<StreamSession>, DBError> {
+        Ok(self
+            .stream_sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.ended_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn get_last_stream_session(
+        &self,
+        channel: &str,
+    ) -> Result<Option<StreamSession>, DBError> {
+        Ok(self
+            .stream_sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.channel == channel)
+            .max_by_key(|s| s.started_at)
+            .cloned())
+    }
+
+    async fn get_stream_sessions_between(
+        &self,
+        channel: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StreamSession>, DBError> {
+        let mut sessions: Vec<StreamSession> = self
+            .stream_sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| {
+                s.channel == channel
+                    && s.started_at < to
+                    && s.ended_at.is_none_or(|ended_at| ended_at > from)
+            })
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|s| s.started_at);
+
+        Ok(sessions)
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for MemoryDB {
+    async fn add_notification(
+        &self,
+        channel: &str,
+        uid: i32,
+        kind: NotificationKind,
+    ) -> Result<bool, DBError> {
+        let mut notifications = self.notifications.lock().unwrap();
+        let notification = (channel.to_owned(), uid, kind);
+        if notifications.contains(&notification) {
+            return Ok(false);
+        }
+
+        notifications.push(notification);
+        Ok(true)
+    }
+
+    async fn remove_notifications(
+        &self,
+        channel: &str,
+        uid: i32,
+        kind: Option<NotificationKind>,
+    ) -> Result<u64, DBError> {
+        let mut notifications = self.notifications.lock().unwrap();
+        let before = notifications.len();
+        notifications
+            .retain(|(c, u, k)| !(c == channel && *u == uid && kind.is_none_or(|kind| kind == *k)));
+
+        Ok((before - notifications.len()) as u64)
+    }
+
+    async fn get_notified_users(
+        &self,
+        channel: &str,
+        kind: NotificationKind,
+    ) -> Result<Vec<String>, DBError> {
+        let users = self.users.lock().unwrap();
+        Ok(self
+            .notifications
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(c, _, k)| c == channel && *k == kind)
+            .filter_map(|(_, uid, _)| users.get(uid).map(|(username, _)| username.clone()))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl ReminderRepository for MemoryDB {
+    async fn add_reminder(&self, reminder: &Reminder) -> Result<i32, DBError> {
+        let id = self.last_reminder_id.fetch_add(1, Ordering::SeqCst) + 1;
+        self.reminders.lock().unwrap().push(Reminder {
+            id,
+            ..reminder.clone()
+        });
+
+        Ok(id)
+    }
+
+    async fn count_pending_reminders(&self, author_uid: i32) -> Result<i64, DBError> {
+        Ok(self
+            .reminders
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.author_uid == author_uid)
+            .count() as i64)
+    }
+
+    async fn get_chat_reminder_targets(&self) -> Result<HashSet<i32>, DBError> {
+        Ok(self
+            .reminders
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.due_at.is_none())
+            .map(|r| r.target_uid)
+            .collect())
+    }
+
+    async fn get_chat_reminders(&self, target_uid: i32) -> Result<Vec<Reminder>, DBError> {
+        Ok(self
+            .reminders
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.target_uid == target_uid && r.due_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn get_next_reminder_due(&self) -> Result<Option<DateTime<Utc>>, DBError> {
+        Ok(self
+            .reminders
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|r| r.due_at)
+            .min())
+    }
+
+    async fn get_due_reminders(&self, until: DateTime<Utc>) -> Result<Vec<Reminder>, DBError> {
+        Ok(self
+            .reminders
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.due_at.is_some_and(|due_at| due_at <= until))
+            .cloned()
+            .collect())
+    }
+
+    async fn cancel_reminder(&self, id: i32, author_uid: Option<i32>) -> Result<bool, DBError> {
+        let mut reminders = self.reminders.lock().unwrap();
+        let before = reminders.len();
+        reminders.retain(|r| !(r.id == id && author_uid.is_none_or(|uid| uid == r.author_uid)));
+
+        Ok(reminders.len() < before)
+    }
+}
+
+#[async_trait]
+impl AfkRepository for MemoryDB {
+    async fn set_afk(&self, status: &AfkStatus) -> Result<(), DBError> {
+        self.afk.lock().unwrap().insert(status.uid, status.clone());
+        Ok(())
+    }
+
+    async fn remove_afk(&self, uid: i32) -> Result<bool, DBError> {
+        Ok(self.afk.lock().unwrap().remove(&uid).is_some())
+    }
+
+    async fn get_afk_statuses(&self) -> Result<Vec<AfkStatus>, DBError> {
+        Ok(self.afk.lock().unwrap().values().cloned().collect())
+    }
+}
+
+#[async_trait]
+impl TimerRepository for MemoryDB {
+    async fn add_timer(&self, timer: &Timer) -> Result<i32, DBError> {
+        let id = self.last_timer_id.fetch_add(1, Ordering::SeqCst) + 1;
+        self.timers.lock().unwrap().push(Timer {
+            id,
+            ..timer.clone()
+        });
+
+        Ok(id)
+    }
+
+    async fn get_timers(&self) -> Result<Vec<Timer>, DBError> {
+        Ok(self.timers.lock().unwrap().clone())
+    }
+
+    async fn set_timer_paused(&self, id: i32, paused: bool) -> Result<(), DBError> {
+        if let Some(timer) = self.timers.lock().unwrap().iter_mut().find(|t| t.id == id) {
+            timer.paused = paused;
+        }
+
+        Ok(())
+    }
+
+    async fn set_timer_sent(&self, id: i32, sent_at: DateTime<Utc>) -> Result<(), DBError> {
+        if let Some(timer) = self.timers.lock().unwrap().iter_mut().find(|t| t.id == id) {
+            timer.last_sent_at = Some(sent_at);
+        }
+
+        Ok(())
+    }
+
+    async fn delete_timer(&self, id: i32) -> Result<(), DBError> {
+        self.timers.lock().unwrap().retain(|t| t.id != id);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl QuoteRepository for MemoryDB {
+    async fn add_quote(&self, quote: &Quote) -> Result<i32, DBError> {
+        let mut quotes = self.quotes.lock().unwrap();
+        let id = quotes
+            .iter()
+            .filter(|q| q.channel == quote.channel)
+            .map(|q| q.id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        quotes.push(Quote {
+            id,
+            ..quote.clone()
+        });
+
+        Ok(id)
+    }
+
+    async fn import_quotes(&self, quotes: &[Quote]) -> Result<u64, DBError> {
+        let mut saved = self.quotes.lock().unwrap();
+        let channels: HashSet<&str> = quotes.iter().map(|q| q.channel.as_str()).collect();
+        for channel in channels {
+            let mut taken: HashSet<i32> = saved
+                .iter()
+                .filter(|q| q.channel == channel)
+                .map(|q| q.id)
+                .collect();
+            let imported: Vec<Quote> = quotes
+                .iter()
+                .filter(|q| q.channel == channel)
+                .cloned()
+                .collect();
+            saved.extend(number_quotes(&mut taken, &imported));
+        }
+
+        Ok(quotes.len() as u64)
+    }
+
+    async fn get_quote(&self, channel: &str, id: i32) -> Result<Option<Quote>, DBError> {
+        Ok(self
+            .quotes
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|q| q.channel == channel && q.id == id)
+            .cloned())
+    }
+
+    async fn get_random_quote(
+        &self,
+        channel: &str,
+        quoted: Option<&str>,
+        search: Option<&str>,
+    ) -> Result<Option<Quote>, DBError> {
+        let search = search.map(|search| search.to_lowercase());
+        let quotes = self.quotes.lock().unwrap();
+        let candidates: Vec<&Quote> = quotes
+            .iter()
+            .filter(|q| q.channel == channel)
+            .filter(|q| quoted.is_none_or(|quoted| q.quoted == quoted))
+            .filter(|q| {
+                search
+                    .as_ref()
+                    .is_none_or(|search| q.text.to_lowercase().contains(search))
+            })
+            .collect();
+
+        Ok(candidates
+            .choose(&mut rand::thread_rng())
+            .map(|q| (*q).clone()))
+    }
+
+    async fn get_quotes(&self, channel: &str) -> Result<Vec<Quote>, DBError> {
+        let mut quotes: Vec<Quote> = self
+            .quotes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|q| q.channel == channel)
+            .cloned()
+            .collect();
+        quotes.sort_by_key(|q| q.id);
+
+        Ok(quotes)
+    }
+
+    async fn delete_quote(&self, channel: &str, id: i32) -> Result<bool, DBError> {
+        let mut quotes = self.quotes.lock().unwrap();
+        let before = quotes.len();
+        quotes.retain(|q| !(q.channel == channel && q.id == id));
+
+        Ok(quotes.len() < before)
+    }
+}
+
+#[async_trait]
+impl CounterRepository for MemoryDB {
+    async fn add_counter(&self, counter: &Counter) -> Result<bool, DBError> {
+        let mut counters = self.counters.lock().unwrap();
+        if counters
+            .iter()
+            .any(|c| c.channel == counter.channel && c.name == counter.name)
+        {
+            return Ok(false);
+        }
+        counters.push(counter.clone());
+
+        Ok(true)
+    }
+
+    async fn delete_counter(&self, channel: &str, name: &str) -> Result<bool, DBError> {
+        let mut counters = self.counters.lock().unwrap();
+        let before = counters.len();
+        counters.retain(|c| !(c.channel == channel && c.name == name));
+        self.counter_changes
+            .lock()
+            .unwrap()
+            .retain(|(c, n, _)| !(c == channel && n == name));
+
+        Ok(counters.len() < before)
+    }
+
+    async fn get_counter_names(&self) -> Result<HashSet<(String, String)>, DBError> {
+        Ok(self
+            .counters
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|c| (c.channel.clone(), c.name.clone()))
+            .collect())
+    }
+
+    async fn get_counter(&self, channel: &str, name: &str) -> Result<Option<Counter>, DBError> {
+        Ok(self
+            .counters
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|c| c.channel == channel && c.name == name)
+            .cloned())
+    }
+
+    async fn change_counter(
+        &self,
+        channel: &str,
+        name: &str,
+        change: CounterChange,
+        changed_by: &str,
+    ) -> Result<Option<i64>, DBError> {
+        let mut counters = self.counters.lock().unwrap();
+        let counter = match counters
+            .iter_mut()
+            .find(|c| c.channel == channel && c.name == name)
+        {
+            Some(counter) => counter,
+            None => return Ok(None),
+        };
+        counter.value = match change {
+            CounterChange::Add(amount) => counter.value + amount,
+            CounterChange::Set(value) => value,
+        };
+        self.counter_changes.lock().unwrap().push((
+            channel.to_owned(),
+            name.to_owned(),
+            CounterEvent {
+                change,
+                value: counter.value,
+                changed_by: changed_by.to_owned(),
+                changed_at: Utc::now(),
+            },
+        ));
+
+        Ok(Some(counter.value))
+    }
+
+    async fn get_counter_history(
+        &self,
+        channel: &str,
+        name: &str,
+        limit: i64,
+    ) -> Result<Vec<CounterEvent>, DBError> {
+        Ok(self
+            .counter_changes
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|(c, n, _)| c == channel && n == name)
+            .take(limit.max(0) as usize)
+            .map(|(_, _, event)| event.clone())
+            .collect())
+    }
+}
+
+#[async_trait]
+impl PointsRepository for MemoryDB {
+    async fn add_points(
+        &self,
+        channel: &str,
+        uid: i32,
+        login: &str,
+        amount: i64,
+    ) -> Result<i64, DBError> {
+        let mut points = self.points.lock().unwrap();
+        let entry = points
+            .entry((channel.to_owned(), uid))
+            .or_insert_with(|| (login.to_lowercase(), 0));
+        entry.0 = login.to_lowercase();
+        entry.1 += amount;
+
+        Ok(entry.1)
+    }
+
+    async fn get_points(&self, channel: &str, uid: i32) -> Result<i64, DBError> {
+        Ok(self
+            .points
+            .lock()
+            .unwrap()
+            .get(&(channel.to_owned(), uid))
+            .map(|(_, balance)| *balance)
+            .unwrap_or(0))
+    }
+
+    async fn wager_points(
+        &self,
+        channel: &str,
+        uid: i32,
+        stake: i64,
+        delta: i64,
+    ) -> Result<Option<i64>, DBError> {
+        let mut points = self.points.lock().unwrap();
+        Ok(match points.get_mut(&(channel.to_owned(), uid)) {
+            Some((_, balance)) if *balance >= stake => {
+                *balance += delta;
+                Some(*balance)
+            }
+            _ => None,
+        })
+    }
+
+    async fn transfer_points(
+        &self,
+        channel: &str,
+        from_uid: i32,
+        to_uid: i32,
+        to_login: &str,
+        amount: i64,
+        to_stake: i64,
+    ) -> Result<Option<(i64, i64)>, DBError> {
+        let mut points = self.points.lock().unwrap();
+        let from_key = (channel.to_owned(), from_uid);
+        let to_key = (channel.to_owned(), to_uid);
+        let from_balance = points.get(&from_key).map(|(_, b)| *b).unwrap_or(0);
+        let to_balance = points.get(&to_key).map(|(_, b)| *b).unwrap_or(0);
+        if from_balance < amount || to_balance < to_stake {
+            return Ok(None);
+        }
+
+        if let Some((_, balance)) = points.get_mut(&from_key) {
+            *balance -= amount;
+        }
+        points
+            .entry(to_key)
+            .or_insert_with(|| (to_login.to_lowercase(), 0))
+            .1 += amount;
+
+        Ok(Some((from_balance - amount, to_balance + amount)))
+    }
+
+    async fn get_top_points(
+        &self,
+        channel: &str,
+        limit: i64,
+    ) -> Result<Vec<(String, i64)>, DBError> {
+        let mut top: Vec<(String, i64)> = self
+            .points
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((c, _), (_, balance))| c == channel && *balance > 0)
+            .map(|(_, (login, balance))| (login.clone(), *balance))
+            .collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(limit.max(0) as usize);
+
+        Ok(top)
+    }
+
+    async fn reset_points(&self, channel: &str, uid: Option<i32>) -> Result<u64, DBError> {
+        let mut points = self.points.lock().unwrap();
+        let before = points.len();
+        points.retain(|(c, u), _| !(c == channel && uid.is_none_or(|uid| *u == uid)));
+
+        Ok((before - points.len()) as u64)
+    }
+}
+
+#[async_trait]
+impl GameRepository for MemoryDB {
+    async fn add_game_score(
+        &self,
+        channel: &str,
+        game: &str,
+        uid: i32,
+        login: &str,
+        points: i64,
+    ) -> Result<i64, DBError> {
+        let mut scores = self.game_scores.lock().unwrap();
+        let entry = scores
+            .entry((channel.to_owned(), game.to_owned(), uid))
+            .or_insert_with(|| (login.to_lowercase(), 0, 0));
+        entry.0 = login.to_lowercase();
+        entry.1 += points;
+        entry.2 += 1;
+
+        Ok(entry.1)
+    }
+
+    async fn get_game_score(
+        &self,
+        channel: &str,
+        game: &str,
+        uid: i32,
+    ) -> Result<(i64, i32), DBError> {
+        Ok(self
+            .game_scores
+            .lock()
+            .unwrap()
+            .get(&(channel.to_owned(), game.to_owned(), uid))
+            .map(|(_, points, scored)| (*points, *scored))
+            .unwrap_or((0, 0)))
+    }
+
+    async fn get_top_game_scores(
+        &self,
+        channel: &str,
+        game: &str,
+        limit: i64,
+    ) -> Result<Vec<(String, i64)>, DBError> {
+        let mut top: Vec<(String, i64)> = self
+            .game_scores
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((c, g, _), _)| c == channel && g == game)
+            .map(|(_, (login, points, _))| (login.clone(), *points))
+            .collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(limit.max(0) as usize);
+
+        Ok(top)
+    }
+}
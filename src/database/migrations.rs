@@ -0,0 +1,240 @@
+use std::fmt;
+
+use tokio_postgres::Client;
+
+use super::DBError;
+
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+// Migrations are embedded into the binary and applied in order, the version of the last applied
+// migration is tracked in each database's schema_version table
+pub const MAIN: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: include_str!("../../migrations/main/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "user_details",
+        sql: include_str!("../../migrations/main/0002_user_details.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "stream_sessions",
+        sql: include_str!("../../migrations/main/0003_stream_sessions.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "notifications",
+        sql: include_str!("../../migrations/main/0004_notifications.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "age_commands",
+        sql: include_str!("../../migrations/main/0005_age_commands.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "user_command",
+        sql: include_str!("../../migrations/main/0006_user_command.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "emote_commands",
+        sql: include_str!("../../migrations/main/0007_emote_commands.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "emote_stats_commands",
+        sql: include_str!("../../migrations/main/0008_emote_stats_commands.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "reminders",
+        sql: include_str!("../../migrations/main/0009_reminders.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "afk",
+        sql: include_str!("../../migrations/main/0010_afk.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "timers",
+        sql: include_str!("../../migrations/main/0011_timers.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "markov_command",
+        sql: include_str!("../../migrations/main/0012_markov_command.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "quotes",
+        sql: include_str!("../../migrations/main/0013_quotes.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "counters",
+        sql: include_str!("../../migrations/main/0014_counters.sql"),
+    },
+    Migration {
+        version: 15,
+        name: "points",
+        sql: include_str!("../../migrations/main/0015_points.sql"),
+    },
+    Migration {
+        version: 16,
+        name: "poll_commands",
+        sql: include_str!("../../migrations/main/0016_poll_commands.sql"),
+    },
+    Migration {
+        version: 17,
+        name: "trivia",
+        sql: include_str!("../../migrations/main/0017_trivia.sql"),
+    },
+    Migration {
+        version: 18,
+        name: "game_commands",
+        sql: include_str!("../../migrations/main/0018_game_commands.sql"),
+    },
+];
+
+pub const LOGS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: include_str!("../../migrations/logs/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "error_log",
+        sql: include_str!("../../migrations/logs/0002_error_log.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "user_id_index",
+        sql: include_str!("../../migrations/logs/0003_user_id_index.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "emote_usage",
+        sql: include_str!("../../migrations/logs/0004_emote_usage.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "markov",
+        sql: include_str!("../../migrations/logs/0005_markov.sql"),
+    },
+];
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Database(DBError),
+
+    // The database has been migrated by a newer build than this one
+    UnknownVersion { found: i32, known: i32 },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Database(e) => write!(f, "error while migrating: {}", e),
+            MigrationError::UnknownVersion { found, known } => write!(
+                f,
+                "database schema is at version {} but this build only knows up to version {}, \
+                refusing to start",
+                found, known
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<DBError> for MigrationError {
+    fn from(e: DBError) -> Self {
+        MigrationError::Database(e)
+    }
+}
+
+impl From<tokio_postgres::Error> for MigrationError {
+    fn from(e: tokio_postgres::Error) -> Self {
+        MigrationError::Database(e.into())
+    }
+}
+
+pub fn latest_version(migrations: &[Migration]) -> i32 {
+    migrations.iter().map(|m| m.version).max().unwrap_or(0)
+}
+
+pub async fn current_version(client: &Client) -> Result<i32, MigrationError> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_version (\
+                version INTEGER PRIMARY KEY, \
+                name TEXT NOT NULL, \
+                applied_at TIMESTAMPTZ NOT NULL DEFAULT now())",
+        )
+        .await?;
+
+    let row = client
+        .query_one("SELECT COALESCE(MAX(version), 0) FROM schema_version", &[])
+        .await?;
+
+    Ok(row.get(0))
+}
+
+// key of the advisory lock held while migrating, any number will do as long as it never changes
+const MIGRATION_LOCK: i64 = 0x626f_7272_6f77;
+
+// Applies every migration newer than the database's current version, each inside its own
+// transaction. Returns the version the database is at afterwards. Holds an advisory lock while
+// it does, so `borrowbot migrate` and a starting bot can't both apply the same migration
+pub async fn run(client: &mut Client, migrations: &[Migration]) -> Result<i32, MigrationError> {
+    client
+        .execute("SELECT pg_advisory_lock($1)", &[&MIGRATION_LOCK])
+        .await?;
+    let migrated = apply(client, migrations).await;
+    client
+        .execute("SELECT pg_advisory_unlock($1)", &[&MIGRATION_LOCK])
+        .await?;
+
+    migrated
+}
+
+async fn apply(client: &mut Client, migrations: &[Migration]) -> Result<i32, MigrationError> {
+    let known = latest_version(migrations);
+    let current = current_version(client).await?;
+
+    if current > known {
+        return Err(MigrationError::UnknownVersion {
+            found: current,
+            known,
+        });
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        let transaction = client.transaction().await?;
+        transaction.batch_execute(migration.sql).await?;
+        transaction
+            .execute(
+                "INSERT INTO schema_version (version, name) VALUES ($1, $2)",
+                &[&migration.version, &migration.name],
+            )
+            .await?;
+        transaction.commit().await?;
+
+        eprintln!(
+            "Applied migration {} ({})",
+            migration.version, migration.name
+        );
+    }
+
+    Ok(known)
+}
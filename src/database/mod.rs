@@ -0,0 +1,455 @@
+pub mod memory;
+pub mod migrations;
+pub mod pool;
+pub mod postgres;
+
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::afk::AfkStatus;
+use crate::commands::Command;
+use crate::counters::{Counter, CounterChange, CounterEvent};
+use crate::emotestats::EmoteUsage;
+use crate::error::BotError;
+use crate::logging::UserActivity;
+use crate::notifications::NotificationKind;
+use crate::quotes::Quote;
+use crate::reminders::Reminder;
+use crate::streams::StreamSession;
+use crate::timers::Timer;
+use crate::types::UserContext;
+pub use memory::MemoryDB;
+pub use pool::{DBError, PgPool};
+pub use postgres::DBController;
+
+// Everything the bot persists goes through these traits, the Postgres controllers are used in
+// production and MemoryDB lets the bot run without a database in tests.
+
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn get_user_or_insert(&self, msg: &PrivmsgMessage) -> Result<UserContext, BotError>;
+
+    async fn get_user_by_uid(&self, uid: i32) -> Result<Option<UserContext>, DBError>;
+
+    async fn get_user_by_name(&self, name: &str) -> Result<Option<UserContext>, DBError>;
+
+    // returns the amount of users updated, 0 if nobody has that name
+    async fn set_permissions_by_name(&self, name: &str, permissions: i32) -> Result<u64, DBError>;
+
+    // keeps a known user's record in line with helix, does nothing for users we haven't seen
+    async fn update_user_details(
+        &self,
+        uid: i32,
+        username: &str,
+        display_name: &str,
+        created_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DBError>;
+}
+
+#[async_trait]
+pub trait ChannelRepository: Send + Sync {
+    async fn get_current_channels(&self) -> Result<HashSet<String>, DBError>;
+
+    // Used for join & leave commands, if joining a channel that is not in the database already, it
+    // will insert it with the value of true for joined
+    async fn modify_or_insert_joined_value(
+        &self,
+        channel: &str,
+        new_joined_value: bool,
+    ) -> Result<(), DBError>;
+}
+
+#[async_trait]
+pub trait CommandRepository: Send + Sync {
+    async fn get_current_commands(&self) -> Result<HashMap<String, Command>, DBError>;
+
+    // (channel, command) for every command turned off in a channel
+    async fn get_disabled_commands(&self) -> Result<HashSet<(String, String)>, DBError>;
+
+    // false if the command already was in that state
+    async fn set_command_disabled(
+        &self,
+        channel: &str,
+        command: &str,
+        disabled: bool,
+    ) -> Result<bool, DBError>;
+}
+
+// whether the backend could reach its database the last time it checked
+pub trait HealthCheck {
+    fn is_healthy(&self) -> bool;
+}
+
+#[async_trait]
+pub trait LogRepository: HealthCheck + Send + Sync {
+    async fn log_message(&self, msg: &PrivmsgMessage) -> Result<(), BotError>;
+
+    // internal record of a command that failed, the user only sees BotError::user_message
+    async fn log_error(
+        &self,
+        command: &str,
+        channel: &str,
+        username: &str,
+        error: &BotError,
+    ) -> Result<(), DBError>;
+
+    // skipping that many of the user's newer messages
+    async fn get_last_message_from_username(
+        &self,
+        channel: &str,
+        username: &str,
+        skip: i64,
+    ) -> Result<Option<(i64, String)>, BotError>;
+
+    async fn get_random_message_from_username(
+        &self,
+        channel: &str,
+        username: &str,
+    ) -> Result<Option<(i64, String)>, BotError>;
+
+    async fn get_random_message(
+        &self,
+        channel: &str,
+    ) -> Result<Option<(i64, String, String)>, BotError>;
+
+    // everything the logs know about a user across all channels, None if they never chatted
+    async fn get_user_activity(&self, uid: i32) -> Result<Option<UserActivity>, DBError>;
+
+    // adds to the emotes' counters for that day
+    async fn record_emote_usage(
+        &self,
+        channel: &str,
+        day: NaiveDate,
+        usage: &[EmoteUsage],
+    ) -> Result<(), DBError>;
+
+    // total uses across every provider and the last day it was used, None if it never was
+    async fn get_emote_count(
+        &self,
+        channel: &str,
+        emote: &str,
+    ) -> Result<Option<(i64, NaiveDate)>, DBError>;
+
+    // most used emotes since the given day, or ever, most used first
+    async fn get_top_emotes(
+        &self,
+        channel: &str,
+        since: Option<NaiveDate>,
+        limit: i64,
+    ) -> Result<Vec<(String, i64)>, DBError>;
+
+    // emote name -> last day anyone used it
+    async fn get_emote_last_used(
+        &self,
+        channel: &str,
+    ) -> Result<HashMap<String, NaiveDate>, DBError>;
+
+    // adds (prefix, next word, times) to each of the models
+    async fn record_markov(
+        &self,
+        models: &[String],
+        transitions: &[(String, String, i64)],
+    ) -> Result<(), DBError>;
+
+    // every word that followed the prefix and how often
+    async fn get_markov_transitions(
+        &self,
+        model: &str,
+        prefix: &str,
+    ) -> Result<Vec<(String, i64)>, DBError>;
+
+    // some of the prefixes ending in the word, in random order
+    async fn get_markov_prefixes(&self, model: &str, word: &str) -> Result<Vec<String>, DBError>;
+
+    async fn delete_markov_model(&self, model: &str) -> Result<(), DBError>;
+
+    async fn get_markov_optouts(&self) -> Result<HashSet<i32>, DBError>;
+
+    // false if the user already was in that state
+    async fn set_markov_optout(&self, uid: i32, opted_out: bool) -> Result<bool, DBError>;
+}
+
+#[async_trait]
+pub trait StreamRepository: Send + Sync {
+    // returns the id of the new session
+    async fn start_stream_session(
+        &self,
+        channel: &str,
+        stream_id: &str,
+        started_at: DateTime<Utc>,
+        title: &str,
+        category: &str,
+    ) -> Result<i32, DBError>;
+
+    async fn end_stream_session(&self, id: i32, ended_at: DateTime<Utc>) -> Result<(), DBError>;
+
+    // the session itself always holds the latest title and category
+    async fn record_stream_change(
+        &self,
+        id: i32,
+        changed_at: DateTime<Utc>,
+        title: &str,
+        category: &str,
+    ) -> Result<(), DBError>;
+
+    async fn get_open_stream_sessions(&self) -> Result<Vec<StreamSession>, DBError>;
+
+    async fn get_last_stream_session(
+        &self,
+        channel: &str,
+    ) -> Result<Option<StreamSession>, DBError>;
+
+    // every session overlapping the range, oldest first
+    async fn get_stream_sessions_between(
+        &self,
+        channel: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StreamSession>, DBError>;
+}
+
+#[async_trait]
+pub trait NotificationRepository: Send + Sync {
+    // false if the user was already subscribed
+    async fn add_notification(
+        &self,
+        channel: &str,
+        uid: i32,
+        kind: NotificationKind,
+    ) -> Result<bool, DBError>;
+
+    // removes every kind when none is given, returns how many were removed
+    async fn remove_notifications(
+        &self,
+        channel: &str,
+        uid: i32,
+        kind: Option<NotificationKind>,
+    ) -> Result<u64, DBError>;
+
+    // logins of everyone to ping, in the order they subscribed
+    async fn get_notified_users(
+        &self,
+        channel: &str,
+        kind: NotificationKind,
+    ) -> Result<Vec<String>, DBError>;
+}
+
+#[async_trait]
+pub trait ReminderRepository: Send + Sync {
+    // the reminder's id is ignored, returns the one it was saved under
+    async fn add_reminder(&self, reminder: &Reminder) -> Result<i32, DBError>;
+
+    async fn count_pending_reminders(&self, author_uid: i32) -> Result<i64, DBError>;
+
+    // everyone with reminders waiting for their next chat message
+    async fn get_chat_reminder_targets(&self) -> Result<HashSet<i32>, DBError>;
+
+    // the user's reminders that wait for them to chat, oldest first. They stay saved until
+    // they are delivered and cancelled
+    async fn get_chat_reminders(&self, target_uid: i32) -> Result<Vec<Reminder>, DBError>;
+
+    async fn get_next_reminder_due(&self) -> Result<Option<DateTime<Utc>>, DBError>;
+
+    // every timed reminder due by then, oldest first
+    async fn get_due_reminders(&self, until: DateTime<Utc>) -> Result<Vec<Reminder>, DBError>;
+
+    // only removes reminders of the given author, anyone's when there is none
+    async fn cancel_reminder(&self, id: i32, author_uid: Option<i32>) -> Result<bool, DBError>;
+}
+
+#[async_trait]
+pub trait AfkRepository: Send + Sync {
+    // replaces whatever status the user had before
+    async fn set_afk(&self, status: &AfkStatus) -> Result<(), DBError>;
+
+    // false if the user wasn't away
+    async fn remove_afk(&self, uid: i32) -> Result<bool, DBError>;
+
+    async fn get_afk_statuses(&self) -> Result<Vec<AfkStatus>, DBError>;
+}
+
+#[async_trait]
+pub trait TimerRepository: Send + Sync {
+    // the timer's id is ignored, returns the one it was saved under
+    async fn add_timer(&self, timer: &Timer) -> Result<i32, DBError>;
+
+    async fn get_timers(&self) -> Result<Vec<Timer>, DBError>;
+
+    async fn set_timer_paused(&self, id: i32, paused: bool) -> Result<(), DBError>;
+
+    async fn set_timer_sent(&self, id: i32, sent_at: DateTime<Utc>) -> Result<(), DBError>;
+
+    async fn delete_timer(&self, id: i32) -> Result<(), DBError>;
+}
+
+#[async_trait]
+pub trait QuoteRepository: Send + Sync {
+    // returns the id it was saved under
+    async fn add_quote(&self, quote: &Quote) -> Result<i32, DBError>;
+
+    // all or nothing, returns how many were saved
+    async fn import_quotes(&self, quotes: &[Quote]) -> Result<u64, DBError>;
+
+    async fn get_quote(&self, channel: &str, id: i32) -> Result<Option<Quote>, DBError>;
+
+    // a random one of the channel's quotes, narrowed down to who said it and/or what it
+    // contains (case insensitive)
+    async fn get_random_quote(
+        &self,
+        channel: &str,
+        quoted: Option<&str>,
+        search: Option<&str>,
+    ) -> Result<Option<Quote>, DBError>;
+
+    // oldest first
+    async fn get_quotes(&self, channel: &str) -> Result<Vec<Quote>, DBError>;
+
+    // false if the channel has no quote with that id
+    async fn delete_quote(&self, channel: &str, id: i32) -> Result<bool, DBError>;
+}
+
+#[async_trait]
+pub trait PointsRepository: Send + Sync {
+    // gives the user points in the channel, returns their new balance
+    async fn add_points(
+        &self,
+        channel: &str,
+        uid: i32,
+        login: &str,
+        amount: i64,
+    ) -> Result<i64, DBError>;
+
+    async fn get_points(&self, channel: &str, uid: i32) -> Result<i64, DBError>;
+
+    // Changes the balance by delta if the user has at least stake, returns the new balance or
+    // None if they didn't have enough
+    async fn wager_points(
+        &self,
+        channel: &str,
+        uid: i32,
+        stake: i64,
+        delta: i64,
+    ) -> Result<Option<i64>, DBError>;
+
+    // Moves amount from one user to another if the sender has it and the receiver has at least
+    // to_stake, returns both new balances or None if either was short
+    async fn transfer_points(
+        &self,
+        channel: &str,
+        from_uid: i32,
+        to_uid: i32,
+        to_login: &str,
+        amount: i64,
+        to_stake: i64,
+    ) -> Result<Option<(i64, i64)>, DBError>;
+
+    // (login, balance), richest first
+    async fn get_top_points(
+        &self,
+        channel: &str,
+        limit: i64,
+    ) -> Result<Vec<(String, i64)>, DBError>;
+
+    // everyone's balance in the channel if uid is None, returns how many were reset
+    async fn reset_points(&self, channel: &str, uid: Option<i32>) -> Result<u64, DBError>;
+}
+
+#[async_trait]
+pub trait CounterRepository: Send + Sync {
+    // false if the channel already has a counter by that name
+    async fn add_counter(&self, counter: &Counter) -> Result<bool, DBError>;
+
+    // false if the channel has no counter by that name, its history goes with it
+    async fn delete_counter(&self, channel: &str, name: &str) -> Result<bool, DBError>;
+
+    // (channel, name) of every counter
+    async fn get_counter_names(&self) -> Result<HashSet<(String, String)>, DBError>;
+
+    async fn get_counter(&self, channel: &str, name: &str) -> Result<Option<Counter>, DBError>;
+
+    // Applies the change and records it in one go, returns the new value or None if there's no
+    // such counter
+    async fn change_counter(
+        &self,
+        channel: &str,
+        name: &str,
+        change: CounterChange,
+        changed_by: &str,
+    ) -> Result<Option<i64>, DBError>;
+
+    // newest first
+    async fn get_counter_history(
+        &self,
+        channel: &str,
+        name: &str,
+        limit: i64,
+    ) -> Result<Vec<CounterEvent>, DBError>;
+}
+
+#[async_trait]
+pub trait GameRepository: Send + Sync {
+    // adds points to the user's score in the channel's game, returns the new score
+    async fn add_game_score(
+        &self,
+        channel: &str,
+        game: &str,
+        uid: i32,
+        login: &str,
+        points: i64,
+    ) -> Result<i64, DBError>;
+
+    // (points, times scored), zeros for someone who never scored
+    async fn get_game_score(
+        &self,
+        channel: &str,
+        game: &str,
+        uid: i32,
+    ) -> Result<(i64, i32), DBError>;
+
+    // (login, points), best first
+    async fn get_top_game_scores(
+        &self,
+        channel: &str,
+        game: &str,
+        limit: i64,
+    ) -> Result<Vec<(String, i64)>, DBError>;
+}
+
+// The main database, implemented by anything that implements all of its repositories
+pub trait Database:
+    HealthCheck
+    + UserRepository
+    + ChannelRepository
+    + CommandRepository
+    + StreamRepository
+    + NotificationRepository
+    + ReminderRepository
+    + AfkRepository
+    + TimerRepository
+    + QuoteRepository
+    + CounterRepository
+    + PointsRepository
+    + GameRepository
+{
+}
+
+impl<
+        T: HealthCheck
+            + UserRepository
+            + ChannelRepository
+            + CommandRepository
+            + StreamRepository
+            + NotificationRepository
+            + ReminderRepository
+            + AfkRepository
+            + TimerRepository
+            + QuoteRepository
+            + CounterRepository
+            + PointsRepository
+            + GameRepository,
+    > Database for T
+{
+}
@@ -0,0 +1,154 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use deadpool_postgres::{Manager, ManagerConfig, Object, Pool, RecyclingMethod, Runtime};
+use tokio_postgres::NoTls;
+
+const MAX_CONNECTIONS: usize = 8;
+const CONNECT_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum DBError {
+    // No connection could be made to the database, even after retrying
+    Unavailable(String),
+
+    // The connection was fine but the query itself failed
+    Query(tokio_postgres::Error),
+
+    // The value can't be stored in its column
+    OutOfRange(String),
+}
+
+impl fmt::Display for DBError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DBError::Unavailable(reason) => write!(f, "database unavailable: {}", reason),
+            DBError::Query(e) => write!(f, "database query failed: {}", e),
+            DBError::OutOfRange(what) => write!(f, "{} doesn't fit in the database", what),
+        }
+    }
+}
+
+impl std::error::Error for DBError {}
+
+impl From<tokio_postgres::Error> for DBError {
+    fn from(e: tokio_postgres::Error) -> Self {
+        if e.is_closed() {
+            DBError::Unavailable(e.to_string())
+        } else {
+            DBError::Query(e)
+        }
+    }
+}
+
+// A pool of postgres connections, each connection is checked with a query before being handed out
+// and replaced with a fresh one if it died, so a dropped database connection recovers by itself.
+pub struct PgPool {
+    name: &'static str,
+    pool: Pool,
+    healthy: Arc<AtomicBool>,
+}
+
+impl PgPool {
+    pub fn new(name: &'static str, config: &str) -> Self {
+        let pg_config: tokio_postgres::Config = config.parse().unwrap();
+        let manager = Manager::from_config(
+            pg_config,
+            NoTls,
+            ManagerConfig {
+                recycling_method: RecyclingMethod::Verified,
+            },
+        );
+
+        let pool = Pool::builder(manager)
+            .max_size(MAX_CONNECTIONS)
+            .runtime(Runtime::Tokio1)
+            .create_timeout(Some(Duration::from_secs(5)))
+            .wait_timeout(Some(Duration::from_secs(10)))
+            .recycle_timeout(Some(Duration::from_secs(5)))
+            .build()
+            .unwrap();
+
+        Self {
+            name,
+            pool,
+            healthy: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    // gets a connection from the pool, retrying with exponential backoff while the database
+    // can't be reached
+    pub async fn get(&self) -> Result<Object, DBError> {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 1;
+        loop {
+            match self.pool.get().await {
+                Ok(client) => return Ok(client),
+                Err(e) if attempt >= CONNECT_ATTEMPTS => {
+                    return Err(DBError::Unavailable(e.to_string()));
+                }
+                Err(e) => {
+                    eprintln!(
+                        "Couldn't get a {} database connection (attempt {}/{}): {}",
+                        self.name, attempt, CONNECT_ATTEMPTS, e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    // periodically pings the database so idle dead connections get replaced and outages show up
+    // in the logs when they start and end, instead of only when a command fails
+    pub fn start_health_check_loop(&self) {
+        let name = self.name;
+        let pool = self.pool.clone();
+        let healthy = Arc::clone(&self.healthy);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+
+                let result = match pool.get().await {
+                    Ok(client) => client
+                        .simple_query("SELECT 1")
+                        .await
+                        .map(|_| ())
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+
+                match result {
+                    Ok(()) => {
+                        if !healthy.swap(true, Ordering::Relaxed) {
+                            eprintln!(
+                                "{}: {} database connection restored",
+                                chrono::Utc::now(),
+                                name
+                            );
+                        }
+                    }
+                    Err(reason) => {
+                        if healthy.swap(false, Ordering::Relaxed) {
+                            eprintln!(
+                                "{}: {} database health check failed: {}",
+                                chrono::Utc::now(),
+                                name,
+                                reason
+                            );
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,1276 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio_postgres::Row;
+use twitch_irc::message::PrivmsgMessage;
+
+use super::{
+    migrations, AfkRepository, ChannelRepository, CommandRepository, CounterRepository, DBError,
+    GameRepository, HealthCheck, NotificationRepository, PgPool, PointsRepository, QuoteRepository,
+    ReminderRepository, StreamRepository, TimerRepository, UserRepository,
+};
+use crate::afk::{AfkKind, AfkStatus};
+use crate::commands::Command;
+use crate::counters::{Counter, CounterChange, CounterEvent};
+use crate::error::BotError;
+use crate::notifications::NotificationKind;
+use crate::quotes::{number_quotes, Quote};
+use crate::reminders::Reminder;
+use crate::streams::StreamSession;
+use crate::timers::{Timer, TimerCondition, TimerSchedule};
+use crate::types::{parse_uid, PermissionLevel, UserContext};
+
+const DB_CONFIG: &str = "host=localhost user=postgres dbname=testmandb";
+
+const STREAM_SESSION_COLUMNS: &str =
+    "id, channel, stream_id, started_at, ended_at, title, category";
+
+fn stream_session_from_row(row: &Row) -> StreamSession {
+    StreamSession {
+        id: row.get(0),
+        channel: row.get(1),
+        stream_id: row.get(2),
+        started_at: row.get(3),
+        ended_at: row.get(4),
+        title: row.get(5),
+        category: row.get(6),
+    }
+}
+
+const REMINDER_COLUMNS: &str = "id, author_uid, author_login, target_uid, target_login, channel, \
+    message, created_at, due_at";
+
+fn reminder_from_row(row: &Row) -> Reminder {
+    Reminder {
+        id: row.get(0),
+        author_uid: row.get(1),
+        author_login: row.get(2),
+        target_uid: row.get(3),
+        target_login: row.get(4),
+        channel: row.get(5),
+        message: row.get(6),
+        created_at: row.get(7),
+        due_at: row.get(8),
+    }
+}
+
+fn oldest_first(mut reminders: Vec<Reminder>) -> Vec<Reminder> {
+    reminders.sort_by_key(|r| (r.created_at, r.id));
+    reminders
+}
+
+const TIMER_COLUMNS: &str = "id, channel, name, message, interval_secs, run_at, min_lines, \
+    condition, paused, created_at, last_sent_at";
+
+// None for rows that don't fit the timer's shape
+fn timer_from_row(row: &Row) -> Option<Timer> {
+    let interval_secs: Option<i32> = row.get(4);
+    let run_at: Option<DateTime<Utc>> = row.get(5);
+    let schedule = match (interval_secs, run_at) {
+        (Some(secs), None) => TimerSchedule::Every(chrono::Duration::seconds(secs as i64)),
+        (None, Some(run_at)) => TimerSchedule::Once(run_at),
+        _ => return None,
+    };
+
+    Some(Timer {
+        id: row.get(0),
+        channel: row.get(1),
+        name: row.get(2),
+        message: row.get(3),
+        schedule,
+        min_lines: row.get(6),
+        condition: TimerCondition::parse(row.get(7))?,
+        paused: row.get(8),
+        created_at: row.get(9),
+        last_sent_at: row.get(10),
+    })
+}
+
+pub struct DBController {
+    pool: PgPool,
+}
+
+impl DBController {
+    pub async fn new() -> Self {
+        let pool = PgPool::new("main", DB_CONFIG);
+
+        let mut client = pool
+            .get()
+            .await
+            .expect("Couldn't connect to the main database");
+        if let Err(e) = migrations::run(&mut client, migrations::MAIN).await {
+            panic!("Error migrating main database: {}", e);
+        }
+        drop(client);
+
+        pool.start_health_check_loop();
+
+        DBController { pool }
+    }
+
+    pub async fn schema_version(&self) -> Result<i32, migrations::MigrationError> {
+        let client = self.pool.get().await?;
+        migrations::current_version(&client).await
+    }
+}
+
+impl HealthCheck for DBController {
+    fn is_healthy(&self) -> bool {
+        self.pool.is_healthy()
+    }
+}
+
+#[async_trait]
+impl UserRepository for DBController {
+    async fn get_user_or_insert(&self, msg: &PrivmsgMessage) -> Result<UserContext, BotError> {
+        let uid = parse_uid(&msg.sender.id)?;
+        match self.get_user_by_uid(uid).await? {
+            Some(user) => Ok(user),
+            None => {
+                self.pool
+                    .get()
+                    .await?
+                    .execute(
+                        "INSERT INTO users (uid, username, permissions) VALUES ($1, $2, $3) \
+                        ON CONFLICT (uid) DO NOTHING",
+                        &[&uid, &msg.sender.login, &0],
+                    )
+                    .await?;
+
+                Ok(UserContext::new(uid, msg.sender.login.clone(), 0))
+            }
+        }
+    }
+
+    async fn get_user_by_uid(&self, uid: i32) -> Result<Option<UserContext>, DBError> {
+        let user = self
+            .pool
+            .get()
+            .await?
+            .query_opt(
+                "SELECT uid, username, permissions FROM users WHERE uid = $1",
+                &[&uid],
+            )
+            .await?;
+
+        Ok(user.map(|user| UserContext::new(user.get(0), user.get(1), user.get(2))))
+    }
+
+    async fn get_user_by_name(&self, name: &str) -> Result<Option<UserContext>, DBError> {
+        let user = self
+            .pool
+            .get()
+            .await?
+            .query_opt(
+                "SELECT uid, username, permissions FROM users WHERE username = $1",
+                &[&name.to_lowercase()],
+            )
+            .await?;
+
+        Ok(user.map(|user| UserContext::new(user.get(0), user.get(1), user.get(2))))
+    }
+
+    async fn set_permissions_by_name(&self, name: &str, permissions: i32) -> Result<u64, DBError> {
+        Ok(self
+            .pool
+            .get()
+            .await?
+            .execute(
+                "UPDATE users SET permissions = $1 WHERE username = $2",
+                &[&permissions, &name],
+            )
+            .await?)
+    }
+
+    async fn update_user_details(
+        &self,
+        uid: i32,
+        username: &str,
+        display_name: &str,
+        created_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DBError> {
+        self.pool
+            .get()
+            .await?
+            .execute(
+                "UPDATE users SET username = $2, display_name = $3, \
+                created_at = COALESCE($4, created_at) WHERE uid = $1",
+                &[&uid, &username, &display_name, &created_at],
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChannelRepository for DBController {
+    async fn get_current_channels(&self) -> Result<HashSet<String>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query("SELECT channel FROM channels WHERE joined = true", &[])
+            .await?;
+
+        let mut current_channels = HashSet::new();
+        for row in &rows {
+            current_channels.insert(row.get(0));
+        }
+
+        Ok(current_channels)
+    }
+
+    async fn modify_or_insert_joined_value(
+        &self,
+        channel: &str,
+        new_joined_value: bool,
+    ) -> Result<(), DBError> {
+        self.pool
+            .get()
+            .await?
+            .execute(
+                "INSERT INTO channels (channel, joined) VALUES ($1, $2) \
+                ON CONFLICT (channel) DO UPDATE SET joined = $2",
+                &[&channel, &new_joined_value],
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CommandRepository for DBController {
+    async fn get_current_commands(&self) -> Result<HashMap<String, Command>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                "SELECT name, about, permissions, user_cooldown FROM commands",
+                &[],
+            )
+            .await?;
+
+        let mut current_commands = HashMap::new();
+        for row in &rows {
+            let command_name: String = row.get(0);
+            let about: String = row.get(1);
+            let permission_needed = PermissionLevel::new(row.get(2));
+            let user_cooldown: i32 = row.get(3);
+
+            current_commands.insert(
+                command_name,
+                Command::new(about, permission_needed, user_cooldown as u64),
+            );
+        }
+
+        Ok(current_commands)
+    }
+
+    async fn get_disabled_commands(&self) -> Result<HashSet<(String, String)>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query("SELECT channel, command FROM disabled_commands", &[])
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    async fn set_command_disabled(
+        &self,
+        channel: &str,
+        command: &str,
+        disabled: bool,
+    ) -> Result<bool, DBError> {
+        let query = if disabled {
+            "INSERT INTO disabled_commands (channel, command) VALUES ($1, $2) \
+            ON CONFLICT DO NOTHING"
+        } else {
+            "DELETE FROM disabled_commands WHERE channel = $1 AND command = $2"
+        };
+        let changed = self
+            .pool
+            .get()
+            .await?
+            .execute(query, &[&channel, &command])
+            .await?;
+
+        Ok(changed > 0)
+    }
+}
+
+#[async_trait]
+impl StreamRepository for DBController {
+    async fn start_stream_session(
+        &self,
+        channel: &str,
+        stream_id: &str,
+        started_at: DateTime<Utc>,
+        title: &str,
+        category: &str,
+    ) -> Result<i32, DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_one(
+                "INSERT INTO stream_sessions (channel, stream_id, started_at, title, category) \
+                VALUES ($1, $2, $3, $4, $5) RETURNING id",
+                &[&channel, &stream_id, &started_at, &title, &category],
+            )
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    async fn end_stream_session(&self, id: i32, ended_at: DateTime<Utc>) -> Result<(), DBError> {
+        self.pool
+            .get()
+            .await?
+            .execute(
+                "UPDATE stream_sessions SET ended_at = $2 WHERE id = $1",
+                &[&id, &ended_at],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn record_stream_change(
+        &self,
+        id: i32,
+        changed_at: DateTime<Utc>,
+        title: &str,
+        category: &str,
+    ) -> Result<(), DBError> {
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        transaction
+            .execute(
+                "INSERT INTO stream_changes (session_id, changed_at, title, category) \
+                VALUES ($1, $2, $3, $4)",
+                &[&id, &changed_at, &title, &category],
+            )
+            .await?;
+        transaction
+            .execute(
+                "UPDATE stream_sessions SET title = $2, category = $3 WHERE id = $1",
+                &[&id, &title, &category],
+            )
+            .await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_open_stream_sessions(&self) -> Result<Vec<StreamSession>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                &format!(
+                    "SELECT {} FROM stream_sessions WHERE ended_at IS NULL",
+                    STREAM_SESSION_COLUMNS
+                )[..],
+                &[],
+            )
+            .await?;
+
+        Ok(rows.iter().map(stream_session_from_row).collect())
+    }
+
+    async fn get_last_stream_session(
+        &self,
+        channel: &str,
+    ) -> Result<Option<StreamSession>, DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_opt(
+                &format!(
+                    "SELECT {} FROM stream_sessions WHERE channel = $1 \
+                    ORDER BY started_at DESC LIMIT 1",
+                    STREAM_SESSION_COLUMNS
+                )[..],
+                &[&channel],
+            )
+            .await?;
+
+        Ok(row.as_ref().map(stream_session_from_row))
+    }
+
+    async fn get_stream_sessions_between(
+        &self,
+        channel: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<StreamSession>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                &format!(
+                    "SELECT {} FROM stream_sessions WHERE channel = $1 AND started_at < $3 \
+                    AND (ended_at IS NULL OR ended_at > $2) ORDER BY started_at",
+                    STREAM_SESSION_COLUMNS
+                )[..],
+                &[&channel, &from, &to],
+            )
+            .await?;
+
+        Ok(rows.iter().map(stream_session_from_row).collect())
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for DBController {
+    async fn add_notification(
+        &self,
+        channel: &str,
+        uid: i32,
+        kind: NotificationKind,
+    ) -> Result<bool, DBError> {
+        let inserted = self
+            .pool
+            .get()
+            .await?
+            .execute(
+                "INSERT INTO notifications (channel, uid, kind) VALUES ($1, $2, $3) \
+                ON CONFLICT DO NOTHING",
+                &[&channel, &uid, &kind.as_str()],
+            )
+            .await?;
+
+        Ok(inserted > 0)
+    }
+
+    async fn remove_notifications(
+        &self,
+        channel: &str,
+        uid: i32,
+        kind: Option<NotificationKind>,
+    ) -> Result<u64, DBError> {
+        let kind = kind.map(|kind| kind.as_str());
+        Ok(self
+            .pool
+            .get()
+            .await?
+            .execute(
+                "DELETE FROM notifications WHERE channel = $1 AND uid = $2 \
+                AND ($3::TEXT IS NULL OR kind = $3)",
+                &[&channel, &uid, &kind],
+            )
+            .await?)
+    }
+
+    async fn get_notified_users(
+        &self,
+        channel: &str,
+        kind: NotificationKind,
+    ) -> Result<Vec<String>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                "SELECT users.username FROM notifications \
+                JOIN users ON users.uid = notifications.uid \
+                WHERE notifications.channel = $1 AND notifications.kind = $2 \
+                ORDER BY notifications.subscribed_at",
+                &[&channel, &kind.as_str()],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+}
+
+#[async_trait]
+impl ReminderRepository for DBController {
+    async fn add_reminder(&self, reminder: &Reminder) -> Result<i32, DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_one(
+                "INSERT INTO reminders (author_uid, author_login, target_uid, target_login, \
+                channel, message, created_at, due_at) \
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id",
+                &[
+                    &reminder.author_uid,
+                    &reminder.author_login,
+                    &reminder.target_uid,
+                    &reminder.target_login,
+                    &reminder.channel,
+                    &reminder.message,
+                    &reminder.created_at,
+                    &reminder.due_at,
+                ],
+            )
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    async fn count_pending_reminders(&self, author_uid: i32) -> Result<i64, DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_one(
+                "SELECT COUNT(*) FROM reminders WHERE author_uid = $1",
+                &[&author_uid],
+            )
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    async fn get_chat_reminder_targets(&self) -> Result<HashSet<i32>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                "SELECT DISTINCT target_uid FROM reminders WHERE due_at IS NULL",
+                &[],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    async fn get_chat_reminders(&self, target_uid: i32) -> Result<Vec<Reminder>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                &format!(
+                    "SELECT {} FROM reminders WHERE target_uid = $1 AND due_at IS NULL",
+                    REMINDER_COLUMNS
+                )[..],
+                &[&target_uid],
+            )
+            .await?;
+
+        Ok(oldest_first(rows.iter().map(reminder_from_row).collect()))
+    }
+
+    async fn get_next_reminder_due(&self) -> Result<Option<DateTime<Utc>>, DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_one("SELECT MIN(due_at) FROM reminders", &[])
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    async fn get_due_reminders(&self, until: DateTime<Utc>) -> Result<Vec<Reminder>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                &format!(
+                    "SELECT {} FROM reminders WHERE due_at <= $1",
+                    REMINDER_COLUMNS
+                )[..],
+                &[&until],
+            )
+            .await?;
+
+        Ok(oldest_first(rows.iter().map(reminder_from_row).collect()))
+    }
+
+    async fn cancel_reminder(&self, id: i32, author_uid: Option<i32>) -> Result<bool, DBError> {
+        let removed = self
+            .pool
+            .get()
+            .await?
+            .execute(
+                "DELETE FROM reminders WHERE id = $1 AND ($2::INTEGER IS NULL OR author_uid = $2)",
+                &[&id, &author_uid],
+            )
+            .await?;
+
+        Ok(removed > 0)
+    }
+}
+
+#[async_trait]
+impl AfkRepository for DBController {
+    async fn set_afk(&self, status: &AfkStatus) -> Result<(), DBError> {
+        self.pool
+            .get()
+            .await?
+            .execute(
+                "INSERT INTO afk (uid, login, kind, reason, since) VALUES ($1, $2, $3, $4, $5) \
+                ON CONFLICT (uid) DO UPDATE SET login = $2, kind = $3, reason = $4, since = $5",
+                &[
+                    &status.uid,
+                    &status.login,
+                    &status.kind.as_str(),
+                    &status.reason,
+                    &status.since,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn remove_afk(&self, uid: i32) -> Result<bool, DBError> {
+        let removed = self
+            .pool
+            .get()
+            .await?
+            .execute("DELETE FROM afk WHERE uid = $1", &[&uid])
+            .await?;
+
+        Ok(removed > 0)
+    }
+
+    async fn get_afk_statuses(&self) -> Result<Vec<AfkStatus>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query("SELECT uid, login, kind, reason, since FROM afk", &[])
+            .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                Some(AfkStatus {
+                    uid: row.get(0),
+                    login: row.get(1),
+                    kind: AfkKind::parse(row.get(2))?,
+                    reason: row.get(3),
+                    since: row.get(4),
+                })
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl TimerRepository for DBController {
+    async fn add_timer(&self, timer: &Timer) -> Result<i32, DBError> {
+        let (interval_secs, run_at) = match timer.schedule {
+            TimerSchedule::Every(interval) => (
+                Some(
+                    i32::try_from(interval.num_seconds())
+                        .map_err(|_| DBError::OutOfRange("timer interval".to_owned()))?,
+                ),
+                None,
+            ),
+            TimerSchedule::Once(run_at) => (None, Some(run_at)),
+        };
+
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_one(
+                "INSERT INTO timers (channel, name, message, interval_secs, run_at, min_lines, \
+                condition, paused, created_at, last_sent_at) \
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING id",
+                &[
+                    &timer.channel,
+                    &timer.name,
+                    &timer.message,
+                    &interval_secs,
+                    &run_at,
+                    &timer.min_lines,
+                    &timer.condition.as_str(),
+                    &timer.paused,
+                    &timer.created_at,
+                    &timer.last_sent_at,
+                ],
+            )
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    async fn get_timers(&self) -> Result<Vec<Timer>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                &format!("SELECT {} FROM timers ORDER BY id", TIMER_COLUMNS)[..],
+                &[],
+            )
+            .await?;
+
+        Ok(rows.iter().filter_map(timer_from_row).collect())
+    }
+
+    async fn set_timer_paused(&self, id: i32, paused: bool) -> Result<(), DBError> {
+        self.pool
+            .get()
+            .await?
+            .execute(
+                "UPDATE timers SET paused = $2 WHERE id = $1",
+                &[&id, &paused],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_timer_sent(&self, id: i32, sent_at: DateTime<Utc>) -> Result<(), DBError> {
+        self.pool
+            .get()
+            .await?
+            .execute(
+                "UPDATE timers SET last_sent_at = $2 WHERE id = $1",
+                &[&id, &sent_at],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_timer(&self, id: i32) -> Result<(), DBError> {
+        self.pool
+            .get()
+            .await?
+            .execute("DELETE FROM timers WHERE id = $1", &[&id])
+            .await?;
+
+        Ok(())
+    }
+}
+
+const QUOTE_COLUMNS: &str = "id, channel, quoted, text, added_by, added_at";
+
+fn quote_from_row(row: &Row) -> Quote {
+    Quote {
+        id: row.get(0),
+        channel: row.get(1),
+        quoted: row.get(2),
+        text: row.get(3),
+        added_by: row.get(4),
+        added_at: row.get(5),
+    }
+}
+
+#[async_trait]
+impl QuoteRepository for DBController {
+    async fn add_quote(&self, quote: &Quote) -> Result<i32, DBError> {
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        // quotes added to a channel at the same time would pick the same number otherwise
+        transaction
+            .execute(
+                "SELECT pg_advisory_xact_lock(hashtext('quotes:' || $1::text))",
+                &[&quote.channel],
+            )
+            .await?;
+        let row = transaction
+            .query_one(
+                "INSERT INTO quotes (channel, id, quoted, text, added_by, added_at) \
+                SELECT $1, COALESCE(MAX(id), 0) + 1, $2, $3, $4, $5 FROM quotes \
+                WHERE channel = $1 RETURNING id",
+                &[
+                    &quote.channel,
+                    &quote.quoted,
+                    &quote.text,
+                    &quote.added_by,
+                    &quote.added_at,
+                ],
+            )
+            .await?;
+        transaction.commit().await?;
+
+        Ok(row.get(0))
+    }
+
+    async fn import_quotes(&self, quotes: &[Quote]) -> Result<u64, DBError> {
+        let mut client = self.pool.get().await?;
+        // one transaction, so a broken import leaves nothing behind
+        let transaction = client.transaction().await?;
+
+        let channels: HashSet<&str> = quotes.iter().map(|q| q.channel.as_str()).collect();
+        let mut numbered = Vec::new();
+        for channel in channels {
+            transaction
+                .execute(
+                    "SELECT pg_advisory_xact_lock(hashtext('quotes:' || $1::text))",
+                    &[&channel],
+                )
+                .await?;
+            let mut taken: HashSet<i32> = transaction
+                .query("SELECT id FROM quotes WHERE channel = $1", &[&channel])
+                .await?
+                .iter()
+                .map(|row| row.get(0))
+                .collect();
+            let imported: Vec<Quote> = quotes
+                .iter()
+                .filter(|q| q.channel == channel)
+                .cloned()
+                .collect();
+            numbered.extend(number_quotes(&mut taken, &imported));
+        }
+
+        let channels: Vec<&str> = numbered.iter().map(|q| q.channel.as_str()).collect();
+        let ids: Vec<i32> = numbered.iter().map(|q| q.id).collect();
+        let quoted: Vec<&str> = numbered.iter().map(|q| q.quoted.as_str()).collect();
+        let texts: Vec<&str> = numbered.iter().map(|q| q.text.as_str()).collect();
+        let added_by: Vec<&str> = numbered.iter().map(|q| q.added_by.as_str()).collect();
+        let added_at: Vec<DateTime<Utc>> = numbered.iter().map(|q| q.added_at).collect();
+
+        let imported = transaction
+            .execute(
+                "INSERT INTO quotes (channel, id, quoted, text, added_by, added_at) \
+                SELECT * FROM UNNEST($1::text[], $2::int[], $3::text[], $4::text[], \
+                $5::text[], $6::timestamptz[])",
+                &[&channels, &ids, &quoted, &texts, &added_by, &added_at],
+            )
+            .await?;
+        transaction.commit().await?;
+
+        Ok(imported)
+    }
+
+    async fn get_quote(&self, channel: &str, id: i32) -> Result<Option<Quote>, DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_opt(
+                &format!(
+                    "SELECT {} FROM quotes WHERE channel = $1 AND id = $2",
+                    QUOTE_COLUMNS
+                )[..],
+                &[&channel, &id],
+            )
+            .await?;
+
+        Ok(row.as_ref().map(quote_from_row))
+    }
+
+    async fn get_random_quote(
+        &self,
+        channel: &str,
+        quoted: Option<&str>,
+        search: Option<&str>,
+    ) -> Result<Option<Quote>, DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_opt(
+                &format!(
+                    "SELECT {} FROM quotes WHERE channel = $1 \
+                    AND ($2::text IS NULL OR quoted = $2) \
+                    AND ($3::text IS NULL OR strpos(lower(text), lower($3)) > 0) \
+                    ORDER BY random() LIMIT 1",
+                    QUOTE_COLUMNS
+                )[..],
+                &[&channel, &quoted, &search],
+            )
+            .await?;
+
+        Ok(row.as_ref().map(quote_from_row))
+    }
+
+    async fn get_quotes(&self, channel: &str) -> Result<Vec<Quote>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                &format!(
+                    "SELECT {} FROM quotes WHERE channel = $1 ORDER BY id",
+                    QUOTE_COLUMNS
+                )[..],
+                &[&channel],
+            )
+            .await?;
+
+        Ok(rows.iter().map(quote_from_row).collect())
+    }
+
+    async fn delete_quote(&self, channel: &str, id: i32) -> Result<bool, DBError> {
+        let deleted = self
+            .pool
+            .get()
+            .await?
+            .execute(
+                "DELETE FROM quotes WHERE channel = $1 AND id = $2",
+                &[&channel, &id],
+            )
+            .await?;
+
+        Ok(deleted > 0)
+    }
+}
+
+#[async_trait]
+impl CounterRepository for DBController {
+    async fn add_counter(&self, counter: &Counter) -> Result<bool, DBError> {
+        let added = self
+            .pool
+            .get()
+            .await?
+            .execute(
+                "INSERT INTO counters (channel, name, template, value, created_at) \
+                VALUES ($1, $2, $3, $4, $5) ON CONFLICT (channel, name) DO NOTHING",
+                &[
+                    &counter.channel,
+                    &counter.name,
+                    &counter.template,
+                    &counter.value,
+                    &counter.created_at,
+                ],
+            )
+            .await?;
+
+        Ok(added > 0)
+    }
+
+    async fn delete_counter(&self, channel: &str, name: &str) -> Result<bool, DBError> {
+        let deleted = self
+            .pool
+            .get()
+            .await?
+            .execute(
+                "DELETE FROM counters WHERE channel = $1 AND name = $2",
+                &[&channel, &name],
+            )
+            .await?;
+
+        Ok(deleted > 0)
+    }
+
+    async fn get_counter_names(&self) -> Result<HashSet<(String, String)>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query("SELECT channel, name FROM counters", &[])
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    async fn get_counter(&self, channel: &str, name: &str) -> Result<Option<Counter>, DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_opt(
+                "SELECT channel, name, template, value, created_at FROM counters \
+                WHERE channel = $1 AND name = $2",
+                &[&channel, &name],
+            )
+            .await?;
+
+        Ok(row.map(|row| Counter {
+            channel: row.get(0),
+            name: row.get(1),
+            template: row.get(2),
+            value: row.get(3),
+            created_at: row.get(4),
+        }))
+    }
+
+    async fn change_counter(
+        &self,
+        channel: &str,
+        name: &str,
+        change: CounterChange,
+        changed_by: &str,
+    ) -> Result<Option<i64>, DBError> {
+        // the update locks the counter's row, so changes coming in at the same time queue up
+        // behind each other instead of overwriting one another
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_opt(
+                "WITH updated AS ( \
+                    UPDATE counters \
+                    SET value = CASE WHEN $3 = 'set' THEN $4 ELSE value + $4 END \
+                    WHERE channel = $1 AND name = $2 RETURNING id, value \
+                ) \
+                INSERT INTO counter_changes (counter_id, kind, amount, value, changed_by) \
+                SELECT id, $3, $4, value, $5 FROM updated RETURNING value",
+                &[
+                    &channel,
+                    &name,
+                    &change.kind(),
+                    &change.amount(),
+                    &changed_by,
+                ],
+            )
+            .await?;
+
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    async fn get_counter_history(
+        &self,
+        channel: &str,
+        name: &str,
+        limit: i64,
+    ) -> Result<Vec<CounterEvent>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                "SELECT h.kind, h.amount, h.value, h.changed_by, h.changed_at \
+                FROM counter_changes h JOIN counters c ON c.id = h.counter_id \
+                WHERE c.channel = $1 AND c.name = $2 ORDER BY h.id DESC LIMIT $3",
+                &[&channel, &name, &limit],
+            )
+            .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                Some(CounterEvent {
+                    change: CounterChange::parse(row.get(0), row.get(1))?,
+                    value: row.get(2),
+                    changed_by: row.get(3),
+                    changed_at: row.get(4),
+                })
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl PointsRepository for DBController {
+    async fn add_points(
+        &self,
+        channel: &str,
+        uid: i32,
+        login: &str,
+        amount: i64,
+    ) -> Result<i64, DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_one(
+                "INSERT INTO points (channel, uid, login, balance) VALUES ($1, $2, $3, $4) \
+                ON CONFLICT (channel, uid) \
+                DO UPDATE SET login = $3, balance = points.balance + $4 RETURNING balance",
+                &[&channel, &uid, &login.to_lowercase(), &amount],
+            )
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    async fn get_points(&self, channel: &str, uid: i32) -> Result<i64, DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_opt(
+                "SELECT balance FROM points WHERE channel = $1 AND uid = $2",
+                &[&channel, &uid],
+            )
+            .await?;
+
+        Ok(row.map(|row| row.get(0)).unwrap_or(0))
+    }
+
+    async fn wager_points(
+        &self,
+        channel: &str,
+        uid: i32,
+        stake: i64,
+        delta: i64,
+    ) -> Result<Option<i64>, DBError> {
+        // checking and changing the balance in one statement, two bets at once can't both
+        // spend the same points
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_opt(
+                "UPDATE points SET balance = balance + $4 \
+                WHERE channel = $1 AND uid = $2 AND balance >= $3 RETURNING balance",
+                &[&channel, &uid, &stake, &delta],
+            )
+            .await?;
+
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    async fn transfer_points(
+        &self,
+        channel: &str,
+        from_uid: i32,
+        to_uid: i32,
+        to_login: &str,
+        amount: i64,
+        to_stake: i64,
+    ) -> Result<Option<(i64, i64)>, DBError> {
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        transaction
+            .execute(
+                "INSERT INTO points (channel, uid, login) VALUES ($1, $2, $3) \
+                ON CONFLICT (channel, uid) DO NOTHING",
+                &[&channel, &to_uid, &to_login.to_lowercase()],
+            )
+            .await?;
+
+        // both rows are locked in the same order every time, so two transfers going opposite
+        // ways wait for each other instead of deadlocking
+        let rows = transaction
+            .query(
+                "SELECT uid, balance FROM points WHERE channel = $1 AND uid = ANY($2) \
+                ORDER BY uid FOR UPDATE",
+                &[&channel, &vec![from_uid, to_uid]],
+            )
+            .await?;
+        let balance = |uid: i32| {
+            rows.iter()
+                .find(|row| row.get::<_, i32>(0) == uid)
+                .map(|row| row.get::<_, i64>(1))
+                .unwrap_or(0)
+        };
+        let (from_balance, to_balance) = (balance(from_uid), balance(to_uid));
+        if from_balance < amount || to_balance < to_stake {
+            return Ok(None);
+        }
+
+        transaction
+            .execute(
+                "UPDATE points SET balance = balance - $3 WHERE channel = $1 AND uid = $2",
+                &[&channel, &from_uid, &amount],
+            )
+            .await?;
+        transaction
+            .execute(
+                "UPDATE points SET balance = balance + $3 WHERE channel = $1 AND uid = $2",
+                &[&channel, &to_uid, &amount],
+            )
+            .await?;
+        transaction.commit().await?;
+
+        Ok(Some((from_balance - amount, to_balance + amount)))
+    }
+
+    async fn get_top_points(
+        &self,
+        channel: &str,
+        limit: i64,
+    ) -> Result<Vec<(String, i64)>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                "SELECT login, balance FROM points WHERE channel = $1 AND balance > 0 \
+                ORDER BY balance DESC, login LIMIT $2",
+                &[&channel, &limit],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    async fn reset_points(&self, channel: &str, uid: Option<i32>) -> Result<u64, DBError> {
+        let reset = self
+            .pool
+            .get()
+            .await?
+            .execute(
+                "DELETE FROM points WHERE channel = $1 AND ($2::integer IS NULL OR uid = $2)",
+                &[&channel, &uid],
+            )
+            .await?;
+
+        Ok(reset)
+    }
+}
+
+#[async_trait]
+impl GameRepository for DBController {
+    async fn add_game_score(
+        &self,
+        channel: &str,
+        game: &str,
+        uid: i32,
+        login: &str,
+        points: i64,
+    ) -> Result<i64, DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_one(
+                "INSERT INTO game_scores (channel, game, uid, login, points, scored) \
+                VALUES ($1, $2, $3, $4, $5, 1) ON CONFLICT (channel, game, uid) DO UPDATE \
+                SET login = $4, points = game_scores.points + $5, \
+                scored = game_scores.scored + 1 RETURNING points",
+                &[&channel, &game, &uid, &login.to_lowercase(), &points],
+            )
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    async fn get_game_score(
+        &self,
+        channel: &str,
+        game: &str,
+        uid: i32,
+    ) -> Result<(i64, i32), DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_opt(
+                "SELECT points, scored FROM game_scores \
+                WHERE channel = $1 AND game = $2 AND uid = $3",
+                &[&channel, &game, &uid],
+            )
+            .await?;
+
+        Ok(row.map(|row| (row.get(0), row.get(1))).unwrap_or((0, 0)))
+    }
+
+    async fn get_top_game_scores(
+        &self,
+        channel: &str,
+        game: &str,
+        limit: i64,
+    ) -> Result<Vec<(String, i64)>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                "SELECT login, points FROM game_scores WHERE channel = $1 AND game = $2 \
+                ORDER BY points DESC, login LIMIT $3",
+                &[&channel, &game, &limit],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{NaiveDate, Utc};
+use tokio::time::Instant;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::api::emotes::Emote;
+use crate::bot::BorrowBot;
+use crate::database::{DBError, LogRepository};
+
+// native twitch emotes are stored under this provider, next to 7TV, BTTV and FFZ
+pub const TWITCH_PROVIDER: &str = "Twitch";
+
+// counted uses are written to the logs this often instead of once per message
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+// how long a channel waits after its emote lists failed to load before they're tried again
+const LOAD_RETRY_INTERVAL: Duration = Duration::from_secs(300);
+
+// (channel, day) -> (emote, provider) -> uses
+type PendingUsage = HashMap<(String, NaiveDate), HashMap<(String, String), i64>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ListLoad {
+    Loading,
+    FailedAt(Instant),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EmoteUsage {
+    pub name: String,
+    pub provider: String,
+    pub uses: i64,
+}
+
+// How often each emote shows up in a message. Native emotes come from the message's tags,
+// third party ones are found by name since twitch doesn't know about them
+pub fn count_emotes(msg: &PrivmsgMessage, third_party: &[&[Emote]]) -> Vec<EmoteUsage> {
+    let mut counts: HashMap<(String, String), i64> = HashMap::new();
+
+    for emote in &msg.emotes {
+        *counts
+            .entry((emote.code.clone(), TWITCH_PROVIDER.to_owned()))
+            .or_insert(0) += 1;
+    }
+
+    for word in msg.message_text.split_whitespace() {
+        if msg.emotes.iter().any(|emote| emote.code == word) {
+            continue;
+        }
+
+        // the channel's own emotes come first, they shadow globals with the same name
+        let found = third_party
+            .iter()
+            .find_map(|emotes| emotes.iter().find(|emote| emote.name == word));
+        if let Some(emote) = found {
+            *counts
+                .entry((emote.name.clone(), emote.provider.to_string()))
+                .or_insert(0) += 1;
+        }
+    }
+
+    let mut usage: Vec<EmoteUsage> = counts
+        .into_iter()
+        .map(|((name, provider), uses)| EmoteUsage {
+            name,
+            provider,
+            uses,
+        })
+        .collect();
+    usage.sort_by(|a, b| a.name.cmp(&b.name));
+
+    usage
+}
+
+// Emote uses counted in memory and added to the daily counters in the logs in batches
+pub struct EmoteStats {
+    logs: Arc<dyn LogRepository>,
+    pending: Mutex<PendingUsage>,
+
+    // one flush at a time, so a flush that returns has written everything counted before it
+    flushing: tokio::sync::Mutex<()>,
+
+    // channel -> its emote lists being loaded or when loading them last failed
+    loads: Mutex<HashMap<String, ListLoad>>,
+}
+
+impl EmoteStats {
+    pub fn new(logs: Arc<dyn LogRepository>) -> Self {
+        Self {
+            logs,
+            pending: Mutex::new(HashMap::new()),
+            flushing: tokio::sync::Mutex::new(()),
+            loads: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Marks the channel's lists as loading, false if they already are or failed too recently
+    fn start_load(&self, channel: &str) -> bool {
+        let mut loads = self.loads.lock().unwrap();
+        match loads.get(channel) {
+            Some(ListLoad::Loading) => false,
+            Some(ListLoad::FailedAt(at)) if at.elapsed() < LOAD_RETRY_INTERVAL => false,
+            _ => {
+                loads.insert(channel.to_owned(), ListLoad::Loading);
+                true
+            }
+        }
+    }
+
+    fn finish_load(&self, channel: &str, loaded: bool) {
+        let mut loads = self.loads.lock().unwrap();
+        if loaded {
+            loads.remove(channel);
+        } else {
+            loads.insert(channel.to_owned(), ListLoad::FailedAt(Instant::now()));
+        }
+    }
+
+    fn add(&self, channel: &str, day: NaiveDate, usage: &[EmoteUsage]) {
+        let mut pending = self.pending.lock().unwrap();
+        let counts = pending.entry((channel.to_owned(), day)).or_default();
+        for emote in usage {
+            *counts
+                .entry((emote.name.clone(), emote.provider.clone()))
+                .or_insert(0) += emote.uses;
+        }
+    }
+
+    // Writes everything counted so far. Commands reading the counters flush first so they
+    // include the latest messages, batches that fail to save are kept for the next flush
+    pub async fn flush(&self) -> Result<(), DBError> {
+        let _flushing = self.flushing.lock().await;
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+
+        let mut result = Ok(());
+        for ((channel, day), counts) in pending {
+            let mut usage: Vec<EmoteUsage> = counts
+                .into_iter()
+                .map(|((name, provider), uses)| EmoteUsage {
+                    name,
+                    provider,
+                    uses,
+                })
+                .collect();
+            usage.sort_by(|a, b| a.name.cmp(&b.name));
+
+            if let Err(e) = self.logs.record_emote_usage(&channel, day, &usage).await {
+                self.add(&channel, day, &usage);
+                result = Err(e);
+            }
+        }
+
+        result
+    }
+
+    pub fn start(bot: Arc<BorrowBot>) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(FLUSH_INTERVAL).await;
+                if let Err(e) = bot.emote_stats().flush().await {
+                    eprintln!("{}: Error recording emote usage: {}", Utc::now(), e);
+                }
+            }
+        });
+    }
+}
+
+// Counts a chat message's emotes towards the channel's daily counters. Third party emotes only
+// count once the channel's lists are loaded, which this kicks off the first time it sees a
+// channel and again a while after loading them failed
+pub fn record_message(bot: &BorrowBot, msg: &PrivmsgMessage) {
+    let channel = msg.channel_login.to_lowercase();
+    let emotes = bot.api().emotes();
+
+    let channel_emotes = emotes.cached_channel(&channel);
+    let global = emotes.cached_global();
+    if (channel_emotes.is_none() || global.is_none()) && bot.emote_stats().start_load(&channel) {
+        let emote_stats = bot.emote_stats();
+        let emotes = Arc::clone(&emotes);
+        let channel = channel.clone();
+        let channel_id = msg.channel_id.clone();
+        tokio::spawn(async move {
+            let loaded = match emotes.global().await {
+                Ok(_) => emotes.channel(&channel, &channel_id).await.map(|_| ()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = &loaded {
+                eprintln!(
+                    "{}: Error loading emotes for #{}: {}",
+                    Utc::now(),
+                    channel,
+                    e
+                );
+            }
+            emote_stats.finish_load(&channel, loaded.is_ok());
+        });
+    }
+
+    let mut third_party: Vec<&[Emote]> = Vec::new();
+    if let Some(channel_emotes) = &channel_emotes {
+        third_party.push(channel_emotes);
+    }
+    if let Some(global) = &global {
+        third_party.push(global);
+    }
+
+    // naming an emote in a command isn't using it
+    if msg.message_text.starts_with('&') {
+        return;
+    }
+
+    let usage = count_emotes(msg, &third_party);
+    if usage.is_empty() {
+        return;
+    }
+
+    let day: NaiveDate = msg.server_timestamp.naive_utc().date();
+    bot.emote_stats().add(&channel, day, &usage);
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+
+use crate::api::helix::HelixError;
+use crate::database::DBError;
+
+#[derive(Debug)]
+pub enum BotError {
+    Database(DBError),
+
+    // A request to the Twitch API failed
+    Helix(HelixError),
+
+    // A request to any other web API failed
+    Api(reqwest::Error),
+
+    // Data coming from Twitch or our own logs wasn't in the shape we expected
+    InvalidMessage(String),
+}
+
+impl BotError {
+    // what gets said in chat when a command fails with this error, the details only go
+    // to the error log
+    pub fn user_message(&self) -> &'static str {
+        match self {
+            BotError::Database(DBError::Unavailable(_)) => {
+                "Sorry, the database is unavailable right now, try again later"
+            }
+            BotError::Database(DBError::Query(_)) | BotError::Database(DBError::OutOfRange(_)) => {
+                "Sorry, something went wrong with my database"
+            }
+            BotError::Helix(HelixError::NotFound) => "Sorry, Twitch couldn't find that",
+            BotError::Helix(HelixError::Unauthorized) => {
+                "Sorry, Twitch isn't accepting my credentials right now"
+            }
+            BotError::Helix(HelixError::RateLimited(_)) => {
+                "Sorry, Twitch is rate limiting me, try again in a bit"
+            }
+            BotError::Helix(HelixError::MissingUserToken) => {
+                "Sorry, I'm not set up to look that up"
+            }
+            BotError::Helix(_) => "Sorry, the Twitch API couldn't be reached, try again later",
+            BotError::Api(_) => "Sorry, an API I rely on couldn't be reached, try again later",
+            BotError::InvalidMessage(_) => "Sorry, something went wrong handling that message",
+        }
+    }
+}
+
+impl fmt::Display for BotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BotError::Database(e) => write!(f, "{}", e),
+            BotError::Helix(e) => write!(f, "{}", e),
+            BotError::Api(e) => write!(f, "api request failed: {}", e),
+            BotError::InvalidMessage(reason) => write!(f, "invalid message: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for BotError {}
+
+impl From<DBError> for BotError {
+    fn from(e: DBError) -> Self {
+        BotError::Database(e)
+    }
+}
+
+impl From<tokio_postgres::Error> for BotError {
+    fn from(e: tokio_postgres::Error) -> Self {
+        BotError::Database(e.into())
+    }
+}
+
+impl From<HelixError> for BotError {
+    fn from(e: HelixError) -> Self {
+        BotError::Helix(e)
+    }
+}
+
+impl From<reqwest::Error> for BotError {
+    fn from(e: reqwest::Error) -> Self {
+        BotError::Api(e)
+    }
+}
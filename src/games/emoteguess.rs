@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use super::{hint, Game, Update};
+use crate::api::emotes::Emote;
+
+const HINT_INTERVAL: Duration = Duration::from_secs(20);
+const MAX_HINTS: usize = 2;
+const GUESS_TIMEOUT: Duration = Duration::from_secs(60);
+
+// what the right guess is worth before any hints, every hint takes one off
+const GUESS_POINTS: i64 = 3;
+
+// Guessing one of the channel's third party emotes from its provider, length and letters that
+// show up over time. The answer is the emote itself, typed anywhere in a message
+pub struct EmoteGuess {
+    emote: Emote,
+    started_at: Instant,
+    hints: usize,
+}
+
+impl EmoteGuess {
+    pub fn new(emote: Emote) -> Self {
+        Self {
+            emote,
+            started_at: Instant::now(),
+            hints: 0,
+        }
+    }
+
+    // "a 7TV emote with 7 letters: _______"
+    pub fn describe(&self) -> String {
+        format!(
+            "a {} emote with {} letters: {}",
+            self.emote.provider,
+            self.emote.name.chars().count(),
+            hint(&self.emote.name, 0)
+        )
+    }
+}
+
+impl Game for EmoteGuess {
+    fn name(&self) -> &'static str {
+        "emoteguess"
+    }
+
+    fn title(&self) -> &'static str {
+        "Emote guessing"
+    }
+
+    // emote names are case sensitive in chat, but a guess in the wrong case still counts
+    fn on_message(&mut self, login: &str, text: &str, _now: Instant) -> Option<Update> {
+        let name = &self.emote.name;
+        let guessed = text.split_whitespace().any(|word| word == name)
+            || text.to_lowercase() == name.to_lowercase();
+        if !guessed {
+            return None;
+        }
+
+        Some(
+            Update::score(
+                format!("{} got it: {}", login, name),
+                GUESS_POINTS - self.hints as i64,
+            )
+            .end(),
+        )
+    }
+
+    fn on_tick(&mut self, now: Instant) -> Update {
+        let elapsed = now.duration_since(self.started_at);
+        if elapsed >= GUESS_TIMEOUT {
+            return Update::say(format!("Time's up, it was {}", self.emote.name)).end();
+        }
+        if self.hints >= MAX_HINTS || elapsed < HINT_INTERVAL * (self.hints as u32 + 1) {
+            return Update::default();
+        }
+
+        self.hints += 1;
+        let uploader = match (&self.emote.owner, self.hints) {
+            (Some(owner), MAX_HINTS) => format!(", uploaded by {}", owner),
+            _ => String::new(),
+        };
+        Update::say(format!(
+            "Hint: {}{}",
+            hint(&self.emote.name, self.hints),
+            uploader
+        ))
+    }
+}
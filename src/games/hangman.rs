@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use rand::seq::SliceRandom;
+use tokio::time::Instant;
+
+use super::{Game, Update};
+use crate::types::DataDir;
+
+// the word list, one word per line in data/games/words.txt
+const WORD_LIST: &str = "words.txt";
+
+// the list shipped with the bot, used when the data directory doesn't have one
+const DEFAULT_WORDS: &str = include_str!("../../data/games/words.txt");
+
+const MIN_WORD_LENGTH: usize = 4;
+const MAX_WORD_LENGTH: usize = 16;
+
+// wrong letters allowed before the game is lost, guessing the whole word wrong is free
+pub const MAX_MISSES: usize = 6;
+const GAME_TIMEOUT: Duration = Duration::from_secs(180);
+
+// a right letter is worth one point, guessing the word one for every letter still hidden
+const LETTER_POINTS: i64 = 1;
+
+// Picks a word from the operator's list or the shipped one, lowercase letters only. Err is what
+// to tell chat. The list is read in place, it's one small file
+pub fn random_word(data_dir: &DataDir) -> Result<String, String> {
+    let path = data_dir
+        .file("games", WORD_LIST)
+        .ok_or("Sorry, I couldn't find the word list")?;
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => DEFAULT_WORDS.to_owned(),
+        Err(e) => {
+            eprintln!("Error reading the word list {}: {}", path.display(), e);
+            return Err("Sorry, I couldn't read the word list".to_owned());
+        }
+    };
+
+    let words: Vec<String> = contents
+        .lines()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|word| {
+            (MIN_WORD_LENGTH..=MAX_WORD_LENGTH).contains(&word.len())
+                && word.chars().all(|c| c.is_ascii_lowercase())
+        })
+        .collect();
+    words
+        .choose(&mut rand::thread_rng())
+        .cloned()
+        .ok_or_else(|| "There are no words in the word list".to_owned())
+}
+
+// Guessing a word one letter at a time. Chat sends single letters or the whole word
+pub struct Hangman {
+    word: String,
+    guessed: HashSet<char>,
+    misses: Vec<char>,
+    started_at: Instant,
+}
+
+impl Hangman {
+    pub fn new(word: String) -> Self {
+        Self {
+            word,
+            guessed: HashSet::new(),
+            misses: Vec::new(),
+            started_at: Instant::now(),
+        }
+    }
+
+    // "_ o _ _ l e"
+    pub fn board(&self) -> String {
+        self.word
+            .chars()
+            .map(|c| {
+                if self.guessed.contains(&c) {
+                    c.to_string()
+                } else {
+                    "_".to_owned()
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    fn hidden(&self) -> usize {
+        self.word
+            .chars()
+            .filter(|c| !self.guessed.contains(c))
+            .count()
+    }
+
+    fn guess_letter(&mut self, login: &str, letter: char) -> Option<Update> {
+        if self.guessed.contains(&letter) || self.misses.contains(&letter) {
+            return None;
+        }
+
+        if !self.word.contains(letter) {
+            self.misses.push(letter);
+            let left = MAX_MISSES - self.misses.len();
+            if left == 0 {
+                return Some(
+                    Update::say(format!(
+                        "No {}, out of guesses. The word was {}",
+                        letter, self.word
+                    ))
+                    .end(),
+                );
+            }
+            return Some(Update::say(format!(
+                "No {}, {} misses left: {}",
+                letter,
+                left,
+                self.board()
+            )));
+        }
+
+        self.guessed.insert(letter);
+        let found = self.word.chars().filter(|c| *c == letter).count();
+        if self.hidden() == 0 {
+            return Some(
+                Update::score(
+                    format!("{} found the last {}: {}", login, letter, self.word),
+                    LETTER_POINTS,
+                )
+                .end(),
+            );
+        }
+        Some(Update::score(
+            format!("{} found {} {}: {}", login, found, letter, self.board()),
+            LETTER_POINTS,
+        ))
+    }
+}
+
+impl Game for Hangman {
+    fn name(&self) -> &'static str {
+        "hangman"
+    }
+
+    fn title(&self) -> &'static str {
+        "Hangman"
+    }
+
+    fn on_message(&mut self, login: &str, text: &str, _now: Instant) -> Option<Update> {
+        let guess = text.to_lowercase();
+        let mut letters = guess.chars();
+        match (letters.next(), letters.next()) {
+            (Some(letter), None) if letter.is_ascii_lowercase() => self.guess_letter(login, letter),
+            _ if guess == self.word => {
+                let points = self.hidden() as i64;
+                Some(Update::score(format!("{} got it: {}", login, self.word), points).end())
+            }
+            _ => None,
+        }
+    }
+
+    fn on_tick(&mut self, now: Instant) -> Update {
+        if now.duration_since(self.started_at) >= GAME_TIMEOUT {
+            return Update::say(format!("Time's up, the word was {}", self.word)).end();
+        }
+        Update::default()
+    }
+}
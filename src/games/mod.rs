@@ -0,0 +1,258 @@
+pub mod emoteguess;
+pub mod hangman;
+pub mod trivia;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::bot::BorrowBot;
+use crate::database::Database;
+use crate::types::parse_uid;
+
+// how often running games are moved along
+const GAME_TICK: Duration = Duration::from_secs(1);
+
+// What a chat message or a tick did to a game
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Update {
+    // announced in order
+    pub messages: Vec<String>,
+
+    // earned by whoever sent the message, their score this game is added to the first message
+    pub points: i64,
+
+    // the game is over, its results follow the messages
+    pub over: bool,
+}
+
+impl Update {
+    pub fn say(message: String) -> Self {
+        Self {
+            messages: vec![message],
+            ..Self::default()
+        }
+    }
+
+    pub fn score(message: String, points: i64) -> Self {
+        Self {
+            messages: vec![message],
+            points,
+            ..Self::default()
+        }
+    }
+
+    pub fn end(mut self) -> Self {
+        self.over = true;
+        self
+    }
+}
+
+// A game chat plays in a channel. It sees every chat message that isn't a command and a tick
+// every second, the runner keeps score, saves it and announces the results
+pub trait Game: Send {
+    // what scores are saved under, also the command that starts it
+    fn name(&self) -> &'static str;
+
+    // "Trivia", for announcing the results
+    fn title(&self) -> &'static str;
+
+    // None if the message has nothing to do with the game
+    fn on_message(&mut self, login: &str, text: &str, now: Instant) -> Option<Update>;
+
+    fn on_tick(&mut self, now: Instant) -> Update;
+}
+
+// The answer with letters blanked out. The first letter of every word shows from the first hint
+// and every third letter after that from the second
+pub fn hint(answer: &str, level: usize) -> String {
+    let mut position = 0;
+    answer
+        .chars()
+        .map(|c| {
+            if !c.is_alphanumeric() {
+                position = 0;
+                return c;
+            }
+            let shown = (level >= 1 && position == 0) || (level >= 2 && position % 3 == 0);
+            position += 1;
+            if shown {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+struct Session {
+    game: Box<dyn Game>,
+
+    // uid -> (login, points this game)
+    scores: HashMap<i32, (String, i64)>,
+}
+
+impl Session {
+    // "Trivia over! Scores: alice 7, bob 3", best first
+    fn results(&self) -> String {
+        if self.scores.is_empty() {
+            return format!("{} over, nobody scored", self.game.title());
+        }
+
+        let mut scores: Vec<&(String, i64)> = self.scores.values().collect();
+        scores.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let scores: Vec<String> = scores
+            .iter()
+            .map(|(login, points)| format!("{} {}", login, points))
+            .collect();
+        format!("{} over! Scores: {}", self.game.title(), scores.join(", "))
+    }
+}
+
+// The chat games running in every channel, at most one per channel. Games only live in memory
+// and a restart ends them, the points people scored are saved as they score them
+pub struct Games {
+    db: Arc<dyn Database>,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl Games {
+    pub fn new(db: Arc<dyn Database>) -> Self {
+        Self {
+            db,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // the name of the game running in the channel
+    pub fn running(&self, channel: &str) -> Option<&'static str> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(channel)
+            .map(|session| session.game.name())
+    }
+
+    // Err with the name of the game the channel is already playing
+    pub fn start_game(&self, channel: &str, game: Box<dyn Game>) -> Result<(), &'static str> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(session) = sessions.get(channel) {
+            return Err(session.game.name());
+        }
+
+        sessions.insert(
+            channel.to_owned(),
+            Session {
+                game,
+                scores: HashMap::new(),
+            },
+        );
+        Ok(())
+    }
+
+    // ends the channel's game if it's the one named, returns its results
+    pub fn stop(&self, channel: &str, name: &str) -> Option<String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.get(channel)?.game.name() != name {
+            return None;
+        }
+        sessions.remove(channel).map(|session| session.results())
+    }
+
+    pub async fn on_message(bot: &BorrowBot, msg: &PrivmsgMessage) {
+        if msg.message_text.starts_with('&') {
+            return;
+        }
+        let uid = match parse_uid(&msg.sender.id) {
+            Ok(uid) => uid,
+            Err(_) => return,
+        };
+        let channel = msg.channel_login.to_lowercase();
+        let login = msg.sender.login.to_lowercase();
+        let games = bot.games();
+
+        let (name, messages, points) = {
+            let mut sessions = games.sessions.lock().unwrap();
+            let session = match sessions.get_mut(&channel) {
+                Some(session) => session,
+                None => return,
+            };
+            let mut update =
+                match session
+                    .game
+                    .on_message(&login, msg.message_text.trim(), Instant::now())
+                {
+                    Some(update) => update,
+                    None => return,
+                };
+
+            if update.points > 0 {
+                let score = session.scores.entry(uid).or_insert((login.clone(), 0));
+                score.0 = login.clone();
+                score.1 += update.points;
+                if let Some(message) = update.messages.first_mut() {
+                    message.push_str(&format!(" (+{}, {} this game)", update.points, score.1));
+                }
+            }
+            let name = session.game.name();
+            if update.over {
+                update.messages.push(session.results());
+                sessions.remove(&channel);
+            }
+            (name, update.messages, update.points)
+        };
+
+        bot.messenger().announce(&channel, messages).await;
+        if points > 0 {
+            // saved in the background, chat doesn't wait for the database
+            let db = Arc::clone(&games.db);
+            tokio::spawn(async move {
+                if let Err(e) = db.add_game_score(&channel, name, uid, &login, points).await {
+                    eprintln!(
+                        "Error saving the {} score of {} in #{}: {}",
+                        name, login, channel, e
+                    );
+                }
+            });
+        }
+    }
+
+    pub fn start(bot: Arc<BorrowBot>) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(GAME_TICK).await;
+                let announcements = bot.games().tick();
+                for (channel, messages) in announcements {
+                    bot.messenger().announce(&channel, messages).await;
+                }
+            }
+        });
+    }
+
+    // moves every game along and ends the ones that are over, returns what to announce where
+    fn tick(&self) -> Vec<(String, Vec<String>)> {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        let mut announcements = Vec::new();
+        let mut finished = Vec::new();
+
+        for (channel, session) in sessions.iter_mut() {
+            let mut update = session.game.on_tick(now);
+            if update.over {
+                update.messages.push(session.results());
+                finished.push(channel.clone());
+            }
+            if !update.messages.is_empty() {
+                announcements.push((channel.clone(), update.messages));
+            }
+        }
+
+        for channel in finished {
+            sessions.remove(&channel);
+        }
+        announcements
+    }
+}
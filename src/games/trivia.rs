@@ -0,0 +1,357 @@
+use std::time::Duration;
+
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use tokio::time::Instant;
+
+use super::{hint, Game, Update};
+use crate::types::DataDir;
+
+// a hint goes out every time this passes without a right answer, until the question times out
+const HINT_INTERVAL: Duration = Duration::from_secs(15);
+const MAX_HINTS: usize = 2;
+const QUESTION_TIMEOUT: Duration = Duration::from_secs(45);
+
+// the pause after a question before the next one is asked
+const QUESTION_BREAK: Duration = Duration::from_secs(5);
+
+// a game stops by itself after this many questions in a row went unanswered
+const MAX_UNANSWERED: usize = 3;
+
+pub const DEFAULT_ROUNDS: usize = 10;
+pub const MAX_ROUNDS: usize = 50;
+
+// what a right answer is worth before any hints, every hint takes one off
+const ANSWER_POINTS: i64 = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Question {
+    pub question: String,
+
+    // every answer that counts, the first one is shown
+    pub answers: Vec<String>,
+    pub category: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize)]
+struct JsonQuestion {
+    question: String,
+    answer: Option<OneOrMany>,
+    #[serde(default)]
+    answers: Vec<String>,
+    category: Option<String>,
+}
+
+// Splits a line of CSV into its fields, fields in double quotes can hold commas and "" for a quote
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields.into_iter().map(|f| f.trim().to_owned()).collect()
+}
+
+// Reads a pack: a JSON list of {"question", "answer" or "answers", "category"}, or CSV lines of
+// question,answer[,other answers...]. Questions without an answer are left out
+pub fn parse_pack(file_name: &str, contents: &str) -> Result<Vec<Question>, String> {
+    let questions: Vec<Question> = if file_name.ends_with(".csv") {
+        contents
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(csv_fields)
+            .filter(|fields| !fields[0].eq_ignore_ascii_case("question"))
+            .map(|mut fields| Question {
+                question: fields.remove(0),
+                answers: fields,
+                category: None,
+            })
+            .collect()
+    } else {
+        serde_json::from_str::<Vec<JsonQuestion>>(contents)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|raw| {
+                let mut answers = match raw.answer {
+                    Some(OneOrMany::One(answer)) => vec![answer],
+                    Some(OneOrMany::Many(answers)) => answers,
+                    None => Vec::new(),
+                };
+                answers.extend(raw.answers);
+                Question {
+                    question: raw.question.trim().to_owned(),
+                    answers,
+                    category: raw.category.filter(|category| !category.is_empty()),
+                }
+            })
+            .collect()
+    };
+
+    Ok(questions
+        .into_iter()
+        .map(|question| Question {
+            answers: question
+                .answers
+                .into_iter()
+                .map(|answer| answer.trim().to_owned())
+                .filter(|answer| !normalize(answer).is_empty())
+                .collect(),
+            ..question
+        })
+        .filter(|question| !question.question.is_empty() && !question.answers.is_empty())
+        .collect())
+}
+
+// Loads data/trivia/<name>.json, or .csv if there's no JSON pack by that name. Err is what to
+// tell chat. Packs are small local files, they're read right away rather than on the blocking pool
+pub fn load_pack(data_dir: &DataDir, name: &str) -> Result<Vec<Question>, String> {
+    let candidates = if name.contains('.') {
+        vec![name.to_owned()]
+    } else {
+        vec![format!("{}.json", name), format!("{}.csv", name)]
+    };
+
+    for file_name in candidates {
+        let path = data_dir
+            .file("trivia", &file_name)
+            .ok_or_else(|| "Sorry, that's not a pack name I can use".to_owned())?;
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                eprintln!("Error reading trivia pack {}: {}", path.display(), e);
+                return Err(format!("Sorry, I couldn't read the {} pack", name));
+            }
+        };
+        return match parse_pack(&file_name, &contents) {
+            Ok(questions) if questions.is_empty() => {
+                Err(format!("There are no questions in the {} pack", name))
+            }
+            Ok(questions) => Ok(questions),
+            Err(e) => {
+                eprintln!("Error reading trivia pack {}: {}", path.display(), e);
+                Err(format!(
+                    "Sorry, the {} pack isn't a list of questions",
+                    name
+                ))
+            }
+        };
+    }
+
+    Err(format!("There's no trivia pack called {}", name))
+}
+
+// the names of the packs in data/trivia, sorted
+pub fn list_packs(data_dir: &DataDir) -> Vec<String> {
+    let entries = match std::fs::read_dir(data_dir.folder("trivia")) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut packs = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_pack = matches!(
+            path.extension().and_then(|ext| ext.to_str()),
+            Some("json" | "csv")
+        );
+        if let (true, Some(name)) = (is_pack, path.file_stem().and_then(|s| s.to_str())) {
+            packs.push(name.to_owned());
+        }
+    }
+    packs.sort();
+    packs.dedup();
+    packs
+}
+
+// lowercase words of letters and digits, without a leading "the", "a" or "an"
+pub fn normalize(text: &str) -> String {
+    let cleaned: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    let mut words: Vec<&str> = cleaned.split_whitespace().collect();
+    if words.len() > 1 && matches!(words[0], "the" | "a" | "an") {
+        words.remove(0);
+    }
+    words.join(" ")
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// The answer the guess matches. Typos are forgiven on longer answers, one for 4 to 7 characters
+// and two beyond that, numbers have to be exact
+pub fn find_answer<'a>(guess: &str, answers: &'a [String]) -> Option<&'a str> {
+    let guess = normalize(guess);
+    if guess.is_empty() {
+        return None;
+    }
+
+    answers
+        .iter()
+        .find(|answer| {
+            let answer = normalize(answer);
+            let allowed = match answer.chars().count() {
+                _ if answer.chars().any(|c| c.is_numeric()) => 0,
+                0..=3 => 0,
+                4..=7 => 1,
+                _ => 2,
+            };
+            edit_distance(&guess, &answer) <= allowed
+        })
+        .map(|answer| answer.as_str())
+}
+
+enum Round {
+    Asking {
+        question: Question,
+        asked_at: Instant,
+        hints: usize,
+    },
+    Waiting {
+        until: Instant,
+    },
+}
+
+// A game of trivia: questions are asked one after another with a short break in between, hints
+// go out while nobody has the answer and a question times out after a while
+pub struct TriviaGame {
+    // still to be asked, the next one last
+    questions: Vec<Question>,
+    rounds: usize,
+    asked: usize,
+    round: Round,
+    unanswered: usize,
+}
+
+impl TriviaGame {
+    // asks rounds of the questions in a random order, the first one right away
+    pub fn new(mut questions: Vec<Question>, rounds: usize) -> Self {
+        questions.shuffle(&mut rand::thread_rng());
+
+        Self {
+            rounds: rounds.min(questions.len()),
+            questions,
+            asked: 0,
+            round: Round::Waiting {
+                until: Instant::now(),
+            },
+            unanswered: 0,
+        }
+    }
+}
+
+impl Game for TriviaGame {
+    fn name(&self) -> &'static str {
+        "trivia"
+    }
+
+    fn title(&self) -> &'static str {
+        "Trivia"
+    }
+
+    fn on_message(&mut self, login: &str, text: &str, now: Instant) -> Option<Update> {
+        let (answer, hints) = match &self.round {
+            Round::Asking {
+                question, hints, ..
+            } => (find_answer(text, &question.answers)?.to_owned(), *hints),
+            Round::Waiting { .. } => return None,
+        };
+
+        self.unanswered = 0;
+        self.round = Round::Waiting {
+            until: now + QUESTION_BREAK,
+        };
+        Some(Update::score(
+            format!("{} got it: {}", login, answer),
+            ANSWER_POINTS - hints as i64,
+        ))
+    }
+
+    fn on_tick(&mut self, now: Instant) -> Update {
+        match &mut self.round {
+            Round::Asking {
+                question,
+                asked_at,
+                hints,
+            } => {
+                let elapsed = now.duration_since(*asked_at);
+                if elapsed >= QUESTION_TIMEOUT {
+                    let mut update =
+                        Update::say(format!("Time's up, the answer was {}", question.answers[0]));
+                    self.unanswered += 1;
+                    self.round = Round::Waiting {
+                        until: now + QUESTION_BREAK,
+                    };
+                    if self.unanswered >= MAX_UNANSWERED {
+                        update
+                            .messages
+                            .push("Nobody's playing, stopping trivia".to_owned());
+                        update = update.end();
+                    }
+                    update
+                } else if *hints < MAX_HINTS && elapsed >= HINT_INTERVAL * (*hints as u32 + 1) {
+                    *hints += 1;
+                    Update::say(format!("Hint: {}", hint(&question.answers[0], *hints)))
+                } else {
+                    Update::default()
+                }
+            }
+            Round::Waiting { until } if *until <= now => {
+                let question = match self.questions.pop().filter(|_| self.asked < self.rounds) {
+                    Some(question) => question,
+                    None => return Update::default().end(),
+                };
+                self.asked += 1;
+                let category = match &question.category {
+                    Some(category) => format!("[{}] ", category),
+                    None => String::new(),
+                };
+                let update = Update::say(format!(
+                    "Question {}/{}: {}{}",
+                    self.asked, self.rounds, category, question.question
+                ));
+                self.round = Round::Asking {
+                    question,
+                    asked_at: now,
+                    hints: 0,
+                };
+                update
+            }
+            Round::Waiting { .. } => Update::default(),
+        }
+    }
+}
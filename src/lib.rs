@@ -1,8 +1,22 @@
+pub mod afk;
 pub mod api;
 pub mod bot;
 pub mod commandhandler;
 pub mod commands;
+pub mod counters;
 pub mod database;
+pub mod emotestats;
+pub mod error;
+pub mod games;
 pub mod logging;
+pub mod markov;
 pub mod messenger;
+pub mod notifications;
+pub mod points;
+pub mod polls;
+pub mod profiles;
+pub mod quotes;
+pub mod reminders;
+pub mod streams;
+pub mod timers;
 pub mod types;
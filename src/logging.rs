@@ -1,32 +1,170 @@
 use core::convert::TryFrom;
-use tokio_postgres::NoTls;
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::Serialize;
+use tokio::sync::Mutex;
 use twitch_irc::message::{AsRawIRC, IRCMessage, PrivmsgMessage};
 
+use crate::database::{migrations, DBError, HealthCheck, LogRepository, PgPool};
+use crate::emotestats::EmoteUsage;
+use crate::error::BotError;
+use crate::types::parse_uid;
+
+const LOG_DB_CONFIG: &str = "host=localhost user=postgres dbname=logs";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct UserActivity {
+    pub first_seen: DateTime<Utc>,
+    pub first_channel: String,
+    pub last_seen: DateTime<Utc>,
+    pub last_channel: String,
+
+    // how many different channels they chatted in
+    pub channels: usize,
+
+    // every login they chatted under, oldest first
+    pub names: Vec<String>,
+}
+
+impl UserActivity {
+    // Sums up (channel, username, first timestamp, last timestamp) rows, one per channel and
+    // name the user chatted under
+    pub fn from_sightings(sightings: &[(String, String, i64, i64)]) -> Option<Self> {
+        let first = sightings.iter().min_by_key(|(_, _, first, _)| *first)?;
+        let last = sightings.iter().max_by_key(|(_, _, _, last)| *last)?;
+
+        let channels: HashSet<&String> = sightings.iter().map(|(channel, ..)| channel).collect();
+
+        let mut names: HashMap<&String, i64> = HashMap::new();
+        for (_, name, first, _) in sightings {
+            let seen = names.entry(name).or_insert(*first);
+            *seen = (*seen).min(*first);
+        }
+        let mut names: Vec<(&String, i64)> = names.into_iter().collect();
+        names.sort_by_key(|(name, first)| (*first, (*name).clone()));
+
+        Some(Self {
+            first_seen: from_timestamp(first.2),
+            first_channel: first.0.clone(),
+            last_seen: from_timestamp(last.3),
+            last_channel: last.0.clone(),
+            channels: channels.len(),
+            names: names.into_iter().map(|(name, _)| name.clone()).collect(),
+        })
+    }
+}
+
+fn from_timestamp(timestamp: i64) -> DateTime<Utc> {
+    DateTime::from_utc(NaiveDateTime::from_timestamp(timestamp, 0), Utc)
+}
+
 pub struct LogController {
-    client: tokio_postgres::Client,
+    pool: PgPool,
+
+    // channels that already have a message table, mirrors the log_channels table
+    log_channels: Mutex<HashSet<String>>,
 }
 
 impl LogController {
     pub async fn new() -> Self {
-        let (client, connection) =
-            tokio_postgres::connect("host=localhost user=postgres dbname=logs", NoTls)
-                .await
-                .unwrap();
+        let pool = PgPool::new("log", LOG_DB_CONFIG);
+
+        let mut client = pool
+            .get()
+            .await
+            .expect("Couldn't connect to the log database");
+        if let Err(e) = migrations::run(&mut client, migrations::LOGS).await {
+            panic!("Error migrating log database: {}", e);
+        }
 
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("connection error: {}", e);
-            }
-        });
+        let rows = client
+            .query("SELECT channel FROM log_channels", &[])
+            .await
+            .unwrap();
+        let log_channels = Mutex::new(rows.iter().map(|row| row.get(0)).collect());
+        drop(client);
+
+        pool.start_health_check_loop();
+
+        LogController { pool, log_channels }
+    }
 
-        LogController { client }
+    pub async fn schema_version(&self) -> Result<i32, migrations::MigrationError> {
+        let client = self.pool.get().await?;
+        migrations::current_version(&client).await
     }
 
-    pub async fn log_message(&self, msg: &PrivmsgMessage) {
+    // creates the message table for a channel the first time we see a message from it
+    pub async fn ensure_channel_table(&self, channel: &str) -> Result<(), DBError> {
+        let channel = channel.to_lowercase();
+        let mut log_channels = self.log_channels.lock().await;
+        if log_channels.contains(&channel) {
+            return Ok(());
+        }
+
+        let table_name = format!("channel_{}", channel);
+        let create_statement = format!(
+            "CREATE TABLE IF NOT EXISTS {} (timestamp bigint, user_id int, username TEXT, message TEXT); \
+            CREATE INDEX IF NOT EXISTS {}_username_idx ON {} (username, timestamp); \
+            CREATE INDEX IF NOT EXISTS {}_user_id_idx ON {} (user_id, timestamp);",
+            table_name, table_name, table_name, table_name, table_name
+        );
+
+        let client = self.pool.get().await?;
+        client.batch_execute(&create_statement[..]).await?;
+        client
+            .execute(
+                "INSERT INTO log_channels (channel) VALUES ($1) ON CONFLICT (channel) DO NOTHING",
+                &[&channel],
+            )
+            .await?;
+
+        log_channels.insert(channel);
+        Ok(())
+    }
+
+    async fn has_channel_table(&self, channel: &str) -> bool {
+        self.log_channels
+            .lock()
+            .await
+            .contains(&channel.to_lowercase())
+    }
+}
+
+impl HealthCheck for LogController {
+    fn is_healthy(&self) -> bool {
+        self.pool.is_healthy()
+    }
+}
+
+#[async_trait]
+impl LogRepository for LogController {
+    async fn log_error(
+        &self,
+        command: &str,
+        channel: &str,
+        username: &str,
+        error: &BotError,
+    ) -> Result<(), DBError> {
+        self.pool
+            .get()
+            .await?
+            .execute(
+                "INSERT INTO error_log (command, channel, username, error) VALUES ($1, $2, $3, $4)",
+                &[&command, &channel, &username, &error.to_string()],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn log_message(&self, msg: &PrivmsgMessage) -> Result<(), BotError> {
         let table_name = &format!("channel_{}", msg.channel_login.to_lowercase());
 
         let timestamp = &msg.server_timestamp.timestamp();
-        let user_id: &i32 = &msg.sender.id.parse().unwrap();
+        let user_id: &i32 = &parse_uid(&msg.sender.id)?;
         let username = &msg.sender.login;
         let message = &msg.source.as_raw_irc();
 
@@ -35,67 +173,63 @@ impl LogController {
             table_name
         );
 
-        if let Err(_) = self
-            .client
+        self.ensure_channel_table(&msg.channel_login).await?;
+
+        self.pool
+            .get()
+            .await?
             .execute(
                 &insert_statement[..],
                 &[timestamp, user_id, username, message],
             )
-            .await
-        {
-            let create_statement = format!(
-                "CREATE TABLE {} (timestamp bigint, user_id int, username TEXT, message TEXT)",
-                table_name
-            );
-
-            self.client
-                .execute(&create_statement[..], &[])
-                .await
-                .unwrap();
-
-            self.client
-                .execute(
-                    &insert_statement[..],
-                    &[timestamp, user_id, username, message],
-                )
-                .await
-                .unwrap();
-        }
+            .await?;
+
+        Ok(())
     }
 
-    pub async fn get_last_message_from_username(
+    async fn get_last_message_from_username(
         &self,
-        channel: &String,
-        username: &String,
-    ) -> Option<(i64, String)> {
+        channel: &str,
+        username: &str,
+        skip: i64,
+    ) -> Result<Option<(i64, String)>, BotError> {
+        if !self.has_channel_table(channel).await {
+            return Ok(None);
+        }
+
         let table_name = &format!("channel_{}", channel.to_lowercase());
 
         let query = format!(
-            "SELECT timestamp, message FROM {} WHERE username = $1 ORDER BY timestamp DESC LIMIT 1",
+            "SELECT timestamp, message FROM {} WHERE username = $1 \
+            ORDER BY timestamp DESC LIMIT 1 OFFSET $2",
             table_name
         );
 
-        if let Ok(row) = self
-            .client
-            .query_one(&query[..], &[&username.to_lowercase()])
-            .await
-        {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_opt(&query[..], &[&username.to_lowercase(), &skip])
+            .await?;
+
+        row.map(|row| {
             let timestamp: i64 = row.get(0);
             let message: String = row.get(1);
-            let message = PrivmsgMessage::try_from(IRCMessage::parse(&message[..]).unwrap())
-                .unwrap()
-                .message_text;
-            Some((timestamp, message))
-        } else {
-            None
-        }
+            let message = message_text(&message)?;
+            Ok((timestamp, message))
+        })
+        .transpose()
     }
 
-    pub async fn get_random_message_from_username(
+    async fn get_random_message_from_username(
         &self,
-        channel: &String,
-        username: &String,
-    ) -> Option<(i64, String)> {
+        channel: &str,
+        username: &str,
+    ) -> Result<Option<(i64, String)>, BotError> {
+        if !self.has_channel_table(channel).await {
+            return Ok(None);
+        }
+
         let table_name = &format!("channel_{}", channel.to_lowercase());
 
         let query = format!(
@@ -104,23 +238,30 @@ impl LogController {
             table_name, table_name
         );
 
-        if let Ok(row) = self
-            .client
-            .query_one(&query[..], &[&username.to_lowercase()])
-            .await
-        {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_opt(&query[..], &[&username.to_lowercase()])
+            .await?;
+
+        row.map(|row| {
             let timestamp: i64 = row.get(0);
             let message: String = row.get(1);
-            let message = PrivmsgMessage::try_from(IRCMessage::parse(&message[..]).unwrap())
-                .unwrap()
-                .message_text;
-            Some((timestamp, message))
-        } else {
-            None
-        }
+            let message = message_text(&message)?;
+            Ok((timestamp, message))
+        })
+        .transpose()
     }
 
-    pub async fn get_random_message(&self, channel: &String) -> Option<(i64, String, String)> {
+    async fn get_random_message(
+        &self,
+        channel: &str,
+    ) -> Result<Option<(i64, String, String)>, BotError> {
+        if !self.has_channel_table(channel).await {
+            return Ok(None);
+        }
+
         let table_name = &format!("channel_{}", channel.to_lowercase());
 
         let query = format!(
@@ -129,16 +270,230 @@ impl LogController {
             table_name, table_name
         );
 
-        if let Ok(row) = self.client.query_one(&query[..], &[]).await {
+        let row = self.pool.get().await?.query_opt(&query[..], &[]).await?;
+
+        row.map(|row| {
             let timestamp: i64 = row.get(0);
             let username: String = row.get(1);
             let message: String = row.get(2);
-            let message = PrivmsgMessage::try_from(IRCMessage::parse(&message[..]).unwrap())
-                .unwrap()
-                .message_text;
-            Some((timestamp, username, message))
-        } else {
-            None
+            let message = message_text(&message)?;
+            Ok((timestamp, username, message))
+        })
+        .transpose()
+    }
+
+    async fn get_user_activity(&self, uid: i32) -> Result<Option<UserActivity>, DBError> {
+        let log_channels: Vec<String> = self.log_channels.lock().await.iter().cloned().collect();
+        if log_channels.is_empty() {
+            return Ok(None);
         }
+
+        // one pass over every channel table, channel logins are safe to inline since they
+        // already name the tables
+        let query = log_channels
+            .iter()
+            .map(|channel| {
+                format!(
+                    "SELECT '{}', username, min(timestamp), max(timestamp) FROM channel_{} \
+                    WHERE user_id = $1 GROUP BY username",
+                    channel, channel
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(" UNION ALL ");
+
+        let rows = self.pool.get().await?.query(&query[..], &[&uid]).await?;
+        let sightings: Vec<(String, String, i64, i64)> = rows
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2), row.get(3)))
+            .collect();
+
+        Ok(UserActivity::from_sightings(&sightings))
+    }
+
+    async fn record_emote_usage(
+        &self,
+        channel: &str,
+        day: NaiveDate,
+        usage: &[EmoteUsage],
+    ) -> Result<(), DBError> {
+        let names: Vec<&str> = usage.iter().map(|u| u.name.as_str()).collect();
+        let providers: Vec<&str> = usage.iter().map(|u| u.provider.as_str()).collect();
+        let uses: Vec<i64> = usage.iter().map(|u| u.uses).collect();
+
+        self.pool
+            .get()
+            .await?
+            .execute(
+                "INSERT INTO emote_usage (channel, emote, provider, day, uses) \
+                SELECT $1, emote, provider, $2, uses \
+                FROM UNNEST($3::text[], $4::text[], $5::bigint[]) AS u (emote, provider, uses) \
+                ON CONFLICT (channel, emote, provider, day) \
+                DO UPDATE SET uses = emote_usage.uses + EXCLUDED.uses",
+                &[&channel, &day, &names, &providers, &uses],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_emote_count(
+        &self,
+        channel: &str,
+        emote: &str,
+    ) -> Result<Option<(i64, NaiveDate)>, DBError> {
+        let row = self
+            .pool
+            .get()
+            .await?
+            .query_one(
+                "SELECT SUM(uses)::bigint, MAX(day) FROM emote_usage \
+                WHERE channel = $1 AND emote = $2",
+                &[&channel, &emote],
+            )
+            .await?;
+
+        let uses: Option<i64> = row.get(0);
+        let last_used: Option<NaiveDate> = row.get(1);
+        Ok(uses.zip(last_used))
+    }
+
+    async fn get_top_emotes(
+        &self,
+        channel: &str,
+        since: Option<NaiveDate>,
+        limit: i64,
+    ) -> Result<Vec<(String, i64)>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                "SELECT emote, SUM(uses)::bigint AS total FROM emote_usage \
+                WHERE channel = $1 AND ($2::date IS NULL OR day >= $2) \
+                GROUP BY emote ORDER BY total DESC, emote LIMIT $3",
+                &[&channel, &since, &limit],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    async fn get_emote_last_used(
+        &self,
+        channel: &str,
+    ) -> Result<HashMap<String, NaiveDate>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                "SELECT emote, MAX(day) FROM emote_usage WHERE channel = $1 GROUP BY emote",
+                &[&channel],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    async fn record_markov(
+        &self,
+        models: &[String],
+        transitions: &[(String, String, i64)],
+    ) -> Result<(), DBError> {
+        let prefixes: Vec<&str> = transitions.iter().map(|t| t.0.as_str()).collect();
+        let nexts: Vec<&str> = transitions.iter().map(|t| t.1.as_str()).collect();
+        let counts: Vec<i64> = transitions.iter().map(|t| t.2).collect();
+
+        self.pool
+            .get()
+            .await?
+            .execute(
+                "INSERT INTO markov (model, prefix, next, count) \
+                SELECT model, prefix, next, count FROM UNNEST($1::text[]) AS m (model) \
+                CROSS JOIN UNNEST($2::text[], $3::text[], $4::bigint[]) AS t (prefix, next, count) \
+                ON CONFLICT (model, prefix, next) \
+                DO UPDATE SET count = markov.count + EXCLUDED.count",
+                &[&models, &prefixes, &nexts, &counts],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_markov_transitions(
+        &self,
+        model: &str,
+        prefix: &str,
+    ) -> Result<Vec<(String, i64)>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                "SELECT next, count FROM markov WHERE model = $1 AND prefix = $2",
+                &[&model, &prefix],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
     }
+
+    async fn get_markov_prefixes(&self, model: &str, word: &str) -> Result<Vec<String>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query(
+                "SELECT prefix FROM (SELECT DISTINCT prefix FROM markov \
+                WHERE model = $1 AND right(prefix, length($2) + 1) = ' ' || $2) AS p \
+                ORDER BY random() LIMIT 100",
+                &[&model, &word],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    async fn delete_markov_model(&self, model: &str) -> Result<(), DBError> {
+        self.pool
+            .get()
+            .await?
+            .execute("DELETE FROM markov WHERE model = $1", &[&model])
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_markov_optouts(&self) -> Result<HashSet<i32>, DBError> {
+        let rows = self
+            .pool
+            .get()
+            .await?
+            .query("SELECT uid FROM markov_optouts", &[])
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    async fn set_markov_optout(&self, uid: i32, opted_out: bool) -> Result<bool, DBError> {
+        let query = if opted_out {
+            "INSERT INTO markov_optouts (uid) VALUES ($1) ON CONFLICT DO NOTHING"
+        } else {
+            "DELETE FROM markov_optouts WHERE uid = $1"
+        };
+        let changed = self.pool.get().await?.execute(query, &[&uid]).await?;
+
+        Ok(changed > 0)
+    }
+}
+
+// messages are logged as raw IRC, this pulls the chat text back out of one
+fn message_text(raw: &str) -> Result<String, BotError> {
+    let irc_message =
+        IRCMessage::parse(raw).map_err(|e| BotError::InvalidMessage(e.to_string()))?;
+    let privmsg = PrivmsgMessage::try_from(irc_message)
+        .map_err(|e| BotError::InvalidMessage(e.to_string()))?;
+
+    Ok(privmsg.message_text)
 }
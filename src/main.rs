@@ -1,9 +1,25 @@
+use std::env;
 use std::sync::Arc;
 
 use borrowbot::bot::BorrowBot;
+use borrowbot::database::DBController;
+use borrowbot::logging::LogController;
 
 #[tokio::main]
 async fn main() {
+    // `borrowbot migrate` only brings both databases up to date and exits,
+    // migrations are otherwise also applied when the bot starts
+    if let Some("migrate") = env::args().nth(1).as_deref() {
+        let db = DBController::new().await;
+        let logs = LogController::new().await;
+        println!(
+            "Main database at schema version {}, log database at schema version {}",
+            db.schema_version().await.unwrap(),
+            logs.schema_version().await.unwrap()
+        );
+        return;
+    }
+
     let bot = Arc::new(BorrowBot::new().await);
 
     BorrowBot::run(bot).await;
@@ -0,0 +1,177 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use rand::Rng;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::bot::BorrowBot;
+use crate::database::{DBError, LogRepository};
+use crate::types::parse_uid;
+
+// how many words predict the next one
+const ORDER: usize = 2;
+
+// longest sentence generated, in words
+pub const MAX_WORDS: usize = 30;
+
+// stand-ins for the start and end of a message, chat can't contain them
+const START: &str = "\u{2}";
+const END: &str = "\u{3}";
+
+// a user's own model, learned from everything they say in every channel
+pub fn user_model(uid: i32) -> String {
+    format!("user:{}", uid)
+}
+
+// (prefix, next word) for every step through the message, prefixes are ORDER words joined by
+// spaces and padded with START at the beginning. The last step goes to END
+pub fn transitions(text: &str) -> Vec<(String, String)> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| word.replace(START, "").replace(END, ""))
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut padded: Vec<&str> = vec![START; ORDER];
+    padded.extend(words.iter().map(|word| word.as_str()));
+    padded.push(END);
+
+    padded
+        .windows(ORDER + 1)
+        .map(|window| (window[..ORDER].join(" "), window[ORDER].to_owned()))
+        .collect()
+}
+
+// picks a word with a chance proportional to how often it followed
+fn pick_weighted(candidates: &[(String, i64)]) -> Option<&str> {
+    let total: i64 = candidates.iter().map(|(_, count)| count).sum();
+    if total <= 0 {
+        return None;
+    }
+
+    let mut roll = rand::thread_rng().gen_range(0..total);
+    for (word, count) in candidates {
+        if roll < *count {
+            return Some(word);
+        }
+        roll -= count;
+    }
+    None
+}
+
+// Walks the model from the start of a message, or on from a random place the seed word was said,
+// until a message would end or MAX_WORDS is reached. None if there's nothing to start from
+pub async fn generate(
+    logs: &dyn LogRepository,
+    model: &str,
+    seed: Option<&str>,
+) -> Result<Option<String>, DBError> {
+    let (mut state, mut words): (Vec<String>, Vec<String>) = match seed {
+        None => (vec![START.to_owned(); ORDER], Vec::new()),
+        Some(seed) => {
+            let prefixes = logs.get_markov_prefixes(model, seed).await?;
+            if prefixes.is_empty() {
+                return Ok(None);
+            }
+            // the words before the seed steer what comes next but aren't said
+            let prefix = &prefixes[rand::thread_rng().gen_range(0..prefixes.len())];
+            (
+                prefix.split(' ').map(|word| word.to_owned()).collect(),
+                vec![seed.to_owned()],
+            )
+        }
+    };
+
+    while words.len() < MAX_WORDS {
+        let candidates = logs.get_markov_transitions(model, &state.join(" ")).await?;
+        let next = match pick_weighted(&candidates) {
+            Some(next) if next != END => next.to_owned(),
+            _ => break,
+        };
+
+        state.remove(0);
+        state.push(next.clone());
+        words.push(next);
+    }
+
+    if words.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(words.join(" ")))
+}
+
+// Feeds chat into the channel's and the sender's models as it comes in, except from people who
+// opted out. Which users opted out is kept in memory so chat doesn't need a query for it
+pub struct Markov {
+    logs: Arc<dyn LogRepository>,
+    opted_out: Mutex<HashSet<i32>>,
+}
+
+impl Markov {
+    pub async fn new(logs: Arc<dyn LogRepository>) -> Result<Self, DBError> {
+        let opted_out = logs.get_markov_optouts().await?;
+
+        Ok(Self {
+            logs,
+            opted_out: Mutex::new(opted_out),
+        })
+    }
+
+    pub fn is_opted_out(&self, uid: i32) -> bool {
+        self.opted_out.lock().unwrap().contains(&uid)
+    }
+
+    // Opting out also forgets the user's own model. What they said before stays part of the
+    // channel's model, there's no telling their words apart in there
+    pub async fn set_opted_out(&self, uid: i32, opted_out: bool) -> Result<bool, DBError> {
+        let changed = self.logs.set_markov_optout(uid, opted_out).await?;
+        if opted_out {
+            self.logs.delete_markov_model(&user_model(uid)).await?;
+            self.opted_out.lock().unwrap().insert(uid);
+        } else {
+            self.opted_out.lock().unwrap().remove(&uid);
+        }
+
+        Ok(changed)
+    }
+
+    // the counts are saved in the background, chat doesn't wait for the logs
+    pub fn learn(bot: &BorrowBot, msg: &PrivmsgMessage) {
+        // commands aren't how anyone talks
+        if msg.message_text.starts_with('&') {
+            return;
+        }
+        let uid = match parse_uid(&msg.sender.id) {
+            Ok(uid) => uid,
+            Err(_) => return,
+        };
+        let markov = bot.markov();
+        if markov.is_opted_out(uid) {
+            return;
+        }
+
+        let mut counts: HashMap<(String, String), i64> = HashMap::new();
+        for transition in transitions(&msg.message_text) {
+            *counts.entry(transition).or_insert(0) += 1;
+        }
+        if counts.is_empty() {
+            return;
+        }
+
+        let models = [msg.channel_login.to_lowercase(), user_model(uid)];
+        let counts: Vec<(String, String, i64)> = counts
+            .into_iter()
+            .map(|((prefix, next), count)| (prefix, next, count))
+            .collect();
+        let logs = Arc::clone(&markov.logs);
+        let channel = msg.channel_login.clone();
+        tokio::spawn(async move {
+            if let Err(e) = logs.record_markov(&models, &counts).await {
+                eprintln!("Error learning from a message in {}: {}", channel, e);
+            }
+        });
+    }
+}
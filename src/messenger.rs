@@ -1,28 +1,54 @@
 use std::collections::{HashSet, VecDeque};
 use std::sync::Arc;
 
+use async_trait::async_trait;
 use tokio::sync::Mutex;
-use twitch_irc::login::StaticLoginCredentials;
+use twitch_irc::login::LoginCredentials;
 use twitch_irc::message::PrivmsgMessage;
-use twitch_irc::{SecureTCPTransport, TwitchIRCClient};
+use twitch_irc::transport::Transport;
+use twitch_irc::TwitchIRCClient;
 
-use crate::api::banphrase;
+use crate::api::banphrase::Banphrase;
 use crate::types::{CommandResponse, UserContext};
 
+// The parts of the IRC client the bot uses, implemented for TwitchIRCClient over any transport so
+// the bot isn't tied to a real connection to Twitch
+#[async_trait]
+pub trait ChatClient: Send + Sync {
+    async fn say(&self, channel: String, message: String) -> Result<(), String>;
+
+    fn set_wanted_channels(&self, channels: HashSet<String>);
+}
+
+#[async_trait]
+impl<T: Transport, L: LoginCredentials> ChatClient for TwitchIRCClient<T, L> {
+    async fn say(&self, channel: String, message: String) -> Result<(), String> {
+        TwitchIRCClient::say(self, channel, message)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    fn set_wanted_channels(&self, channels: HashSet<String>) {
+        TwitchIRCClient::set_wanted_channels(self, channels)
+    }
+}
+
 pub struct Messenger {
-    irc_client: Arc<TwitchIRCClient<SecureTCPTransport, StaticLoginCredentials>>,
+    irc_client: Arc<dyn ChatClient>,
+    banphrase: Arc<Banphrase>,
     pub message_queue: Arc<Mutex<VecDeque<(String, String)>>>,
 }
 
 impl Messenger {
-    pub fn new(client: TwitchIRCClient<SecureTCPTransport, StaticLoginCredentials>) -> Self {
+    pub fn new(client: Arc<dyn ChatClient>, banphrase: Arc<Banphrase>) -> Self {
         Messenger {
-            irc_client: Arc::new(client),
+            irc_client: client,
+            banphrase,
             message_queue: Arc::new(Mutex::new(VecDeque::new())),
         }
     }
 
-    pub fn client(&self) -> Arc<TwitchIRCClient<SecureTCPTransport, StaticLoginCredentials>> {
+    pub fn client(&self) -> Arc<dyn ChatClient> {
         Arc::clone(&self.irc_client)
     }
 
@@ -48,7 +74,7 @@ impl Messenger {
         let response = format!("@{}, {}", user_context.login, response);
 
         let ensured_response = if questionable_output {
-            if let Ok(is_banned) = banphrase::contains_banphrase(&response).await {
+            if let Ok(is_banned) = self.banphrase.contains_banphrase(&response).await {
                 if !is_banned {
                     response
                 } else {
@@ -65,6 +91,14 @@ impl Messenger {
         (*queue).insert(0, (msg.channel_login.clone(), ensured_response));
     }
 
+    // Queued behind everything else in order, unlike command responses which skip ahead
+    pub async fn announce(&self, channel: &str, messages: Vec<String>) {
+        let mut queue = self.message_queue.lock().await;
+        for message in messages {
+            (*queue).push_back((channel.to_owned(), message));
+        }
+    }
+
     // adhere to global 1 second cooldown
     pub fn sender_loop(&self) {
         let message_queue = Arc::clone(&self.message_queue);
@@ -83,10 +117,12 @@ impl Messenger {
                         ""
                     };
 
-                    irc_client
-                        .say(target_channel, response + same_message_modifier)
+                    if let Err(e) = irc_client
+                        .say(target_channel.clone(), response + same_message_modifier)
                         .await
-                        .unwrap();
+                    {
+                        eprintln!("Error sending message to #{}: {}", target_channel, e);
+                    }
                 }
             }
         });
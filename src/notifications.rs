@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::bot::BorrowBot;
+use crate::streams::StreamEvent;
+
+// twitch allows 500 characters, some room is left for the messenger's duplicate message suffix
+const MAX_MESSAGE_LENGTH: usize = 490;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NotificationKind {
+    // the channel going live
+    Live,
+
+    // the title or category changing while live
+    Title,
+}
+
+impl NotificationKind {
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "live" => Some(NotificationKind::Live),
+            "title" => Some(NotificationKind::Title),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::Live => "live",
+            NotificationKind::Title => "title",
+        }
+    }
+}
+
+// Splits the mentions over as few messages as possible, the first one starts with the header
+pub fn batch_mentions(header: &str, logins: &[String]) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current: String = header.chars().take(MAX_MESSAGE_LENGTH).collect();
+
+    for login in logins {
+        let mention = format!("@{}", login);
+        let separator = if current.is_empty() { "" } else { " " };
+        if current.chars().count() + separator.len() + mention.chars().count() > MAX_MESSAGE_LENGTH
+        {
+            messages.push(current);
+            current = mention;
+        } else {
+            current.push_str(separator);
+            current.push_str(&mention);
+        }
+    }
+
+    if !current.is_empty() {
+        messages.push(current);
+    }
+
+    messages
+}
+
+// the announcement for an event and who wants to hear about it, None if nobody gets pinged for it
+fn announcement(event: &StreamEvent) -> Option<(&str, NotificationKind, String, String)> {
+    match event {
+        StreamEvent::Online {
+            channel,
+            title,
+            category,
+        } => Some((
+            channel,
+            NotificationKind::Live,
+            format!("{} is live! {} [{}]", channel, title, category),
+            format!("{} is live!", channel),
+        )),
+        StreamEvent::TitleChanged { channel, title } => Some((
+            channel,
+            NotificationKind::Title,
+            format!("{} changed the title: {}", channel, title),
+            format!("{} changed the title", channel),
+        )),
+        StreamEvent::CategoryChanged { channel, category } => Some((
+            channel,
+            NotificationKind::Title,
+            format!("{} is now streaming {}", channel, category),
+            format!("{} changed the category", channel),
+        )),
+        StreamEvent::Offline { .. } => None,
+    }
+}
+
+// Pings everyone subscribed whenever the stream tracker reports a change
+pub fn start_notifier(bot: Arc<BorrowBot>) {
+    let mut events = bot.streams().subscribe();
+
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    eprintln!("Notifier fell behind, missed {} stream events", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+
+            let (channel, kind, header, safe_header) = match announcement(&event) {
+                Some(announcement) => announcement,
+                None => continue,
+            };
+
+            let logins = match bot.db().get_notified_users(channel, kind).await {
+                Ok(logins) => logins,
+                Err(e) => {
+                    eprintln!("Error loading notifications for #{}: {}", channel, e);
+                    continue;
+                }
+            };
+            if logins.is_empty() {
+                continue;
+            }
+
+            // titles and categories come from the streamer, so they go past the banphrase api
+            let header = match bot.api().banphrase().contains_banphrase(&header).await {
+                Ok(false) => header,
+                _ => safe_header,
+            };
+
+            bot.messenger()
+                .announce(channel, batch_mentions(&header, &logins))
+                .await;
+        }
+    });
+}
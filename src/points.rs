@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rand::seq::SliceRandom;
+use tokio::time::Instant;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::bot::BorrowBot;
+use crate::database::Database;
+use crate::types::parse_uid;
+
+// the economy is off wherever this command is disabled, earning included
+pub const POINTS_COMMAND: &str = "points";
+
+// earned for chatting, at most once per EARN_INTERVAL per channel
+pub const POINTS_PER_MESSAGE: i64 = 10;
+const EARN_INTERVAL: Duration = Duration::from_secs(60);
+
+// how long a challenged user has to accept a duel
+pub const DUEL_TIMEOUT: Duration = Duration::from_secs(60);
+
+// twitch global emotes, so the reels show up for everyone
+const SLOT_SYMBOLS: [&str; 5] = ["Kappa", "LUL", "PogChamp", "Kreygasm", "SeemsGood"];
+
+// payouts as multiples of the bet, anything else loses it
+const SLOTS_JACKPOT: i64 = 10;
+const SLOTS_PAIR: i64 = 1;
+
+// "all" or a positive number of points
+pub fn parse_amount(amount: &str, balance: i64) -> Option<i64> {
+    match amount {
+        "all" => Some(balance).filter(|balance| *balance > 0),
+        amount => amount.parse::<i64>().ok().filter(|amount| *amount > 0),
+    }
+}
+
+// three reels and what they pay for the bet, the bet itself included
+pub fn spin_slots(bet: i64) -> ([&'static str; 3], i64) {
+    let mut rng = rand::thread_rng();
+    let mut reels = [""; 3];
+    for reel in reels.iter_mut() {
+        *reel = SLOT_SYMBOLS.choose(&mut rng).copied().unwrap_or_default();
+    }
+
+    let payout = if reels[0] == reels[1] && reels[1] == reels[2] {
+        bet * SLOTS_JACKPOT
+    } else if reels[0] == reels[1] || reels[1] == reels[2] || reels[0] == reels[2] {
+        bet * SLOTS_PAIR
+    } else {
+        0
+    };
+
+    (reels, payout)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Duel {
+    pub challenger_uid: i32,
+    pub challenger_login: String,
+    pub amount: i64,
+    pub challenged_at: Instant,
+}
+
+// Hands out points for chatting and keeps the open duel challenges. Balances themselves are only
+// ever changed in the database, each change checks the balance in the same statement or
+// transaction so simultaneous commands can't spend the same points twice
+pub struct Economy {
+    db: Arc<dyn Database>,
+
+    // (channel, uid) -> when they last earned points there
+    earned: Mutex<HashMap<(String, i32), Instant>>,
+
+    // (channel, challenged uid) -> the challenge waiting for them
+    duels: Mutex<HashMap<(String, i32), Duel>>,
+}
+
+impl Economy {
+    pub fn new(db: Arc<dyn Database>) -> Self {
+        Self {
+            db,
+            earned: Mutex::new(HashMap::new()),
+            duels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(bot: &BorrowBot, channel: &str) -> bool {
+        !bot.commands().is_disabled(channel, POINTS_COMMAND)
+    }
+
+    // the points are added in the background, chat doesn't wait for the database
+    pub fn on_message(bot: &BorrowBot, msg: &PrivmsgMessage) {
+        let channel = msg.channel_login.to_lowercase();
+        // commands don't count as chatting, otherwise &points spam would pay
+        if msg.message_text.starts_with('&') || !Self::is_enabled(bot, &channel) {
+            return;
+        }
+        let uid = match parse_uid(&msg.sender.id) {
+            Ok(uid) => uid,
+            Err(_) => return,
+        };
+
+        let economy = bot.economy();
+        {
+            let mut earned = economy.earned.lock().unwrap();
+            let now = Instant::now();
+            let key = (channel.clone(), uid);
+            if earned
+                .get(&key)
+                .is_some_and(|at| now.duration_since(*at) < EARN_INTERVAL)
+            {
+                return;
+            }
+            earned.insert(key, now);
+        }
+
+        let db = Arc::clone(&economy.db);
+        let login = msg.sender.login.clone();
+        tokio::spawn(async move {
+            if let Err(e) = db
+                .add_points(&channel, uid, &login, POINTS_PER_MESSAGE)
+                .await
+            {
+                eprintln!("Error giving points to {} in #{}: {}", login, channel, e);
+            }
+        });
+    }
+
+    // replaces any challenge the user already had waiting in the channel
+    pub fn challenge(&self, channel: &str, challenged_uid: i32, duel: Duel) {
+        self.duels
+            .lock()
+            .unwrap()
+            .insert((channel.to_owned(), challenged_uid), duel);
+    }
+
+    // the challenge waiting for the user, if it hasn't run out
+    pub fn take_challenge(&self, channel: &str, challenged_uid: i32) -> Option<Duel> {
+        self.duels
+            .lock()
+            .unwrap()
+            .remove(&(channel.to_owned(), challenged_uid))
+            .filter(|duel| duel.challenged_at.elapsed() < DUEL_TIMEOUT)
+    }
+
+    // forgets earning times and challenges that ran out, nothing reads them anymore
+    fn prune(&self) {
+        let now = Instant::now();
+        self.earned
+            .lock()
+            .unwrap()
+            .retain(|_, at| now.duration_since(*at) < EARN_INTERVAL);
+        self.duels
+            .lock()
+            .unwrap()
+            .retain(|_, duel| now.duration_since(duel.challenged_at) < DUEL_TIMEOUT);
+    }
+
+    pub fn start(bot: Arc<BorrowBot>) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(EARN_INTERVAL).await;
+                bot.economy().prune();
+            }
+        });
+    }
+}
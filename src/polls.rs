@@ -0,0 +1,369 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::bot::BorrowBot;
+use crate::commands::format_duration;
+use crate::types::parse_uid;
+
+// how often running polls are checked for their end and new votes
+const POLL_TICK: Duration = Duration::from_secs(1);
+
+// the least time between two tallies of a running poll, they're only sent if someone voted
+const TALLY_INTERVAL: Duration = Duration::from_secs(30);
+
+pub const MAX_POLL_OPTIONS: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bet {
+    pub login: String,
+
+    // index into the poll's options
+    pub option: usize,
+    pub amount: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Poll {
+    pub question: String,
+    pub options: Vec<String>,
+
+    // uid -> index into options, the first vote is the one that counts
+    pub votes: HashMap<i32, usize>,
+
+    // None if the poll doesn't take bets, otherwise uid -> their bet
+    pub bets: Option<HashMap<i32, Bet>>,
+    pub ends_at: Instant,
+
+    // votes when the last tally went out
+    tallied_votes: usize,
+    tallied_at: Instant,
+}
+
+impl Poll {
+    pub fn new(question: String, options: Vec<String>, duration: Duration, bets: bool) -> Self {
+        let now = Instant::now();
+
+        Self {
+            question,
+            options,
+            votes: HashMap::new(),
+            bets: if bets { Some(HashMap::new()) } else { None },
+            ends_at: now + duration,
+            tallied_votes: 0,
+            tallied_at: now,
+        }
+    }
+
+    // votes per option, in the order of the options
+    fn counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.options.len()];
+        for option in self.votes.values() {
+            counts[*option] += 1;
+        }
+        counts
+    }
+
+    // the options with the most votes, more than one on a tie and none without votes
+    pub fn leaders(&self) -> Vec<usize> {
+        let counts = self.counts();
+        let most = counts.iter().copied().max().unwrap_or(0);
+        if most == 0 {
+            return Vec::new();
+        }
+
+        (0..counts.len()).filter(|i| counts[*i] == most).collect()
+    }
+
+    // "1) yes: 3 (75%), 2) no: 1 (25%)"
+    pub fn tally(&self) -> String {
+        let counts = self.counts();
+        let total: usize = counts.iter().sum();
+
+        self.options
+            .iter()
+            .zip(counts)
+            .enumerate()
+            .map(|(i, (option, count))| {
+                let percent = (count * 100).checked_div(total).unwrap_or(0);
+                format!("{}) {}: {} ({}%)", i + 1, option, count, percent)
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    // "1) yes 2) no", for announcing the poll
+    pub fn describe_options(&self) -> String {
+        self.options
+            .iter()
+            .enumerate()
+            .map(|(i, option)| format!("{}) {}", i + 1, option))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    fn results(&self) -> String {
+        let leaders = self.leaders();
+        let outcome = match leaders.as_slice() {
+            [] => "nobody voted".to_owned(),
+            [winner] => format!("{} wins", self.options[*winner]),
+            tied => format!(
+                "it's a tie between {}",
+                tied.iter()
+                    .map(|i| self.options[*i].as_str())
+                    .collect::<Vec<&str>>()
+                    .join(" and ")
+            ),
+        };
+
+        format!(
+            "Poll over: {} | {} | {}",
+            self.question,
+            self.tally(),
+            outcome
+        )
+    }
+}
+
+// Bot-run polls, at most one per channel. They're only kept in memory, a restart ends them
+// without results. Bets are taken out of the balance when they're placed and handed back or
+// paid out when the poll ends or is cancelled, so the points can't be spent twice meanwhile
+#[derive(Default)]
+pub struct Polls {
+    polls: Mutex<HashMap<String, Poll>>,
+}
+
+impl Polls {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_running(&self, channel: &str) -> bool {
+        self.polls.lock().unwrap().contains_key(channel)
+    }
+
+    // false if the channel already has a poll running
+    pub fn start_poll(&self, channel: &str, poll: Poll) -> bool {
+        let mut polls = self.polls.lock().unwrap();
+        if polls.contains_key(channel) {
+            return false;
+        }
+        polls.insert(channel.to_owned(), poll);
+        true
+    }
+
+    // drops the channel's poll without results, the caller refunds its bets
+    pub fn cancel(&self, channel: &str) -> Option<Poll> {
+        self.polls.lock().unwrap().remove(channel)
+    }
+
+    // moves the channel's poll end to now, the scheduler announces the results
+    pub fn end_now(&self, channel: &str) -> bool {
+        match self.polls.lock().unwrap().get_mut(channel) {
+            Some(poll) => {
+                poll.ends_at = Instant::now();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Whether the bet could be placed right now, Err is what to tell them
+    pub fn check_bet(&self, channel: &str, uid: i32, bet: &Bet) -> Result<(), String> {
+        let polls = self.polls.lock().unwrap();
+        check_bet(polls.get(channel), channel, uid, bet).map(|_| ())
+    }
+
+    // Places a bet on the running poll, the caller already took the points out of the balance
+    // and hands them back on Err, which is what to tell them
+    pub fn bet(&self, channel: &str, uid: i32, bet: Bet) -> Result<String, String> {
+        let mut polls = self.polls.lock().unwrap();
+        let option = check_bet(polls.get(channel), channel, uid, &bet)?;
+
+        let response = format!("You bet {} points on {}", bet.amount, option);
+        if let Some(bets) = polls.get_mut(channel).and_then(|poll| poll.bets.as_mut()) {
+            bets.insert(uid, bet);
+        }
+        Ok(response)
+    }
+
+    // counts a message that is just an option's number as a vote, no & needed
+    pub fn on_message(bot: &BorrowBot, msg: &PrivmsgMessage) {
+        let choice = match msg.message_text.trim().parse::<usize>() {
+            Ok(choice) if choice >= 1 => choice - 1,
+            _ => return,
+        };
+        let uid = match parse_uid(&msg.sender.id) {
+            Ok(uid) => uid,
+            Err(_) => return,
+        };
+
+        let polls = bot.polls();
+        let mut polls = polls.polls.lock().unwrap();
+        if let Some(poll) = polls.get_mut(&msg.channel_login.to_lowercase()) {
+            if choice < poll.options.len() && poll.ends_at > Instant::now() {
+                poll.votes.entry(uid).or_insert(choice);
+            }
+        }
+    }
+
+    pub fn start(bot: Arc<BorrowBot>) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(POLL_TICK).await;
+                bot.polls().check(&bot).await;
+            }
+        });
+    }
+
+    // announces the results of polls that ended and a tally of running ones that got votes
+    async fn check(&self, bot: &BorrowBot) {
+        let now = Instant::now();
+        let mut finished = Vec::new();
+        let mut tallies = Vec::new();
+        {
+            let mut polls = self.polls.lock().unwrap();
+            let ended: Vec<String> = polls
+                .iter()
+                .filter(|(_, poll)| poll.ends_at <= now)
+                .map(|(channel, _)| channel.clone())
+                .collect();
+            for channel in ended {
+                if let Some(poll) = polls.remove(&channel) {
+                    finished.push((channel, poll));
+                }
+            }
+
+            for (channel, poll) in polls.iter_mut() {
+                if poll.votes.len() > poll.tallied_votes
+                    && now.duration_since(poll.tallied_at) >= TALLY_INTERVAL
+                {
+                    poll.tallied_votes = poll.votes.len();
+                    poll.tallied_at = now;
+                    let left = chrono::Duration::seconds(
+                        poll.ends_at.duration_since(now).as_secs() as i64,
+                    );
+                    tallies.push((
+                        channel.clone(),
+                        format!(
+                            "Poll: {} | {} | {} left",
+                            poll.question,
+                            poll.tally(),
+                            format_duration(left)
+                        ),
+                    ));
+                }
+            }
+        }
+
+        for (channel, tally) in tallies {
+            bot.messenger().announce(&channel, vec![tally]).await;
+        }
+        for (channel, poll) in finished {
+            let mut messages = vec![poll.results()];
+            if let Some(bets) = &poll.bets {
+                if let Some(payout) = settle_bets(bot, &channel, &poll.leaders(), bets).await {
+                    messages.push(payout);
+                }
+            }
+            bot.messenger().announce(&channel, messages).await;
+        }
+    }
+}
+
+// the name of the option bet on if the poll takes the bet, Err is what to tell them
+fn check_bet(poll: Option<&Poll>, channel: &str, uid: i32, bet: &Bet) -> Result<String, String> {
+    let poll = poll
+        .filter(|poll| poll.ends_at > Instant::now())
+        .ok_or_else(|| format!("There's no poll running in #{}", channel))?;
+    if bet.option >= poll.options.len() {
+        return Err(format!(
+            "Pick an option between 1 and {}",
+            poll.options.len()
+        ));
+    }
+    let bets = poll
+        .bets
+        .as_ref()
+        .ok_or_else(|| "This poll doesn't take bets".to_owned())?;
+    if bets.contains_key(&uid) {
+        return Err("You already placed a bet on this poll".to_owned());
+    }
+
+    Ok(poll.options[bet.option].clone())
+}
+
+// hands every bet back, for polls that were cancelled or had no single winner
+pub async fn refund_bets(bot: &BorrowBot, channel: &str, bets: &HashMap<i32, Bet>) {
+    for (uid, bet) in bets {
+        if let Err(e) = bot
+            .db()
+            .add_points(channel, *uid, &bet.login, bet.amount)
+            .await
+        {
+            eprintln!("Error refunding {}'s bet in #{}: {}", bet.login, channel, e);
+        }
+    }
+}
+
+// Splits the losing bets between the winners by the size of their bets, winners get their own
+// bet back on top. Everyone is refunded when there's no single winner
+async fn settle_bets(
+    bot: &BorrowBot,
+    channel: &str,
+    leaders: &[usize],
+    bets: &HashMap<i32, Bet>,
+) -> Option<String> {
+    if bets.is_empty() {
+        return None;
+    }
+    let winner = match leaders {
+        [winner] => *winner,
+        _ => {
+            refund_bets(bot, channel, bets).await;
+            return Some("No clear winner, all bets were refunded".to_owned());
+        }
+    };
+    let db = bot.db();
+
+    let losing: Vec<&Bet> = bets.values().filter(|bet| bet.option != winner).collect();
+    let losers = losing.len();
+    let pool: i64 = losing.iter().map(|bet| bet.amount).sum();
+
+    let winning: Vec<(&i32, &Bet)> = bets
+        .iter()
+        .filter(|(_, bet)| bet.option == winner)
+        .collect();
+    let winning_total: i64 = winning.iter().map(|(_, bet)| bet.amount).sum();
+    if winning_total == 0 {
+        refund_bets(bot, channel, bets).await;
+        return Some("Nobody bet on the winner, all bets were refunded".to_owned());
+    }
+
+    let mut paid = 0;
+    let mut winners = 0;
+    for (uid, bet) in winning {
+        // the product can outgrow an i64, the share itself is never more than the pool
+        let share = i128::from(pool) * i128::from(bet.amount) / i128::from(winning_total);
+        let share = i64::try_from(share).unwrap_or(pool);
+        match db
+            .add_points(channel, *uid, &bet.login, bet.amount.saturating_add(share))
+            .await
+        {
+            Ok(_) => {
+                paid += share;
+                winners += 1;
+            }
+            Err(e) => eprintln!("Error paying {}'s bet in #{}: {}", bet.login, channel, e),
+        }
+    }
+
+    Some(format!(
+        "Bets: {} points from {} losing bets went to {} winners",
+        paid, losers, winners
+    ))
+}
@@ -0,0 +1,186 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hyper::StatusCode;
+use serde::Serialize;
+
+use crate::api::eventsub::JsonRoutes;
+use crate::bot::BorrowBot;
+use crate::error::BotError;
+use crate::logging::UserActivity;
+use crate::types::PermissionLevel;
+
+// What helix and the bot itself know about a user, shown by &user and served as JSON
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct UserProfile {
+    pub id: String,
+    pub login: String,
+
+    // helix leaves out banned and deleted accounts, these are None for them
+    pub display_name: Option<String>,
+    pub broadcaster_type: Option<String>,
+    pub description: Option<String>,
+    pub profile_image_url: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+
+    // None if they never talked where the bot is
+    pub permissions: Option<PermissionLevel>,
+    pub activity: Option<UserActivity>,
+}
+
+impl UserProfile {
+    // None if neither helix nor the bot know the login
+    pub async fn lookup(bot: &BorrowBot, login: &str) -> Result<Option<Self>, BotError> {
+        let helix_user = bot.api().helix().get_user_by_login(login).await?;
+        let known_user = bot.db().get_user_by_name(login).await?;
+        let uid = match (&helix_user, &known_user) {
+            (Some(helix_user), _) => helix_user.id.parse::<i32>().ok(),
+            (None, Some(known_user)) => Some(known_user.uid),
+            (None, None) => None,
+        };
+        let activity = match uid {
+            Some(uid) => bot.logs().get_user_activity(uid).await?,
+            None => None,
+        };
+        let permissions = known_user.map(|known_user| known_user.permissions);
+
+        let profile = match helix_user {
+            Some(helix_user) => Self {
+                id: helix_user.id,
+                login: helix_user.login,
+                display_name: Some(helix_user.display_name),
+                broadcaster_type: non_empty(helix_user.broadcaster_type),
+                description: non_empty(helix_user.description),
+                profile_image_url: non_empty(helix_user.profile_image_url),
+                created_at: DateTime::parse_from_rfc3339(&helix_user.created_at)
+                    .ok()
+                    .map(|created_at| created_at.with_timezone(&Utc)),
+                permissions,
+                activity,
+            },
+            None => match uid {
+                Some(uid) => Self {
+                    id: uid.to_string(),
+                    login: login.to_owned(),
+                    display_name: None,
+                    broadcaster_type: None,
+                    description: None,
+                    profile_image_url: None,
+                    created_at: None,
+                    permissions,
+                    activity,
+                },
+                None => return Ok(None),
+            },
+        };
+
+        Ok(Some(profile))
+    }
+
+    // "Pajlada | id 11 | partner | created 2016-12-14 | bot moderator | ...", the
+    // description goes last so it's what gets cut off
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        match &self.display_name {
+            Some(display_name) if display_name.to_lowercase() == self.login => {
+                parts.push(display_name.clone())
+            }
+            Some(display_name) => parts.push(format!("{} ({})", display_name, self.login)),
+            None => parts.push(format!("{} (banned or deleted)", self.login)),
+        }
+        parts.push(format!("id {}", self.id));
+        if let Some(broadcaster_type) = &self.broadcaster_type {
+            parts.push(broadcaster_type.clone());
+        }
+        if let Some(created_at) = self.created_at {
+            parts.push(format!("created {}", created_at.format("%Y-%m-%d")));
+        }
+
+        if let Some(permissions) = self.permissions {
+            parts.push(format!("bot {}", permissions));
+        }
+
+        if let Some(activity) = &self.activity {
+            parts.push(format!(
+                "first seen {} in #{}",
+                activity.first_seen.format("%Y-%m-%d"),
+                activity.first_channel
+            ));
+            parts.push(format!(
+                "last seen {} in #{}",
+                activity.last_seen.format("%Y-%m-%d"),
+                activity.last_channel
+            ));
+            parts.push(match activity.channels {
+                1 => "seen in 1 channel".to_owned(),
+                channels => format!("seen in {} channels", channels),
+            });
+
+            let previous_names: Vec<&str> = activity
+                .names
+                .iter()
+                .map(|name| name.as_str())
+                .filter(|name| *name != self.login)
+                .collect();
+            if !previous_names.is_empty() {
+                parts.push(format!("also known as {}", previous_names.join(", ")));
+            }
+        }
+
+        if let Some(profile_image_url) = &self.profile_image_url {
+            parts.push(profile_image_url.clone());
+        }
+        if let Some(description) = &self.description {
+            parts.push(description.clone());
+        }
+
+        parts.join(" | ")
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+// Serves GET /users/<login> with the profile as JSON
+pub struct ProfileRoutes {
+    bot: Arc<BorrowBot>,
+}
+
+impl ProfileRoutes {
+    pub fn new(bot: Arc<BorrowBot>) -> Self {
+        Self { bot }
+    }
+}
+
+#[async_trait]
+impl JsonRoutes for ProfileRoutes {
+    async fn get(&self, path: &str) -> (StatusCode, String) {
+        let login = match path.strip_prefix("/users/") {
+            Some(login) if !login.is_empty() && !login.contains('/') => login.to_lowercase(),
+            _ => return (StatusCode::NOT_FOUND, String::new()),
+        };
+
+        match UserProfile::lookup(&self.bot, &login).await {
+            Ok(Some(profile)) => match serde_json::to_string(&profile) {
+                Ok(json) => (StatusCode::OK, json),
+                Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, String::new()),
+            },
+            Ok(None) => (StatusCode::NOT_FOUND, String::new()),
+            Err(e) => {
+                eprintln!(
+                    "{}: Error looking up the profile of {}: {}",
+                    Utc::now(),
+                    login,
+                    e
+                );
+                (StatusCode::INTERNAL_SERVER_ERROR, String::new())
+            }
+        }
+    }
+}
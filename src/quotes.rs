@@ -0,0 +1,102 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// longest quote that can be added from chat
+pub const MAX_QUOTE_LENGTH: usize = 400;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Quote {
+    // numbered per channel, 0 for quotes that weren't saved yet
+    pub id: i32,
+
+    #[serde(skip)]
+    pub channel: String,
+
+    // login of whoever said it
+    pub quoted: String,
+    pub text: String,
+    pub added_by: String,
+    pub added_at: DateTime<Utc>,
+}
+
+impl Quote {
+    // "#12 "text" - alice, 2021-05-03"
+    pub fn describe(&self) -> String {
+        format!(
+            "#{} \"{}\" - {}, {}",
+            self.id,
+            self.text,
+            self.quoted,
+            self.added_at.format("%Y-%m-%d")
+        )
+    }
+}
+
+// One quote as other bots export them, only the text is needed. Names for the fields vary
+#[derive(Deserialize)]
+struct ImportedQuote {
+    #[serde(default, alias = "number")]
+    id: Option<i32>,
+
+    #[serde(alias = "quote", alias = "message")]
+    text: String,
+
+    #[serde(default, alias = "user", alias = "author")]
+    quoted: Option<String>,
+
+    #[serde(default, alias = "addedBy", alias = "added_by_login")]
+    added_by: Option<String>,
+
+    #[serde(default, alias = "addedAt", alias = "date", alias = "created_at")]
+    added_at: Option<DateTime<Utc>>,
+}
+
+pub fn to_json(quotes: &[Quote]) -> String {
+    serde_json::to_string_pretty(quotes).unwrap_or_else(|_| "[]".to_owned())
+}
+
+// Reads a JSON list of quotes into the channel, numbers they had are kept if they're free when
+// they're saved. Missing authors become "unknown", missing dates the time of the import
+pub fn from_json(json: &str, channel: &str, imported_by: &str) -> Result<Vec<Quote>, String> {
+    let imported: Vec<ImportedQuote> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+    Ok(imported
+        .into_iter()
+        .filter(|quote| !quote.text.trim().is_empty())
+        .map(|quote| Quote {
+            id: quote.id.filter(|id| *id > 0).unwrap_or(0),
+            channel: channel.to_owned(),
+            quoted: quote
+                .quoted
+                .map(|login| login.trim_start_matches('@').to_lowercase())
+                .filter(|login| !login.is_empty())
+                .unwrap_or_else(|| "unknown".to_owned()),
+            text: quote.text.trim().to_owned(),
+            added_by: quote.added_by.unwrap_or_else(|| imported_by.to_owned()),
+            added_at: quote.added_at.unwrap_or_else(Utc::now),
+        })
+        .collect())
+}
+
+// Numbers quotes about to be saved in a channel that already uses `taken`. Quotes keep the number
+// they came with unless another quote has it, the rest go after the highest one
+pub fn number_quotes(taken: &mut HashSet<i32>, quotes: &[Quote]) -> Vec<Quote> {
+    let mut numbered: Vec<Quote> = quotes.to_vec();
+    let mut unnumbered = Vec::new();
+    for (i, quote) in numbered.iter().enumerate() {
+        if quote.id <= 0 || !taken.insert(quote.id) {
+            unnumbered.push(i);
+        }
+    }
+
+    let mut next = taken.iter().max().copied().unwrap_or(0);
+    for i in unnumbered {
+        next += 1;
+        taken.insert(next);
+        numbered[i].id = next;
+    }
+
+    numbered
+}
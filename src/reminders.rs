@@ -0,0 +1,290 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use tokio::sync::Notify;
+use twitch_irc::message::PrivmsgMessage;
+
+use crate::bot::BorrowBot;
+use crate::commands::format_duration;
+use crate::database::{DBError, Database};
+use crate::types::parse_uid;
+
+// how long the scheduler waits before asking the database again after it failed
+const RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// a reminder that couldn't be delivered this many times is dropped
+const MAX_DELIVERY_ATTEMPTS: u32 = 10;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reminder {
+    pub id: i32,
+    pub author_uid: i32,
+    pub author_login: String,
+    pub target_uid: i32,
+    pub target_login: String,
+
+    // where it was set, timed reminders are delivered there
+    pub channel: String,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+
+    // None for reminders delivered the next time the target chats
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+impl Reminder {
+    // what the target gets to read, without the mention
+    pub fn text(&self) -> String {
+        match self.due_at {
+            Some(due_at) if self.author_uid == self.target_uid => format!(
+                "reminder from {} ago: {}",
+                format_duration(due_at - self.created_at),
+                self.message
+            ),
+            Some(due_at) => format!(
+                "reminder from {} ({} ago): {}",
+                self.author_login,
+                format_duration(due_at - self.created_at),
+                self.message
+            ),
+            None => format!(
+                "reminder from {} ({} ago): {}",
+                self.author_login,
+                format_duration(Utc::now() - self.created_at),
+                self.message
+            ),
+        }
+    }
+}
+
+// Durations like "90s", "2h" or "1d12h", None for anything else or nothing at all
+pub fn parse_duration(text: &str) -> Option<chrono::Duration> {
+    let mut total = chrono::Duration::zero();
+    let mut number = String::new();
+
+    for c in text.to_lowercase().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let amount: i32 = number.parse().ok()?;
+        number.clear();
+        let unit = match c {
+            's' => chrono::Duration::seconds(1),
+            'm' => chrono::Duration::minutes(1),
+            'h' => chrono::Duration::hours(1),
+            'd' => chrono::Duration::days(1),
+            'w' => chrono::Duration::weeks(1),
+            _ => return None,
+        };
+        total = total.checked_add(&(unit * amount))?;
+    }
+
+    if !number.is_empty() || total <= chrono::Duration::zero() {
+        return None;
+    }
+    Some(total)
+}
+
+// Keeps track of who has reminders waiting so chat messages from everyone else don't need a
+// query, and wakes the scheduler up when a timed reminder comes in
+pub struct Reminders {
+    db: Arc<dyn Database>,
+    waiting: Mutex<HashSet<i32>>,
+    scheduled: Notify,
+
+    // reminder id -> deliveries that failed since the bot started
+    failures: Mutex<HashMap<i32, u32>>,
+}
+
+impl Reminders {
+    pub async fn new(db: Arc<dyn Database>) -> Result<Self, DBError> {
+        let waiting = db.get_chat_reminder_targets().await?;
+
+        Ok(Self {
+            db,
+            waiting: Mutex::new(waiting),
+            scheduled: Notify::new(),
+            failures: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // returns the id it was saved under
+    pub async fn add(&self, reminder: &Reminder) -> Result<i32, DBError> {
+        let id = self.db.add_reminder(reminder).await?;
+        if reminder.due_at.is_some() {
+            self.scheduled.notify_one();
+        } else {
+            self.waiting.lock().unwrap().insert(reminder.target_uid);
+        }
+
+        Ok(id)
+    }
+
+    pub async fn cancel(&self, id: i32, author_uid: Option<i32>) -> Result<bool, DBError> {
+        let cancelled = self.db.cancel_reminder(id, author_uid).await?;
+        if cancelled {
+            self.scheduled.notify_one();
+        }
+
+        Ok(cancelled)
+    }
+
+    // returns how many times delivering the reminder failed so far
+    fn count_failure(&self, id: i32) -> u32 {
+        let mut failures = self.failures.lock().unwrap();
+        let attempts = failures.entry(id).or_insert(0);
+        *attempts += 1;
+        *attempts
+    }
+
+    // Hands the sender everything that was waiting for them to chat, in the channel they chatted
+    // in. Delivery happens in the background, reminders that couldn't be delivered wait for the
+    // next message
+    pub fn deliver_waiting(bot: &Arc<BorrowBot>, msg: &PrivmsgMessage) {
+        let uid = match parse_uid(&msg.sender.id) {
+            Ok(uid) => uid,
+            Err(_) => return,
+        };
+        let reminders = bot.reminders();
+        if !reminders.waiting.lock().unwrap().remove(&uid) {
+            return;
+        }
+
+        let bot = Arc::clone(bot);
+        let login = msg.sender.login.clone();
+        let channel = msg.channel_login.to_lowercase();
+        tokio::spawn(async move {
+            let delivered = match reminders.db.get_chat_reminders(uid).await {
+                Ok(waiting) => {
+                    let mut delivered = true;
+                    for reminder in waiting {
+                        delivered &= deliver(&bot, &reminder, &channel).await;
+                    }
+                    delivered
+                }
+                Err(e) => {
+                    eprintln!("Error loading reminders for {}: {}", login, e);
+                    false
+                }
+            };
+            if !delivered {
+                reminders.waiting.lock().unwrap().insert(uid);
+            }
+        });
+    }
+
+    // Sleeps until the next timed reminder is due. Everything is read back from the database, so
+    // reminders that came due while the bot was down go out as soon as it starts
+    pub fn start_scheduler(bot: Arc<BorrowBot>) {
+        tokio::spawn(async move {
+            let reminders = bot.reminders();
+            loop {
+                let due_at = match reminders.db.get_next_reminder_due().await {
+                    Ok(Some(due_at)) => due_at,
+                    Ok(None) => {
+                        reminders.scheduled.notified().await;
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("{}: Error loading reminders: {}", Utc::now(), e);
+                        tokio::time::sleep(RETRY_INTERVAL).await;
+                        continue;
+                    }
+                };
+
+                let wait = (due_at - Utc::now()).to_std().unwrap_or_default();
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    // something earlier might have come in, or the next one was cancelled
+                    _ = reminders.scheduled.notified() => continue,
+                }
+
+                let delivered = match reminders.db.get_due_reminders(due_at).await {
+                    Ok(due) => {
+                        let mut delivered = true;
+                        for reminder in due {
+                            let channel = reminder.channel.clone();
+                            delivered &= deliver(&bot, &reminder, &channel).await;
+                        }
+                        delivered
+                    }
+                    Err(e) => {
+                        eprintln!("{}: Error loading due reminders: {}", Utc::now(), e);
+                        false
+                    }
+                };
+                // undelivered reminders stay due, give whatever failed some time to recover
+                if !delivered {
+                    tokio::time::sleep(RETRY_INTERVAL).await;
+                }
+            }
+        });
+    }
+}
+
+// Pings the target in chat, or whispers them when the bot can't say it there: the channel was
+// left since the reminder was set, turned &remind off, or the banphrase api won't clear it.
+// Channels have no offline-only mode, the bot talks in every joined channel whether it's live
+// or not, so that isn't a reason to whisper. The reminder is removed once it went out or failed
+// to go out MAX_DELIVERY_ATTEMPTS times, false if it is still saved
+async fn deliver(bot: &BorrowBot, reminder: &Reminder, channel: &str) -> bool {
+    let text = reminder.text();
+
+    let joined = bot.current_channels().lock().await.contains(channel);
+    let mut sent = false;
+    if joined && !bot.commands().is_disabled(channel, "remind") {
+        let message = format!("@{}, {}", reminder.target_login, text);
+        if let Ok(false) = bot.api().banphrase().contains_banphrase(&message).await {
+            bot.messenger().announce(channel, vec![message]).await;
+            sent = true;
+        }
+    }
+
+    if !sent {
+        if let Err(e) = bot
+            .api()
+            .helix()
+            .send_whisper(&reminder.target_uid.to_string(), &text)
+            .await
+        {
+            eprintln!(
+                "{}: Couldn't deliver reminder {} to {}: {}",
+                Utc::now(),
+                reminder.id,
+                reminder.target_login,
+                e
+            );
+
+            let attempts = bot.reminders().count_failure(reminder.id);
+            if attempts < MAX_DELIVERY_ATTEMPTS {
+                return false;
+            }
+            eprintln!(
+                "{}: Giving up on reminder {} after {} attempts",
+                Utc::now(),
+                reminder.id,
+                attempts
+            );
+        }
+    }
+
+    if let Err(e) = bot.db().cancel_reminder(reminder.id, None).await {
+        eprintln!(
+            "{}: Error removing delivered reminder {}: {}",
+            Utc::now(),
+            reminder.id,
+            e
+        );
+        return false;
+    }
+    bot.reminders()
+        .failures
+        .lock()
+        .unwrap()
+        .remove(&reminder.id);
+
+    true
+}
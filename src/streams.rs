@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, Mutex};
+
+use crate::api::eventsub::TwitchEvent;
+use crate::api::helix::{Helix, Stream};
+use crate::database::{DBError, Database};
+use crate::error::BotError;
+
+pub const STREAM_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+// with EventSub delivering changes the poller only catches whatever got lost
+pub const EVENTSUB_STREAM_POLL_INTERVAL: Duration = Duration::from_secs(900);
+
+#[derive(Clone, Debug)]
+pub struct StreamSession {
+    pub id: i32,
+    pub channel: String,
+    pub stream_id: String,
+    pub started_at: DateTime<Utc>,
+
+    // None while the channel is still live
+    pub ended_at: Option<DateTime<Utc>>,
+
+    // latest title and category of the broadcast
+    pub title: String,
+    pub category: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamEvent {
+    Online {
+        channel: String,
+        title: String,
+        category: String,
+    },
+    Offline {
+        channel: String,
+    },
+    TitleChanged {
+        channel: String,
+        title: String,
+    },
+    CategoryChanged {
+        channel: String,
+        category: String,
+    },
+}
+
+// Keeps track of which joined channels are live, persisting every broadcast as a session and
+// announcing changes to whoever subscribed
+pub struct StreamTracker {
+    db: Arc<dyn Database>,
+    helix: Arc<Helix>,
+
+    // channel -> its open session, only holds channels that are live
+    live: Mutex<HashMap<String, StreamSession>>,
+    events: broadcast::Sender<StreamEvent>,
+}
+
+impl StreamTracker {
+    pub async fn new(db: Arc<dyn Database>, helix: Arc<Helix>) -> Result<Self, DBError> {
+        // sessions left open by the last run are picked back up, the next poll closes them if
+        // the channel went offline in the meantime
+        let live = db
+            .get_open_stream_sessions()
+            .await?
+            .into_iter()
+            .map(|session| (session.channel.clone(), session))
+            .collect();
+        let (events, _) = broadcast::channel(64);
+
+        Ok(Self {
+            db,
+            helix,
+            live: Mutex::new(live),
+            events,
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StreamEvent> {
+        self.events.subscribe()
+    }
+
+    pub async fn live_session(&self, channel: &str) -> Option<StreamSession> {
+        self.live.lock().await.get(channel).cloned()
+    }
+
+    pub fn start_polling(
+        tracker: Arc<StreamTracker>,
+        channels: Arc<Mutex<HashSet<String>>>,
+        interval: Duration,
+    ) {
+        tokio::spawn(async move {
+            // the first poll happens right away so sessions are known at startup
+            loop {
+                let channels = channels.lock().await.clone();
+                if let Err(e) = tracker.poll(&channels).await {
+                    eprintln!("{}: Error polling stream status: {}", Utc::now(), e);
+                }
+
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    pub async fn poll(&self, channels: &HashSet<String>) -> Result<(), BotError> {
+        let logins: Vec<&str> = channels.iter().map(|channel| channel.as_str()).collect();
+        let streams: HashMap<String, Stream> = self
+            .helix
+            .get_streams(&logins)
+            .await?
+            .into_iter()
+            .filter(|stream| stream.type_field == "live")
+            .map(|stream| (stream.user_login.to_lowercase(), stream))
+            .collect();
+
+        // channels that were left while live still need their session closed
+        let mut tracked: HashSet<String> = self.live.lock().await.keys().cloned().collect();
+        tracked.extend(channels.iter().cloned());
+
+        // one channel failing to save shouldn't hold up the others
+        for channel in tracked {
+            if let Err(e) = self.update(&channel, streams.get(&channel)).await {
+                eprintln!(
+                    "{}: Error updating stream status of {}: {}",
+                    Utc::now(),
+                    channel,
+                    e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    // Applies stream changes delivered by EventSub as they come in
+    pub fn follow_events(
+        tracker: Arc<StreamTracker>,
+        mut events: broadcast::Receiver<TwitchEvent>,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                let result = match events.recv().await {
+                    // stream.online doesn't carry the title and category, helix has them
+                    Ok(TwitchEvent::StreamOnline {
+                        broadcaster_login, ..
+                    })
+                    | Ok(TwitchEvent::ChannelUpdate {
+                        broadcaster_login, ..
+                    }) => tracker.refresh(&broadcaster_login).await,
+                    Ok(TwitchEvent::StreamOffline {
+                        broadcaster_login, ..
+                    }) => tracker
+                        .update(&broadcaster_login, None)
+                        .await
+                        .map_err(BotError::from),
+                    Ok(_) => Ok(()),
+                    Err(RecvError::Lagged(missed)) => {
+                        eprintln!("Stream tracker missed {} EventSub events", missed);
+                        Ok(())
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                if let Err(e) = result {
+                    eprintln!("{}: Error applying EventSub event: {}", Utc::now(), e);
+                }
+            }
+        });
+    }
+
+    // asks helix about a single channel
+    pub async fn refresh(&self, channel: &str) -> Result<(), BotError> {
+        let stream = self
+            .helix
+            .get_streams(&[channel])
+            .await?
+            .into_iter()
+            .find(|stream| stream.type_field == "live");
+        self.update(channel, stream.as_ref()).await?;
+
+        Ok(())
+    }
+
+    // Brings a channel's state in line with what helix reported, None meaning it's offline
+    pub async fn update(&self, channel: &str, stream: Option<&Stream>) -> Result<(), DBError> {
+        let now = Utc::now();
+        let mut live = self.live.lock().await;
+        let mut events = Vec::new();
+
+        match (live.get_mut(channel), stream) {
+            (Some(session), Some(stream)) if session.stream_id == stream.id => {
+                let title_changed = session.title != stream.title;
+                let category_changed = session.category != stream.game_name;
+                if title_changed || category_changed {
+                    self.db
+                        .record_stream_change(session.id, now, &stream.title, &stream.game_name)
+                        .await?;
+                    session.title = stream.title.clone();
+                    session.category = stream.game_name.clone();
+                }
+
+                if title_changed {
+                    events.push(StreamEvent::TitleChanged {
+                        channel: channel.to_owned(),
+                        title: stream.title.clone(),
+                    });
+                }
+                if category_changed {
+                    events.push(StreamEvent::CategoryChanged {
+                        channel: channel.to_owned(),
+                        category: stream.game_name.clone(),
+                    });
+                }
+            }
+            (Some(session), None) => {
+                self.db.end_stream_session(session.id, now).await?;
+                live.remove(channel);
+                events.push(StreamEvent::Offline {
+                    channel: channel.to_owned(),
+                });
+            }
+            (previous, Some(stream)) => {
+                // a different stream id means the channel went down and back up between polls
+                if let Some(previous) = previous {
+                    self.db.end_stream_session(previous.id, now).await?;
+                }
+
+                let id = self
+                    .db
+                    .start_stream_session(
+                        channel,
+                        &stream.id,
+                        stream.started_at,
+                        &stream.title,
+                        &stream.game_name,
+                    )
+                    .await?;
+                live.insert(
+                    channel.to_owned(),
+                    StreamSession {
+                        id,
+                        channel: channel.to_owned(),
+                        stream_id: stream.id.clone(),
+                        started_at: stream.started_at,
+                        ended_at: None,
+                        title: stream.title.clone(),
+                        category: stream.game_name.clone(),
+                    },
+                );
+                events.push(StreamEvent::Online {
+                    channel: channel.to_owned(),
+                    title: stream.title.clone(),
+                    category: stream.game_name.clone(),
+                });
+            }
+            (None, None) => {}
+        }
+        drop(live);
+
+        for event in events {
+            // nobody listening is fine
+            let _ = self.events.send(event);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::time::Instant;
+
+use crate::bot::BorrowBot;
+use crate::commands::format_duration;
+use crate::database::{DBError, Database};
+
+// how often timers are checked, which is also how late they can be
+const TIMER_TICK: Duration = Duration::from_secs(5);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimerCondition {
+    Always,
+    Live,
+    Offline,
+}
+
+impl TimerCondition {
+    pub fn parse(condition: &str) -> Option<Self> {
+        match condition {
+            "always" => Some(TimerCondition::Always),
+            "live" => Some(TimerCondition::Live),
+            "offline" => Some(TimerCondition::Offline),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimerCondition::Always => "always",
+            TimerCondition::Live => "live",
+            TimerCondition::Offline => "offline",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimerSchedule {
+    Every(chrono::Duration),
+    Once(DateTime<Utc>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Timer {
+    pub id: i32,
+    pub channel: String,
+    pub name: String,
+    pub message: String,
+    pub schedule: TimerSchedule,
+
+    // chat lines needed since the last time it went out
+    pub min_lines: i32,
+    pub condition: TimerCondition,
+    pub paused: bool,
+    pub created_at: DateTime<Utc>,
+    pub last_sent_at: Option<DateTime<Utc>>,
+}
+
+impl Timer {
+    // "every 20m, 30 lines, live only" for listing it in chat
+    pub fn describe(&self) -> String {
+        let mut parts = vec![match self.schedule {
+            TimerSchedule::Every(interval) => format!("every {}", format_duration(interval)),
+            TimerSchedule::Once(run_at) => {
+                // rounded up, a timer set for 10m shouldn't say 9m right away
+                let remaining = (run_at - Utc::now()).num_milliseconds();
+                let remaining = chrono::Duration::seconds((remaining + 999) / 1000);
+                format!("once in {}", format_duration(remaining))
+            }
+        }];
+        if self.min_lines > 0 {
+            parts.push(format!("{} lines", self.min_lines));
+        }
+        match self.condition {
+            TimerCondition::Always => {}
+            TimerCondition::Live => parts.push("live only".to_owned()),
+            TimerCondition::Offline => parts.push("offline only".to_owned()),
+        }
+        if self.paused {
+            parts.push("paused".to_owned());
+        }
+
+        parts.join(", ")
+    }
+
+    // when it should go out next, counted on the runtime's clock from the wall clock times
+    fn due(&self) -> Instant {
+        let due_at = match self.schedule {
+            TimerSchedule::Every(interval) => {
+                self.last_sent_at.unwrap_or(self.created_at) + interval
+            }
+            TimerSchedule::Once(run_at) => run_at,
+        };
+
+        Instant::now() + (due_at - Utc::now()).to_std().unwrap_or_default()
+    }
+}
+
+struct ScheduledTimer {
+    timer: Timer,
+    due: Instant,
+
+    // the channel's line count when it last went out
+    lines_at: u64,
+}
+
+// Every channel's timers, kept in memory and checked every few seconds. Chat lines are only
+// counted while the bot runs, so thresholds start over after a restart
+pub struct TimerTracker {
+    db: Arc<dyn Database>,
+    timers: Mutex<Vec<ScheduledTimer>>,
+
+    // channel -> chat lines seen since startup
+    lines: Mutex<HashMap<String, u64>>,
+}
+
+impl TimerTracker {
+    pub async fn new(db: Arc<dyn Database>) -> Result<Self, DBError> {
+        let timers = db
+            .get_timers()
+            .await?
+            .into_iter()
+            .map(|timer| ScheduledTimer {
+                due: timer.due(),
+                timer,
+                lines_at: 0,
+            })
+            .collect();
+
+        Ok(Self {
+            db,
+            timers: Mutex::new(timers),
+            lines: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn count_line(&self, channel: &str) {
+        *self
+            .lines
+            .lock()
+            .unwrap()
+            .entry(channel.to_owned())
+            .or_insert(0) += 1;
+    }
+
+    fn line_count(&self, channel: &str) -> u64 {
+        self.lines
+            .lock()
+            .unwrap()
+            .get(channel)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // the channel's timers, in the order they were added
+    pub fn list(&self, channel: &str) -> Vec<Timer> {
+        self.timers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|scheduled| scheduled.timer.channel == channel)
+            .map(|scheduled| scheduled.timer.clone())
+            .collect()
+    }
+
+    pub fn get(&self, channel: &str, name: &str) -> Option<Timer> {
+        self.list(channel)
+            .into_iter()
+            .find(|timer| timer.name == name)
+    }
+
+    // returns the id it was saved under
+    pub async fn add(&self, timer: Timer) -> Result<i32, DBError> {
+        let id = self.db.add_timer(&timer).await?;
+        let lines_at = self.line_count(&timer.channel);
+        let timer = Timer { id, ..timer };
+        let due = timer.due();
+        self.timers.lock().unwrap().push(ScheduledTimer {
+            due,
+            timer,
+            lines_at,
+        });
+
+        Ok(id)
+    }
+
+    // false if the channel has no timer by that name
+    pub async fn set_paused(
+        &self,
+        channel: &str,
+        name: &str,
+        paused: bool,
+    ) -> Result<bool, DBError> {
+        let id = match self.get(channel, name) {
+            Some(timer) => timer.id,
+            None => return Ok(false),
+        };
+        self.db.set_timer_paused(id, paused).await?;
+
+        if let Some(scheduled) = self
+            .timers
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|scheduled| scheduled.timer.id == id)
+        {
+            scheduled.timer.paused = paused;
+        }
+        Ok(true)
+    }
+
+    // false if the channel has no timer by that name
+    pub async fn delete(&self, channel: &str, name: &str) -> Result<bool, DBError> {
+        let id = match self.get(channel, name) {
+            Some(timer) => timer.id,
+            None => return Ok(false),
+        };
+        self.db.delete_timer(id).await?;
+
+        self.timers
+            .lock()
+            .unwrap()
+            .retain(|scheduled| scheduled.timer.id != id);
+        Ok(true)
+    }
+
+    pub fn start(bot: Arc<BorrowBot>) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(TIMER_TICK).await;
+                bot.timers().send_due(&bot).await;
+            }
+        });
+    }
+
+    // Queues every timer that is due, had enough chat since it last went out and whose channel
+    // is in the right state. One-off timers are removed once they went out
+    async fn send_due(&self, bot: &BorrowBot) {
+        let now = Instant::now();
+        let candidates: Vec<Timer> = self
+            .timers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|scheduled| !scheduled.timer.paused && scheduled.due <= now)
+            .filter(|scheduled| {
+                self.line_count(&scheduled.timer.channel) - scheduled.lines_at
+                    >= scheduled.timer.min_lines as u64
+            })
+            .map(|scheduled| scheduled.timer.clone())
+            .collect();
+
+        for timer in candidates {
+            if !bot.current_channels().lock().await.contains(&timer.channel) {
+                continue;
+            }
+            let live = bot.streams().live_session(&timer.channel).await.is_some();
+            let allowed = match timer.condition {
+                TimerCondition::Always => true,
+                TimerCondition::Live => live,
+                TimerCondition::Offline => !live,
+            };
+            if !allowed {
+                continue;
+            }
+
+            let message = match bot.counters().fill(&timer.channel, &timer.message).await {
+                Ok(message) => message,
+                Err(e) => {
+                    eprintln!(
+                        "Error filling in counters for timer {} of #{}: {}",
+                        timer.name, timer.channel, e
+                    );
+                    timer.message.clone()
+                }
+            };
+            bot.messenger()
+                .announce(&timer.channel, vec![message])
+                .await;
+
+            let saved = match timer.schedule {
+                TimerSchedule::Every(_) => self.db.set_timer_sent(timer.id, Utc::now()).await,
+                TimerSchedule::Once(_) => self.db.delete_timer(timer.id).await,
+            };
+            if let Err(e) = saved {
+                eprintln!(
+                    "Error saving timer {} of #{}: {}",
+                    timer.name, timer.channel, e
+                );
+            }
+
+            let lines_at = self.line_count(&timer.channel);
+            let mut timers = self.timers.lock().unwrap();
+            match timer.schedule {
+                TimerSchedule::Every(interval) => {
+                    if let Some(scheduled) = timers.iter_mut().find(|s| s.timer.id == timer.id) {
+                        scheduled.timer.last_sent_at = Some(Utc::now());
+                        scheduled.due = Instant::now() + interval.to_std().unwrap_or_default();
+                        scheduled.lines_at = lines_at;
+                    }
+                }
+                TimerSchedule::Once(_) => timers.retain(|s| s.timer.id != timer.id),
+            }
+        }
+    }
+}
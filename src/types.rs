@@ -1,3 +1,10 @@
+use std::env;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::error::BotError;
+
 #[derive(Debug)]
 pub struct UserContext {
     pub uid: i32,
@@ -15,7 +22,8 @@ impl UserContext {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PermissionLevel {
     User,
     Moderator,
@@ -39,6 +47,47 @@ impl CommandResponse {
     }
 }
 
+// twitch user ids are sent as strings in IRC tags
+pub fn parse_uid(id: &str) -> Result<i32, BotError> {
+    id.parse()
+        .map_err(|_| BotError::InvalidMessage(format!("user id '{}' is not a number", id)))
+}
+
+// The directory the bot's operator puts files in, like quotes and trivia packs
+#[derive(Clone, Debug)]
+pub struct DataDir {
+    root: PathBuf,
+}
+
+impl DataDir {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    // BORROWBOT_DATA_DIR, "data" by default
+    pub fn from_env() -> Self {
+        Self::new(env::var("BORROWBOT_DATA_DIR").unwrap_or_else(|_| "data".to_owned()))
+    }
+
+    pub fn folder(&self, folder: &str) -> PathBuf {
+        self.root.join(folder)
+    }
+
+    // a file in a folder of the data directory, None if the name could point anywhere else
+    pub fn file(&self, folder: &str, name: &str) -> Option<PathBuf> {
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+        if !valid {
+            return None;
+        }
+
+        Some(self.folder(folder).join(name))
+    }
+}
+
 impl std::fmt::Display for PermissionLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
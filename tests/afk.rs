@@ -0,0 +1,140 @@
+mod common;
+
+use std::time::Duration;
+
+use borrowbot::afk::{AfkKind, AfkStatus};
+use borrowbot::database::AfkRepository;
+use chrono::Utc;
+use common::{MockRoute, TestBotBuilder};
+
+#[tokio::test(start_paused = true)]
+async fn away_users_are_welcomed_back_in_any_channel() {
+    let mut bot = TestBotBuilder::new(&["forsen", "pajlada"]).start().await;
+
+    bot.chat("forsen", "alice", 1, "&afk lunch").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, You're now AFK: lunch"
+    );
+
+    bot.chat("pajlada", "bob", 2, "hey @Alice, you there?")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "@bob, alice is AFK: lunch (0s ago)"
+    );
+    // nobody in the channel gets told again right away
+    bot.chat("pajlada", "carol", 3, "alice??").await;
+    bot.expect_silence(Duration::from_secs(10)).await;
+
+    bot.chat("pajlada", "alice", 1, "back").await;
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "alice is no longer AFK: lunch (0s ago)"
+    );
+
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    bot.chat("pajlada", "bob", 2, "@alice welcome back").await;
+    bot.expect_silence(Duration::from_secs(10)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn away_statuses_survive_a_restart() {
+    let builder = TestBotBuilder::new(&["forsen"]);
+    builder
+        .db()
+        .set_afk(&AfkStatus {
+            uid: 1,
+            login: "alice".to_owned(),
+            kind: AfkKind::Gn,
+            reason: "".to_owned(),
+            since: Utc::now() - chrono::Duration::hours(8),
+        })
+        .await
+        .unwrap();
+    let mut bot = builder.start().await;
+
+    bot.chat("forsen", "bob", 2, "gn alice").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, alice is sleeping (8h ago)"
+    );
+    bot.chat("forsen", "alice", 1, "good morning").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "alice is no longer sleeping (8h ago)"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn moderators_can_turn_afk_off_in_their_channel() {
+    let builder = TestBotBuilder::new(&["forsen", "pajlada"]);
+    builder.db().add_user(9, "modguy", 1);
+    let mut bot = builder.start().await;
+
+    bot.chat("forsen", "modguy", 9, "&disable nope").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Sorry, I don't know that command"
+    );
+    bot.chat("forsen", "modguy", 9, "&disable &afk").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Disabled &afk in #forsen"
+    );
+
+    bot.chat("forsen", "alice", 1, "&afk").await;
+    bot.expect_silence(Duration::from_secs(10)).await;
+    bot.chat("pajlada", "alice", 1, "&brb").await;
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "@alice, You're now away"
+    );
+
+    bot.chat("forsen", "bob", 2, "@alice").await;
+    bot.expect_silence(Duration::from_secs(10)).await;
+    // coming back where it's off still ends the status, quietly
+    bot.chat("forsen", "alice", 1, "back").await;
+    bot.expect_silence(Duration::from_secs(10)).await;
+    bot.chat("pajlada", "bob", 2, "@alice").await;
+    bot.expect_silence(Duration::from_secs(10)).await;
+
+    bot.chat("forsen", "modguy", 9, "&enable afk").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Enabled &afk in #forsen"
+    );
+    bot.chat("forsen", "alice", 1, "&afk").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, You're now AFK"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn reasons_are_left_out_unless_the_banphrase_api_clears_them() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .banphrase_route(MockRoute::new(
+            "POST",
+            "/banphrases/test",
+            200,
+            "{\"banned\":true,\"input_message\":\"\",\"banphrase_data\":null}",
+        ))
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&afk something bad").await;
+    bot.expect_message_in("forsen").await;
+
+    bot.chat("forsen", "bob", 2, "@alice hello").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, alice is AFK (0s ago)"
+    );
+
+    bot.chat("forsen", "alice", 1, "back").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "alice is no longer AFK (0s ago)"
+    );
+}
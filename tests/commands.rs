@@ -0,0 +1,449 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use borrowbot::api::eventsub::EventSubReceiver;
+use borrowbot::profiles::ProfileRoutes;
+use chrono::Utc;
+
+use common::{helix_user_json, wait_until, MockRoute, TestBotBuilder};
+
+#[tokio::test(start_paused = true)]
+async fn ping_responds_with_uptime() {
+    let mut bot = TestBotBuilder::new(&["forsen"]).start().await;
+
+    bot.chat("forsen", "alice", 1, "&ping").await;
+
+    let response = bot.expect_message_in("forsen").await;
+    assert!(
+        response.starts_with("@alice, Pong! Uptime:"),
+        "{}",
+        response
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn ping_reports_unreachable_databases() {
+    let mut bot = TestBotBuilder::new(&["forsen"]).start().await;
+    bot.db.set_unreachable(true);
+
+    bot.chat("forsen", "alice", 1, "&ping").await;
+
+    let response = bot.expect_message_in("forsen").await;
+    assert!(
+        response.ends_with(" | can't reach the database or the logs"),
+        "{}",
+        response
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn non_commands_are_logged_and_ignored() {
+    let mut bot = TestBotBuilder::new(&["forsen"]).start().await;
+
+    bot.chat("forsen", "alice", 1, "hello chat").await;
+    bot.expect_silence(Duration::from_secs(10)).await;
+
+    assert_eq!(bot.db.message_count(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn user_cooldown_silences_repeated_commands() {
+    let mut bot = TestBotBuilder::new(&["forsen"]).start().await;
+
+    bot.chat("forsen", "alice", 1, "&ping").await;
+    bot.expect_message_in("forsen").await;
+
+    bot.chat("forsen", "alice", 1, "&ping").await;
+    bot.expect_silence(Duration::from_secs(2)).await;
+
+    // other users have their own cooldowns
+    bot.chat("forsen", "bob", 2, "&ping").await;
+    assert!(bot
+        .expect_message_in("forsen")
+        .await
+        .starts_with("@bob, Pong!"));
+
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "alice", 1, "&ping").await;
+    assert!(bot
+        .expect_message_in("forsen")
+        .await
+        .starts_with("@alice, Pong!"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn commands_check_permissions() {
+    let builder = TestBotBuilder::new(&["forsen"]);
+    builder.db().add_user(2, "bob", 1);
+    let mut bot = builder.start().await;
+
+    bot.chat("forsen", "alice", 1, "&say hi").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Sorry, only moderators have access to the say command"
+    );
+
+    bot.chat("forsen", "bob", 2, "&say hi").await;
+    assert_eq!(bot.expect_message_in("forsen").await, "@bob, hi");
+}
+
+#[tokio::test(start_paused = true)]
+async fn questionable_output_is_checked_for_banphrases() {
+    let builder = TestBotBuilder::new(&["forsen"]).banphrase_route(MockRoute::new(
+        "POST",
+        "/banphrases/test",
+        200,
+        "{\"banned\":true,\"input_message\":\"\",\"banphrase_data\":null}",
+    ));
+    builder.db().add_user(2, "bob", 1);
+    let mut bot = builder.start().await;
+
+    bot.chat("forsen", "bob", 2, "&say something bad").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Uh oh, the anticipated response contained a banphrase monkaS"
+    );
+    assert_eq!(bot.banphrase.requests().len(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn uid_looks_users_up_on_helix() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users?login=pajlada",
+            200,
+            &helix_user_json("11148817", "pajlada"),
+        ))
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users?login=nobody",
+            200,
+            "{\"data\":[]}",
+        ))
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&uid").await;
+    assert_eq!(bot.expect_message_in("forsen").await, "@alice, 1");
+
+    bot.chat("forsen", "bob", 2, "&uid pajlada").await;
+    assert_eq!(bot.expect_message_in("forsen").await, "@bob, 11148817");
+
+    bot.chat("forsen", "carol", 3, "&uid nobody").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, Sorry, I couldn't find user"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn users_seen_in_chat_are_resolved_without_helix() {
+    let mut bot = TestBotBuilder::new(&["forsen"]).start().await;
+
+    bot.chat("forsen", "bob", 22, "hello").await;
+    bot.chat("forsen", "alice", 1, "&uid BOB").await;
+    assert_eq!(bot.expect_message_in("forsen").await, "@alice, 22");
+
+    assert!(bot
+        .helix_requests()
+        .iter()
+        .all(|r| !r.starts_with("GET /helix/users")));
+}
+
+#[tokio::test(start_paused = true)]
+async fn chatters_are_enriched_from_helix_in_batches() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users",
+            200,
+            &helix_user_json("1", "alice"),
+        ))
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&ping").await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "alice", 1, "hello again").await;
+    tokio::time::sleep(Duration::from_secs(31)).await;
+
+    let (display_name, created_at) = bot.db.user_details(1).unwrap();
+    assert_eq!(display_name, "alice");
+    assert_eq!(
+        created_at.unwrap().to_rfc3339(),
+        "2016-12-14T20:32:28+00:00"
+    );
+    assert_eq!(
+        bot.helix_requests()
+            .iter()
+            .filter(|r| r.starts_with("GET /helix/users"))
+            .collect::<Vec<_>>(),
+        vec!["GET /helix/users?id=1"]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn failed_api_calls_are_reported_and_logged() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .helix_route(MockRoute::new("GET", "/helix/users", 500, "not json"))
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&uid pajlada").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Sorry, the Twitch API couldn't be reached, try again later"
+    );
+
+    let errors = bot.db.logged_errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("&uid #forsen alice"), "{}", errors[0]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn lastmessage_reads_the_logs() {
+    let mut bot = TestBotBuilder::new(&["forsen"]).start().await;
+
+    bot.chat("forsen", "bob", 2, "first").await;
+    bot.chat("forsen", "bob", 2, "second").await;
+    bot.chat("forsen", "alice", 1, "&lastmessage bob").await;
+
+    let response = bot.expect_message_in("forsen").await;
+    assert!(response.ends_with("bob: second"), "{}", response);
+}
+
+#[tokio::test(start_paused = true)]
+async fn join_adds_a_channel() {
+    let builder = TestBotBuilder::new(&["forsen"]).helix_route(MockRoute::new(
+        "GET",
+        "/helix/users?login=pajlada",
+        200,
+        &helix_user_json("11148817", "pajlada"),
+    ));
+    builder.db().add_user(3, "admin", 2);
+    let mut bot = builder.start().await;
+
+    bot.chat("forsen", "admin", 3, "&join pajlada").await;
+
+    let mut said = vec![
+        bot.next_message().await.unwrap(),
+        bot.next_message().await.unwrap(),
+    ];
+    said.sort();
+    assert_eq!(
+        said,
+        vec![
+            (
+                "forsen".to_owned(),
+                "@admin, Succesfully joined channel".to_owned()
+            ),
+            ("pajlada".to_owned(), "🚨".to_owned()),
+        ]
+    );
+
+    bot.chat("pajlada", "alice", 1, "&ping").await;
+    assert!(bot
+        .expect_message_in("pajlada")
+        .await
+        .starts_with("@alice, Pong!"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn accountage_reports_when_accounts_were_created() {
+    let builder = TestBotBuilder::new(&["forsen"])
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users?login=pajlada",
+            200,
+            &helix_user_json("11148817", "pajlada"),
+        ))
+        .helix_route(MockRoute::new("GET", "/helix/users", 200, "{\"data\":[]}"));
+    builder.db().add_user(5, "gone", 0);
+    let mut bot = builder.start().await;
+
+    bot.chat("forsen", "alice", 1, "&accountage pajlada").await;
+    let response = bot.expect_message_in("forsen").await;
+    assert!(
+        response.starts_with("@alice, pajlada created their account ")
+            && response.contains(" years, ")
+            && response.ends_with(" ago (2016-12-14)"),
+        "{}",
+        response
+    );
+
+    // helix leaves out banned accounts, someone we know of must be one of them
+    bot.chat("forsen", "bob", 2, "&accountage gone").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, gone seems to be banned or deleted"
+    );
+
+    bot.chat("forsen", "carol", 3, "&accountage nobody").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, Sorry, I couldn't find user nobody"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn followage_asks_helix_with_the_user_token() {
+    let followed_at = Utc::now() - chrono::Duration::minutes(125);
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users?login=forsen",
+            200,
+            &helix_user_json("22484632", "forsen"),
+        ))
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users?login=xqcow",
+            200,
+            &helix_user_json("71092938", "xqcow"),
+        ))
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/channels/followers?broadcaster_id=22484632&user_id=1",
+            200,
+            &format!(
+                "{{\"data\":[{{\"user_id\":\"1\",\"user_login\":\"alice\",\
+                \"user_name\":\"alice\",\"followed_at\":\"{}\"}}],\"total\":1}}",
+                followed_at.to_rfc3339()
+            ),
+        ))
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/channels/followers?broadcaster_id=22484632&user_id=2",
+            200,
+            "{\"data\":[],\"total\":1}",
+        ))
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/channels/followers",
+            403,
+            "{\"error\":\"Forbidden\",\"status\":403}",
+        ))
+        .start()
+        .await;
+
+    bot.chat("forsen", "bob", 2, "hello").await;
+    bot.chat("forsen", "alice", 1, "&followage").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        format!(
+            "@alice, alice has been following forsen for 2 hours, 5 minutes (since {})",
+            followed_at.format("%Y-%m-%d")
+        )
+    );
+    assert_eq!(
+        bot.helix.authorizations().last().unwrap().as_deref(),
+        Some("Bearer test_user_token")
+    );
+
+    bot.chat("forsen", "carol", 3, "&followage bob forsen")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, bob isn't following forsen"
+    );
+
+    bot.chat("forsen", "dave", 4, "&followage alice xqcow")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@dave, Sorry, I need to be a moderator in xqcow to see its followers"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn user_combines_helix_with_what_the_bot_has_seen() {
+    let builder = TestBotBuilder::new(&["forsen", "xqcow"])
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users?login=pajlada",
+            200,
+            "{\"data\":[{\"id\":\"11\",\"login\":\"pajlada\",\"display_name\":\"Pajlada\",\
+            \"type\":\"\",\"broadcaster_type\":\"partner\",\"description\":\"I make bots\",\
+            \"profile_image_url\":\"https://img.example/pajlada.png\",\
+            \"offline_image_url\":\"\",\"view_count\":0,\
+            \"created_at\":\"2016-12-14T20:32:28Z\"}]}",
+        ))
+        .helix_route(MockRoute::new("GET", "/helix/users", 200, "{\"data\":[]}"));
+    builder.db().add_user(11, "pajlada", 1);
+    builder.db().add_user(5, "gone", 0);
+    let mut bot = builder.start().await;
+
+    bot.chat("forsen", "pajbot_old", 11, "hello").await;
+    bot.chat("xqcow", "pajlada", 11, "hello again").await;
+    bot.chat("forsen", "gone", 5, "bye").await;
+    wait_until(|| bot.db.message_count() == 3).await;
+
+    bot.chat("forsen", "alice", 1, "&user pajlada").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Pajlada | id 11 | partner | created 2016-12-14 | bot moderator | \
+        first seen 2020-09-13 in #forsen | last seen 2020-09-13 in #xqcow | seen in 2 channels | \
+        also known as pajbot_old | https://img.example/pajlada.png | I make bots"
+    );
+
+    // helix doesn't know banned users, the logs still do
+    bot.chat("forsen", "bob", 2, "&user gone").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, gone (banned or deleted) | id 5 | bot user | first seen 2020-09-13 in #forsen | \
+        last seen 2020-09-13 in #forsen | seen in 1 channel"
+    );
+
+    bot.chat("forsen", "carol", 3, "&user nobody").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, Sorry, I couldn't find user nobody"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn user_profiles_are_served_as_json() {
+    let builder = TestBotBuilder::new(&["forsen"])
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users?login=pajlada",
+            200,
+            "{\"data\":[{\"id\":\"11\",\"login\":\"pajlada\",\"display_name\":\"Pajlada\",\
+        \"type\":\"\",\"broadcaster_type\":\"\",\"description\":\"\",\
+        \"profile_image_url\":\"\",\"offline_image_url\":\"\",\"view_count\":0,\
+        \"created_at\":\"2016-12-14T20:32:28Z\"}]}",
+        ))
+        .helix_route(MockRoute::new("GET", "/helix/users", 200, "{\"data\":[]}"));
+    builder.db().add_user(11, "pajlada", 1);
+    let mut bot = builder.start().await;
+    bot.chat("forsen", "pajlada", 11, "hello").await;
+    wait_until(|| bot.db.message_count() == 1).await;
+
+    let receiver = Arc::new(EventSubReceiver::new(
+        "secret".to_owned(),
+        SocketAddr::from(([127, 0, 0, 1], 0)),
+    ));
+    let routes = Arc::new(ProfileRoutes::new(Arc::clone(&bot.bot)));
+    let addr = EventSubReceiver::serve(receiver, Some(routes)).unwrap();
+
+    let resp = reqwest::get(format!("http://{}/users/pajlada", addr))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), reqwest::StatusCode::OK);
+    let profile: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(profile["id"], "11");
+    assert_eq!(profile["display_name"], "Pajlada");
+    assert_eq!(profile["broadcaster_type"], serde_json::Value::Null);
+    assert_eq!(profile["permissions"], "moderator");
+    assert_eq!(profile["activity"]["channels"], 1);
+    assert_eq!(profile["activity"]["first_channel"], "forsen");
+
+    let resp = reqwest::get(format!("http://{}/users/nobody", addr))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), reqwest::StatusCode::NOT_FOUND);
+}
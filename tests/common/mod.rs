@@ -0,0 +1,704 @@
+// Test harness that runs the real BorrowBot::run loop against an in-process IRC transport,
+// an in-memory database and mocked web APIs on localhost.
+//
+// Tests should use #[tokio::test(start_paused = true)], the fake transport relies on the
+// current thread runtime and paused time lets cooldowns and the messenger's rate limit run
+// instantly.
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::channel::mpsc as futures_mpsc;
+use futures::StreamExt;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use itertools::Either;
+use tokio::sync::mpsc;
+use twitch_irc::login::StaticLoginCredentials;
+use twitch_irc::message::{IRCMessage, IRCParseError};
+use twitch_irc::transport::Transport;
+use twitch_irc::{ClientConfig, TwitchIRCClient};
+
+use borrowbot::api::banphrase::Banphrase;
+use borrowbot::api::emotes::EmoteConfig;
+use borrowbot::api::eventsub::EventSubConfig;
+use borrowbot::api::helix::{Helix, HelixConfig};
+use borrowbot::api::supinic::Supinic;
+use borrowbot::api::APIController;
+use borrowbot::bot::BorrowBot;
+use borrowbot::database::{ChannelRepository, MemoryDB};
+use borrowbot::types::DataDir;
+
+type Incoming = Result<IRCMessage, Either<String, IRCParseError>>;
+
+// State of the fake IRC server for the test running on this thread. Transport::new takes no
+// arguments so this is how a new connection finds the server it belongs to.
+thread_local! {
+    static FAKE_SERVER: RefCell<Option<FakeServer>> = const { RefCell::new(None) };
+}
+
+#[derive(Clone)]
+struct FakeServer {
+    // incoming halves of every connection the client opened, newest last
+    connections: Arc<Mutex<Vec<futures_mpsc::UnboundedSender<Incoming>>>>,
+    // (channel, message) for every PRIVMSG the client sent
+    sent: mpsc::UnboundedSender<(String, String)>,
+}
+
+#[derive(Debug)]
+pub struct FakeTransport {
+    incoming: futures_mpsc::UnboundedReceiver<Incoming>,
+    outgoing: futures_mpsc::UnboundedSender<IRCMessage>,
+}
+
+#[async_trait]
+impl Transport for FakeTransport {
+    type ConnectError = String;
+    type IncomingError = String;
+    type OutgoingError = futures_mpsc::SendError;
+    type Incoming = futures_mpsc::UnboundedReceiver<Incoming>;
+    type Outgoing = futures_mpsc::UnboundedSender<IRCMessage>;
+
+    async fn new() -> Result<Self, String> {
+        let server = FAKE_SERVER
+            .with(|server| server.borrow().clone())
+            .ok_or_else(|| "no fake server installed on this thread".to_owned())?;
+
+        let (incoming_tx, incoming) = futures_mpsc::unbounded();
+        let (outgoing, mut outgoing_rx) = futures_mpsc::unbounded::<IRCMessage>();
+        server.connections.lock().unwrap().push(incoming_tx.clone());
+
+        // plays the part of the twitch server for this connection
+        tokio::spawn(async move {
+            while let Some(message) = outgoing_rx.next().await {
+                let reply = match message.command.as_str() {
+                    "PING" => Some(format!(
+                        ":tmi.twitch.tv PONG tmi.twitch.tv :{}",
+                        message.params.join(" ")
+                    )),
+                    "JOIN" => Some(format!(
+                        ":borrowbot!borrowbot@borrowbot.tmi.twitch.tv JOIN {}",
+                        message.params[0]
+                    )),
+                    "PRIVMSG" => {
+                        let channel = message.params[0].trim_start_matches('#').to_owned();
+                        let text = message.params[1].trim_start_matches(". ");
+                        let text = text.trim_end_matches([' ', '\u{E0000}']);
+                        server.sent.send((channel, text.to_owned())).ok();
+                        None
+                    }
+                    _ => None,
+                };
+
+                if let Some(reply) = reply {
+                    incoming_tx
+                        .unbounded_send(Ok(IRCMessage::parse(&reply).unwrap()))
+                        .ok();
+                }
+            }
+        });
+
+        Ok(FakeTransport { incoming, outgoing })
+    }
+
+    fn split(self) -> (Self::Incoming, Self::Outgoing) {
+        (self.incoming, self.outgoing)
+    }
+}
+
+#[derive(Clone)]
+struct MockResponse {
+    status: u16,
+    body: String,
+    headers: Vec<(String, String)>,
+}
+
+#[derive(Clone)]
+pub struct MockRoute {
+    method: &'static str,
+    // matched against the path, or against path and query if this contains a '?'
+    path: String,
+    // given out in order, the last one repeats forever
+    responses: Vec<MockResponse>,
+    hits: Arc<AtomicUsize>,
+}
+
+impl MockRoute {
+    pub fn new(method: &'static str, path: &str, status: u16, body: &str) -> Self {
+        Self {
+            method,
+            path: path.to_owned(),
+            responses: Vec::new(),
+            hits: Arc::new(AtomicUsize::new(0)),
+        }
+        .then(status, body)
+    }
+
+    pub fn then(mut self, status: u16, body: &str) -> Self {
+        self.responses.push(MockResponse {
+            status,
+            body: body.to_owned(),
+            headers: Vec::new(),
+        });
+        self
+    }
+
+    // adds a header to the most recently added response
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        if let Some(response) = self.responses.last_mut() {
+            response.headers.push((name.to_owned(), value.to_owned()));
+        }
+        self
+    }
+
+    fn next_response(&self) -> MockResponse {
+        let hit = self.hits.fetch_add(1, Ordering::SeqCst);
+        self.responses[hit.min(self.responses.len() - 1)].clone()
+    }
+}
+
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub authorization: Option<String>,
+}
+
+// A tiny HTTP server answering with canned responses, records every request it gets
+pub struct MockHttp {
+    pub url: String,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+}
+
+impl MockHttp {
+    pub async fn start(routes: Vec<MockRoute>) -> Self {
+        let routes = Arc::new(routes);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let service_requests = Arc::clone(&requests);
+        let make_service = make_service_fn(move |_| {
+            let routes = Arc::clone(&routes);
+            let requests = Arc::clone(&service_requests);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let routes = Arc::clone(&routes);
+                    let requests = Arc::clone(&requests);
+                    async move { Ok::<_, Infallible>(respond(&routes, &requests, request)) }
+                }))
+            }
+        });
+
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+
+        Self { url, requests }
+    }
+
+    // "METHOD /path?query" for every request received so far
+    pub fn requests(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| format!("{} {}", r.method, r.path))
+            .collect()
+    }
+
+    pub fn authorizations(&self) -> Vec<Option<String>> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| r.authorization.clone())
+            .collect()
+    }
+}
+
+fn respond(
+    routes: &[MockRoute],
+    requests: &Mutex<Vec<MockRequest>>,
+    request: Request<Body>,
+) -> Response<Body> {
+    let path = request.uri().path().to_owned();
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str().to_owned())
+        .unwrap_or_else(|| path.clone());
+    requests.lock().unwrap().push(MockRequest {
+        method: request.method().to_string(),
+        path: path_and_query.clone(),
+        authorization: request
+            .headers()
+            .get("Authorization")
+            .map(|value| value.to_str().unwrap().to_owned()),
+    });
+
+    let route = routes.iter().find(|route| {
+        route.method == request.method().as_str()
+            && if route.path.contains('?') {
+                route.path == path_and_query
+            } else {
+                route.path == path
+            }
+    });
+
+    match route {
+        Some(route) => {
+            let response = route.next_response();
+            let mut builder = Response::builder()
+                .status(response.status)
+                .header("Content-Type", "application/json");
+            for (name, value) in response.headers {
+                builder = builder.header(name, value);
+            }
+            builder.body(Body::from(response.body)).unwrap()
+        }
+        None => Response::builder()
+            .status(404)
+            .body(Body::from("{\"error\":\"Not Found\",\"status\":404}"))
+            .unwrap(),
+    }
+}
+
+pub fn helix_user_json(id: &str, login: &str) -> String {
+    format!(
+        "{{\"data\":[{{\"id\":\"{}\",\"login\":\"{}\",\"display_name\":\"{}\",\"type\":\"\",\
+        \"broadcaster_type\":\"\",\"description\":\"\",\"profile_image_url\":\"\",\
+        \"offline_image_url\":\"\",\"view_count\":0,\"created_at\":\"2016-12-14T20:32:28Z\"}}]}}",
+        id, login, login
+    )
+}
+
+// Waits for background tasks talking to the mock servers without moving the paused clock
+pub async fn wait_until(mut condition: impl FnMut() -> bool) {
+    let started = std::time::Instant::now();
+    while !condition() {
+        assert!(
+            started.elapsed() < Duration::from_secs(5),
+            "condition wasn't met in time"
+        );
+        // newer compilers apply yield_now's must_use to the awaited unit as well
+        #[allow(unused_must_use)]
+        tokio::task::yield_now().await;
+    }
+}
+
+pub fn helix_stream_json(id: &str, login: &str, title: &str, game: &str, live_for: i64) -> String {
+    let started_at = Utc::now() - chrono::Duration::seconds(live_for);
+    format!(
+        "{{\"data\":[{{\"id\":\"{}\",\"user_id\":\"1\",\"user_login\":\"{}\",\
+        \"user_name\":\"{}\",\"game_id\":\"1\",\"game_name\":\"{}\",\"type\":\"live\",\
+        \"title\":\"{}\",\"viewer_count\":1,\"started_at\":\"{}\"}}]}}",
+        id,
+        login,
+        login,
+        game,
+        title,
+        started_at.to_rfc3339()
+    )
+}
+
+pub async fn helix_for(mock: &MockHttp, access_token: Option<&str>) -> Helix {
+    Helix::with_config(HelixConfig {
+        client_id: "test_client_id".to_owned(),
+        client_secret: "test_client_secret".to_owned(),
+        access_token: access_token.map(|token| token.to_owned()),
+        user_token: Some("test_user_token".to_owned()),
+        api_url: format!("{}/helix", mock.url),
+        auth_url: format!("{}/oauth2", mock.url),
+    })
+    .await
+    .unwrap()
+}
+
+pub fn app_token_json(token: &str, expires_in: u64) -> String {
+    format!(
+        "{{\"access_token\":\"{}\",\"expires_in\":{},\"token_type\":\"bearer\"}}",
+        token, expires_in
+    )
+}
+
+pub fn validate_json(expires_in: i64) -> String {
+    format!(
+        "{{\"client_id\":\"test_client_id\",\"scopes\":[],\"expires_in\":{}}}",
+        expires_in
+    )
+}
+
+pub struct TestBot {
+    pub db: Arc<MemoryDB>,
+    pub bot: Arc<BorrowBot>,
+    pub helix: MockHttp,
+    pub banphrase: MockHttp,
+    pub supinic: MockHttp,
+
+    // serves 7tv, bttv and ffz under /7tv, /bttv and /ffz
+    pub emotes: MockHttp,
+    server: FakeServer,
+    sent: mpsc::UnboundedReceiver<(String, String)>,
+    message_id: u64,
+}
+
+pub struct TestBotBuilder {
+    db: Arc<MemoryDB>,
+    helix_routes: Vec<MockRoute>,
+    banphrase_routes: Vec<MockRoute>,
+    emote_routes: Vec<MockRoute>,
+    eventsub_secret: Option<String>,
+    data_dir: PathBuf,
+}
+
+// every test bot gets a data directory of its own, so tests running in parallel never share files
+static DATA_DIRS: AtomicUsize = AtomicUsize::new(0);
+
+impl TestBotBuilder {
+    // every channel given is joined before the bot starts
+    pub fn new(channels: &[&str]) -> Self {
+        let db = Arc::new(MemoryDB::new());
+        db.add_command("help", "Lists every command", 0, 5);
+        db.add_command("ping", "Pong!", 0, 5);
+        db.add_command("say", "Usage: &say <message>", 1, 5);
+        db.add_command("uid", "Usage: &uid [user]", 0, 5);
+        db.add_command("join", "Usage: &join <channel>", 2, 0);
+        db.add_command("leave", "Usage: &leave <channel>", 2, 0);
+        db.add_command("lastmessage", "Usage: &lastmessage [user] [channel]", 0, 5);
+        db.add_command("title", "Usage: &title [channel]", 0, 5);
+        db.add_command("game", "Usage: &game [channel]", 0, 5);
+        db.add_command("uptime", "Usage: &uptime [channel]", 0, 5);
+        db.add_command("lastseen", "Usage: &lastseen live <channel>", 0, 5);
+        db.add_command("notify", "Usage: &notify <live|title>", 0, 5);
+        db.add_command("unnotify", "Usage: &unnotify [live|title]", 0, 5);
+        db.add_command("accountage", "Usage: &accountage [user]", 0, 5);
+        db.add_command("followage", "Usage: &followage [user] [channel]", 0, 5);
+        db.add_command("user", "Usage: &user [login]", 0, 5);
+        db.add_command("emote", "Usage: &emote <name> [channel]", 0, 5);
+        db.add_command("randomemote", "Usage: &randomemote [channel]", 0, 5);
+        db.add_command("emotecount", "Usage: &emotecount <emote>", 0, 5);
+        db.add_command(
+            "topemotes",
+            "Usage: &topemotes [day|week|month|year|all]",
+            0,
+            5,
+        );
+        db.add_command("unusedemotes", "Usage: &unusedemotes [days]", 1, 5);
+        db.add_command(
+            "remind",
+            "Usage: &remind <user|me> [in <time>] <message>",
+            0,
+            5,
+        );
+        db.add_command("unremind", "Usage: &unremind <id>", 0, 5);
+        db.add_command("afk", "Usage: &afk [reason]", 0, 5);
+        db.add_command("gn", "Usage: &gn [reason]", 0, 5);
+        db.add_command("brb", "Usage: &brb [reason]", 0, 5);
+        db.add_command("disable", "Usage: &disable <command>", 1, 0);
+        db.add_command("enable", "Usage: &enable <command>", 1, 0);
+        db.add_command(
+            "timer",
+            "Usage: &timer <add|once|list|pause|resume|delete>",
+            1,
+            0,
+        );
+        db.add_command("markov", "Usage: &markov [@user] [seed word]", 0, 10);
+        db.add_command("addquote", "Usage: &addquote <user> <text>", 0, 10);
+        db.add_command("quote", "Usage: &quote [id|user|search]", 0, 5);
+        db.add_command("delquote", "Usage: &delquote <id>", 1, 0);
+        db.add_command(
+            "quotes",
+            "Usage: &quotes export, or &quotes import <file>",
+            2,
+            0,
+        );
+        db.add_command("counter", "Usage: &counter <add|delete|list|history>", 1, 0);
+        db.add_command("points", "Usage: &points [user]", 0, 5);
+        db.add_command("give", "Usage: &give <user> <amount|all>", 0, 5);
+        db.add_command("roulette", "Usage: &roulette <amount|all>", 0, 5);
+        db.add_command("slots", "Usage: &slots <amount|all>", 0, 5);
+        db.add_command("duel", "Usage: &duel <user> <amount>", 0, 5);
+        db.add_command("toppoints", "Usage: &toppoints", 0, 10);
+        db.add_command("resetpoints", "Usage: &resetpoints <user|all>", 1, 0);
+        db.add_command(
+            "poll",
+            "Usage: &poll \"question\" option 1 | option 2",
+            1,
+            0,
+        );
+        db.add_command("bet", "Usage: &bet <option> <amount|all>", 0, 5);
+        db.add_command("trivia", "Usage: &trivia start <pack> [questions]", 0, 5);
+        db.add_command("hangman", "Usage: &hangman", 0, 30);
+        db.add_command("emoteguess", "Usage: &emoteguess", 0, 30);
+        db.add_command(
+            "setpermissions",
+            "Usage: &setpermissions <user> <0|1|2>",
+            2,
+            0,
+        );
+
+        let builder = Self {
+            db,
+            helix_routes: Vec::new(),
+            emote_routes: Vec::new(),
+            eventsub_secret: None,
+            data_dir: std::env::temp_dir().join(format!(
+                "borrowbot-test-{}-{}",
+                std::process::id(),
+                DATA_DIRS.fetch_add(1, Ordering::SeqCst)
+            )),
+            banphrase_routes: vec![MockRoute::new(
+                "POST",
+                "/banphrases/test",
+                200,
+                "{\"banned\":false,\"input_message\":\"\",\"banphrase_data\":null}",
+            )],
+        };
+
+        for channel in channels {
+            futures::executor::block_on(builder.db.modify_or_insert_joined_value(channel, true))
+                .unwrap();
+        }
+
+        builder
+    }
+
+    pub fn db(&self) -> Arc<MemoryDB> {
+        Arc::clone(&self.db)
+    }
+
+    // modguy (uid 9) can use moderator commands
+    pub fn with_moderator(self) -> Self {
+        self.db.add_user(9, "modguy", 1);
+        self
+    }
+
+    pub fn helix_route(mut self, route: MockRoute) -> Self {
+        self.helix_routes.push(route);
+        self
+    }
+
+    // where the bot looks for operator files, empty unless a test adds some
+    pub fn data_dir(&self) -> PathBuf {
+        self.data_dir.clone()
+    }
+
+    // writes a file into the bot's data directory, `path` is relative to it
+    pub fn data_file(self, path: &str, contents: &str) -> Self {
+        let path = self.data_dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+        self
+    }
+
+    pub fn emote_route(mut self, route: MockRoute) -> Self {
+        self.emote_routes.push(route);
+        self
+    }
+
+    // receive EventSub on a random local port, signed with `secret`
+    pub fn eventsub(mut self, secret: &str) -> Self {
+        self.eventsub_secret = Some(secret.to_owned());
+        self
+    }
+
+    pub fn banphrase_route(mut self, route: MockRoute) -> Self {
+        self.banphrase_routes.insert(0, route);
+        self
+    }
+
+    pub async fn start(mut self) -> TestBot {
+        self.helix_routes.push(MockRoute::new(
+            "GET",
+            "/oauth2/validate",
+            200,
+            &validate_json(5000000),
+        ));
+        // nobody is live unless a test says so
+        self.helix_routes.push(MockRoute::new(
+            "GET",
+            "/helix/streams",
+            200,
+            "{\"data\":[]}",
+        ));
+        self.helix_routes.push(MockRoute::new(
+            "GET",
+            "/helix/eventsub/subscriptions",
+            200,
+            "{\"data\":[],\"pagination\":{}}",
+        ));
+        self.helix_routes.push(MockRoute::new(
+            "POST",
+            "/helix/eventsub/subscriptions",
+            202,
+            "{\"data\":[]}",
+        ));
+        let helix = MockHttp::start(self.helix_routes).await;
+        let banphrase = MockHttp::start(self.banphrase_routes).await;
+        // channels have no third party emotes unless a test says so
+        let emotes = MockHttp::start(self.emote_routes).await;
+        let supinic = MockHttp::start(vec![MockRoute::new(
+            "PUT",
+            "/bot-program/bot/active",
+            200,
+            "{}",
+        )])
+        .await;
+
+        let (sent_tx, sent) = mpsc::unbounded_channel();
+        let server = FakeServer {
+            connections: Arc::new(Mutex::new(Vec::new())),
+            sent: sent_tx,
+        };
+        FAKE_SERVER.with(|fake_server| *fake_server.borrow_mut() = Some(server.clone()));
+
+        let mut api = APIController::new(
+            Arc::new(helix_for(&helix, Some("test_access_token")).await),
+            Arc::new(Supinic::with_config("", "", supinic.url.clone())),
+            Arc::new(Banphrase::with_url(format!(
+                "{}/banphrases/test",
+                banphrase.url
+            ))),
+        )
+        .with_emotes(EmoteConfig {
+            seventv_url: format!("{}/7tv", emotes.url),
+            bttv_url: format!("{}/bttv", emotes.url),
+            ffz_url: format!("{}/ffz", emotes.url),
+        });
+        if let Some(secret) = self.eventsub_secret {
+            api = api.with_eventsub(&EventSubConfig {
+                secret,
+                callback_url: "https://bot.example/eventsub".to_owned(),
+                listen_addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+                bot_user_id: None,
+            });
+        }
+        let api = Arc::new(api);
+
+        let config = ClientConfig::new_simple(StaticLoginCredentials::new(
+            "borrowbot".to_owned(),
+            Some("test_oauth".to_owned()),
+        ));
+        let (irc_stream, irc_client) =
+            TwitchIRCClient::<FakeTransport, StaticLoginCredentials>::new(config);
+
+        let bot = Arc::new(
+            BorrowBot::from_parts(
+                irc_stream,
+                Arc::new(irc_client),
+                Arc::clone(&self.db) as _,
+                Arc::clone(&self.db) as _,
+                api,
+            )
+            .await
+            .with_data_dir(DataDir::new(self.data_dir)),
+        );
+        tokio::spawn(BorrowBot::run(Arc::clone(&bot)));
+
+        TestBot {
+            db: self.db,
+            bot,
+            helix,
+            banphrase,
+            supinic,
+            emotes,
+            server,
+            sent,
+            message_id: 0,
+        }
+    }
+}
+
+impl TestBot {
+    // a chat message from `login` arriving in `channel`
+    pub async fn chat(&mut self, channel: &str, login: &str, uid: i32, text: &str) {
+        self.chat_with_emotes(channel, login, uid, text, "", 1600000000000)
+            .await;
+    }
+
+    // `emotes` is the raw twitch emotes tag, like "25:0-4,6-10"
+    pub async fn chat_with_emotes(
+        &mut self,
+        channel: &str,
+        login: &str,
+        uid: i32,
+        text: &str,
+        emotes: &str,
+        sent_ts: i64,
+    ) {
+        self.message_id += 1;
+        let raw = format!(
+            "@badge-info=;badges=;color=#FF0000;display-name={login};emotes={emotes};flags=;\
+            id=00000000-0000-0000-0000-{id:012};mod=0;room-id=1;subscriber=0;\
+            tmi-sent-ts={sent_ts};turbo=0;user-id={uid};user-type= \
+            :{login}!{login}@{login}.tmi.twitch.tv PRIVMSG #{channel} :{text}",
+            login = login,
+            emotes = emotes,
+            id = self.message_id,
+            sent_ts = sent_ts,
+            uid = uid,
+            channel = channel,
+            text = text
+        );
+        self.send_raw(&raw).await;
+    }
+
+    pub async fn send_raw(&mut self, raw: &str) {
+        let message = IRCMessage::parse(raw).unwrap();
+
+        // the client only opens its connection once run() sets the wanted channels
+        loop {
+            let connection = self.server.connections.lock().unwrap().last().cloned();
+            if let Some(connection) = connection {
+                connection.unbounded_send(Ok(message)).unwrap();
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    // next (channel, message) the bot said, None if it stays quiet for a minute
+    pub async fn next_message(&mut self) -> Option<(String, String)> {
+        self.next_message_within(Duration::from_secs(60)).await
+    }
+
+    pub async fn next_message_within(&mut self, duration: Duration) -> Option<(String, String)> {
+        tokio::time::timeout(duration, self.sent.recv())
+            .await
+            .ok()
+            .flatten()
+    }
+
+    // waits for the bot's next message in `channel`, skipping anything said elsewhere
+    pub async fn expect_message_in(&mut self, channel: &str) -> String {
+        loop {
+            match self.next_message().await {
+                Some((sent_channel, message)) if sent_channel == channel => return message,
+                Some(_) => continue,
+                None => panic!(
+                    "expected a message in #{} but the bot stayed quiet",
+                    channel
+                ),
+            }
+        }
+    }
+
+    pub async fn expect_silence(&mut self, duration: Duration) {
+        if let Some((channel, message)) = self.next_message_within(duration).await {
+            panic!(
+                "expected no messages but the bot said '{}' in #{}",
+                message, channel
+            );
+        }
+    }
+
+    pub fn helix_requests(&self) -> Vec<String> {
+        self.helix.requests()
+    }
+}
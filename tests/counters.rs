@@ -0,0 +1,162 @@
+mod common;
+
+use std::time::Duration;
+
+use common::TestBotBuilder;
+
+#[tokio::test(start_paused = true)]
+async fn moderators_keep_count_and_chat_can_look() {
+    let mut bot = TestBotBuilder::new(&["forsen", "pajlada"])
+        .with_moderator()
+        .start()
+        .await;
+
+    bot.chat("forsen", "modguy", 9, "&counter add quote").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, &quote is already a command"
+    );
+    bot.chat(
+        "forsen",
+        "modguy",
+        9,
+        "&counter add Deaths forsen died {count} times",
+    )
+    .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Added &deaths, moderators change it with &deaths +1, -1, set <n> or reset"
+    );
+
+    bot.chat("forsen", "alice", 1, "&deaths").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, forsen died 0 times"
+    );
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "alice", 1, "&deaths +").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Sorry, only moderators can change &deaths"
+    );
+    // counters are per channel
+    bot.chat("pajlada", "bob", 2, "&deaths").await;
+    bot.expect_silence(Duration::from_secs(5)).await;
+
+    for (change, expected) in [
+        ("+", "forsen died 1 times"),
+        ("+5", "forsen died 6 times"),
+        ("-", "forsen died 5 times"),
+        ("set 10", "forsen died 10 times"),
+        ("-3", "forsen died 7 times"),
+    ] {
+        bot.chat("forsen", "modguy", 9, &format!("&deaths {}", change))
+            .await;
+        assert_eq!(
+            bot.expect_message_in("forsen").await,
+            format!("@modguy, {}", expected)
+        );
+    }
+    bot.chat("forsen", "modguy", 9, "&deaths reset").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, forsen died 0 times"
+    );
+
+    bot.chat("forsen", "modguy", 9, "&counter history deaths")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, &deaths: reset by modguy 0s ago (now 0), -3 by modguy 0s ago (now 7), \
+        set to 10 by modguy 0s ago (now 10), -1 by modguy 0s ago (now 5), \
+        +5 by modguy 0s ago (now 6)"
+    );
+    bot.chat("forsen", "modguy", 9, "&counter add wins").await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "modguy", 9, "&counter list").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Counters in #forsen: &deaths, &wins"
+    );
+    bot.chat("forsen", "modguy", 9, "&wins").await;
+    assert_eq!(bot.expect_message_in("forsen").await, "@modguy, wins: 0");
+
+    bot.chat("forsen", "modguy", 9, "&counter delete deaths")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Deleted &deaths"
+    );
+    bot.chat("forsen", "modguy", 9, "&deaths").await;
+    bot.expect_silence(Duration::from_secs(5)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn simultaneous_changes_all_count() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .with_moderator()
+        .start()
+        .await;
+
+    bot.chat("forsen", "modguy", 9, "&counter add wins").await;
+    bot.expect_message_in("forsen").await;
+
+    for _ in 0..20 {
+        bot.chat("forsen", "modguy", 9, "&wins +").await;
+    }
+    let mut seen = Vec::new();
+    for _ in 0..20 {
+        let response = bot.expect_message_in("forsen").await;
+        let value: i64 = response
+            .trim_start_matches("@modguy, wins: ")
+            .parse()
+            .unwrap();
+        seen.push(value);
+    }
+    seen.sort_unstable();
+    assert_eq!(seen, (1..=20).collect::<Vec<i64>>());
+
+    bot.chat("forsen", "modguy", 9, "&wins").await;
+    assert_eq!(bot.expect_message_in("forsen").await, "@modguy, wins: 20");
+}
+
+#[tokio::test(start_paused = true)]
+async fn counters_fill_in_templates_and_timers() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .with_moderator()
+        .start()
+        .await;
+
+    bot.chat("forsen", "modguy", 9, "&counter add wins").await;
+    bot.expect_message_in("forsen").await;
+    bot.chat(
+        "forsen",
+        "modguy",
+        9,
+        "&counter add losses {count} losses and {counter:wins} wins, {counter:typo}",
+    )
+    .await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "modguy", 9, "&wins +3").await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "modguy", 9, "&losses +").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, 1 losses and 3 wins, {counter:typo}"
+    );
+
+    bot.chat(
+        "forsen",
+        "modguy",
+        9,
+        "&timer once score 5m Today's score: {counter:wins}-{counter:losses}",
+    )
+    .await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "modguy", 9, "&wins +").await;
+    bot.expect_message_in("forsen").await;
+    assert_eq!(
+        bot.next_message_within(Duration::from_secs(6 * 60)).await,
+        Some(("forsen".to_owned(), "Today's score: 4-1".to_owned()))
+    );
+}
@@ -0,0 +1,350 @@
+mod common;
+
+use std::time::Duration;
+
+use borrowbot::database::LogRepository;
+use chrono::Utc;
+use common::{helix_user_json, wait_until, MockRoute, TestBotBuilder};
+
+const SEVENTV_CHANNEL: &str = "{\"id\":\"22484632\",\"emote_set\":{\"emotes\":[\
+    {\"id\":\"60ae4bb30e35477634610fda\",\"name\":\"forsenE\",\
+    \"data\":{\"owner\":{\"username\":\"someone\"}}}]}}";
+
+const BTTV_CHANNEL: &str = "{\"channelEmotes\":[{\"id\":\"b1\",\"code\":\"OMEGALUL\"}],\
+    \"sharedEmotes\":[{\"id\":\"b2\",\"code\":\"PepeLaugh\",\"user\":{\"name\":\"pepega\"}}]}";
+
+const FFZ_GLOBAL: &str = "{\"default_sets\":[3],\"sets\":{\
+    \"3\":{\"emoticons\":[{\"id\":9,\"name\":\"ZreknarF\",\"owner\":{\"name\":\"zrekn\"}}]},\
+    \"4\":{\"emoticons\":[{\"id\":10,\"name\":\"NotDefault\",\"owner\":null}]}}}";
+
+fn forsen() -> TestBotBuilder {
+    TestBotBuilder::new(&["forsen"]).helix_route(MockRoute::new(
+        "GET",
+        "/helix/users?login=forsen",
+        200,
+        &helix_user_json("22484632", "forsen"),
+    ))
+}
+
+#[tokio::test(start_paused = true)]
+async fn emote_names_the_provider_and_uploader() {
+    let mut bot = forsen()
+        .emote_route(MockRoute::new(
+            "GET",
+            "/7tv/users/twitch/22484632",
+            200,
+            SEVENTV_CHANNEL,
+        ))
+        .emote_route(MockRoute::new(
+            "GET",
+            "/bttv/cached/users/twitch/22484632",
+            200,
+            BTTV_CHANNEL,
+        ))
+        .emote_route(MockRoute::new("GET", "/ffz/set/global", 200, FFZ_GLOBAL))
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&emote OMEGALUL").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, OMEGALUL is a BTTV #forsen emote by forsen https://betterttv.com/emotes/b1"
+    );
+
+    bot.chat("forsen", "bob", 2, "&emote forsenE").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, forsenE is a 7TV #forsen emote by someone https://7tv.app/emotes/60ae4bb30e35477634610fda"
+    );
+
+    // any capitalization works when nothing matches exactly
+    bot.chat("forsen", "carol", 3, "&emote zreknarf").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, ZreknarF is a global FFZ emote by zrekn https://www.frankerfacez.com/emoticon/9"
+    );
+
+    bot.chat("forsen", "dave", 4, "&emote NotDefault").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@dave, forsen doesn't have a 7TV, BTTV or FFZ emote called that"
+    );
+
+    // every list was only fetched once
+    let requests = bot.emotes.requests();
+    assert_eq!(requests.len(), 6, "{:?}", requests);
+}
+
+#[tokio::test(start_paused = true)]
+async fn emotes_are_refreshed_and_outages_keep_the_old_list() {
+    let mut bot = forsen()
+        .emote_route(
+            MockRoute::new("GET", "/7tv/users/twitch/22484632", 200, SEVENTV_CHANNEL)
+                .then(500, "{}"),
+        )
+        .emote_route(
+            MockRoute::new(
+                "GET",
+                "/bttv/cached/users/twitch/22484632",
+                200,
+                BTTV_CHANNEL,
+            )
+            .then(
+                200,
+                "{\"channelEmotes\":[{\"id\":\"b3\",\"code\":\"LULE\"}],\"sharedEmotes\":[]}",
+            ),
+        )
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&emote OMEGALUL").await;
+    assert!(bot.expect_message_in("forsen").await.contains("BTTV"));
+
+    tokio::time::sleep(Duration::from_secs(1800)).await;
+    wait_until(|| {
+        bot.emotes
+            .requests()
+            .iter()
+            .filter(|r| r.starts_with("GET /bttv/cached/users"))
+            .count()
+            == 2
+    })
+    .await;
+
+    bot.chat("forsen", "alice", 1, "&emote LULE").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, LULE is a BTTV #forsen emote by forsen https://betterttv.com/emotes/b3"
+    );
+
+    // 7tv failing the refresh doesn't make its emotes disappear
+    bot.chat("forsen", "bob", 2, "&emote forsenE").await;
+    assert!(bot
+        .expect_message_in("forsen")
+        .await
+        .starts_with("@bob, forsenE is a 7TV #forsen emote"));
+
+    bot.chat("forsen", "carol", 3, "&emote OMEGALUL").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, forsen doesn't have a 7TV, BTTV or FFZ emote called that"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn other_channels_are_looked_up_without_being_kept_fresh() {
+    let mut bot = forsen()
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users?login=xqcow",
+            200,
+            &helix_user_json("71092938", "xqcow"),
+        ))
+        .emote_route(MockRoute::new(
+            "GET",
+            "/7tv/users/twitch/71092938",
+            200,
+            SEVENTV_CHANNEL,
+        ))
+        .start()
+        .await;
+    let lookups = |bot: &common::TestBot| {
+        bot.emotes
+            .requests()
+            .iter()
+            .filter(|r| r.starts_with("GET /7tv/users/twitch/71092938"))
+            .count()
+    };
+
+    bot.chat("forsen", "alice", 1, "&emote forsenE xqcow").await;
+    assert!(bot
+        .expect_message_in("forsen")
+        .await
+        .starts_with("@alice, forsenE is a 7TV #xqcow emote"));
+    bot.chat("forsen", "bob", 2, "&emote forsenE xqcow").await;
+    bot.expect_message_in("forsen").await;
+    assert_eq!(lookups(&bot), 1);
+
+    // the refresh leaves it alone, and by then it's too old to be used again
+    tokio::time::sleep(Duration::from_secs(1800)).await;
+    wait_until(|| {
+        bot.emotes
+            .requests()
+            .iter()
+            .filter(|r| r.starts_with("GET /7tv/emote-sets/global"))
+            .count()
+            == 2
+    })
+    .await;
+    assert_eq!(lookups(&bot), 1);
+
+    bot.chat("forsen", "carol", 3, "&emote forsenE xqcow").await;
+    bot.expect_message_in("forsen").await;
+    assert_eq!(lookups(&bot), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn randomemote_prefers_channel_emotes() {
+    let mut bot = forsen()
+        .emote_route(MockRoute::new(
+            "GET",
+            "/7tv/users/twitch/22484632",
+            200,
+            SEVENTV_CHANNEL,
+        ))
+        .emote_route(MockRoute::new("GET", "/ffz/set/global", 200, FFZ_GLOBAL))
+        .helix_route(MockRoute::new("GET", "/helix/users", 200, "{\"data\":[]}"))
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&randomemote").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, forsenE (7TV)"
+    );
+
+    bot.chat("forsen", "bob", 2, "&randomemote nobody").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, Sorry, I couldn't find that channel"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn emote_usage_is_counted_as_messages_arrive() {
+    let builder = TestBotBuilder::new(&["forsen"]).emote_route(MockRoute::new(
+        "GET",
+        "/7tv/users/twitch/1",
+        200,
+        "{\"emote_set\":{\"emotes\":[{\"id\":\"a\",\"name\":\"forsenE\",\"data\":null},\
+        {\"id\":\"b\",\"name\":\"forsenOld\",\"data\":null}]}}",
+    ));
+    builder.db().add_user(9, "modguy", 1);
+    let mut bot = builder.start().await;
+
+    // the first message from a channel loads its emote lists
+    bot.chat("forsen", "alice", 1, "hi").await;
+    wait_until(|| bot.emotes.requests().len() == 6).await;
+
+    let now = Utc::now();
+    let long_ago = now - chrono::Duration::days(40);
+    bot.chat_with_emotes(
+        "forsen",
+        "alice",
+        1,
+        "forsenE",
+        "",
+        long_ago.timestamp_millis(),
+    )
+    .await;
+    bot.chat_with_emotes(
+        "forsen",
+        "bob",
+        2,
+        "Kappa forsenE Kappa forsenE forsenE",
+        "25:0-4,14-18",
+        now.timestamp_millis(),
+    )
+    .await;
+
+    bot.chat("forsen", "alice", 1, "&emotecount forsenE").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        format!(
+            "@alice, forsenE has been used 4 times in #forsen, last on {}",
+            now.format("%Y-%m-%d")
+        )
+    );
+
+    bot.chat("forsen", "bob", 2, "&topemotes week").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, Top emotes in #forsen this week: forsenE (3), Kappa (2)"
+    );
+
+    bot.chat("forsen", "carol", 3, "&topemotes").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, Top emotes in #forsen of all time: forsenE (4), Kappa (2)"
+    );
+
+    bot.chat("forsen", "modguy", 9, "&unusedemotes 30").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, 1 unused for 30 days: forsenOld (7TV)"
+    );
+
+    bot.chat("forsen", "modguy", 9, "&emotecount LULW").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, LULW hasn't been used in #forsen yet"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn emote_usage_is_saved_in_batches() {
+    let mut bot = TestBotBuilder::new(&["forsen"]).start().await;
+
+    bot.chat_with_emotes(
+        "forsen",
+        "bob",
+        2,
+        "Kappa Kappa",
+        "25:0-4,6-10",
+        Utc::now().timestamp_millis(),
+    )
+    .await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(
+        bot.db.get_emote_count("forsen", "Kappa").await.unwrap(),
+        None
+    );
+
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    let (uses, _) = bot
+        .db
+        .get_emote_count("forsen", "Kappa")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(uses, 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn emote_lists_that_fail_to_load_are_retried_later() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .emote_route(MockRoute::new("GET", "/7tv/emote-sets/global", 500, "{}"))
+        .emote_route(MockRoute::new(
+            "GET",
+            "/bttv/cached/emotes/global",
+            500,
+            "{}",
+        ))
+        .emote_route(MockRoute::new("GET", "/ffz/set/global", 500, "{}"))
+        .start()
+        .await;
+    let global_loads = |bot: &common::TestBot| {
+        bot.emotes
+            .requests()
+            .iter()
+            .filter(|r| r.starts_with("GET /7tv/emote-sets/global"))
+            .count()
+    };
+
+    for i in 0..5 {
+        bot.chat("forsen", "alice", 1, &format!("hi {}", i)).await;
+    }
+    wait_until(|| bot.db.message_count() == 5).await;
+    // gives the failed load real time to finish without moving the paused clock
+    let started = std::time::Instant::now();
+    wait_until(|| started.elapsed() > Duration::from_millis(200)).await;
+    assert_eq!(global_loads(&bot), 1);
+
+    bot.chat("forsen", "alice", 1, "hi again").await;
+    wait_until(|| bot.db.message_count() == 6).await;
+    assert_eq!(global_loads(&bot), 1);
+
+    tokio::time::sleep(Duration::from_secs(300)).await;
+    bot.chat("forsen", "alice", 1, "one more time").await;
+    wait_until(|| global_loads(&bot) == 2).await;
+}
@@ -0,0 +1,389 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use borrowbot::api::eventsub::{
+    signature, EventSubConfig, EventSubManager, EventSubReceiver, TwitchEvent,
+};
+use borrowbot::api::usercache::UserCache;
+use chrono::Utc;
+use common::{
+    helix_for, helix_stream_json, helix_user_json, validate_json, wait_until, MockHttp, MockRoute,
+    TestBotBuilder,
+};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+
+const SECRET: &str = "s3cre7 but not really";
+
+// the same headers the twitch cli sends along with its test events
+fn headers(message_id: &str, message_type: &str, timestamp: &str, body: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("Twitch-Eventsub-Message-Id", message_id.parse().unwrap());
+    headers.insert(
+        "Twitch-Eventsub-Message-Timestamp",
+        timestamp.parse().unwrap(),
+    );
+    headers.insert(
+        "Twitch-Eventsub-Message-Type",
+        message_type.parse().unwrap(),
+    );
+    headers.insert(
+        "Twitch-Eventsub-Message-Signature",
+        signature(SECRET, message_id, timestamp, body.as_bytes())
+            .parse()
+            .unwrap(),
+    );
+    headers
+}
+
+fn signed(message_id: &str, message_type: &str, body: &str) -> HeaderMap {
+    headers(message_id, message_type, &Utc::now().to_rfc3339(), body)
+}
+
+fn notification(type_field: &str, event: &str) -> String {
+    format!(
+        "{{\"subscription\":{{\"id\":\"f1c2a387\",\"status\":\"enabled\",\"type\":\"{}\",\
+        \"version\":\"1\",\"condition\":{{}},\"transport\":{{\"method\":\"webhook\",\
+        \"callback\":\"https://bot.example/eventsub\"}},\"created_at\":\"2021-11-16T10:11:12Z\"}},\
+        \"event\":{}}}",
+        type_field, event
+    )
+}
+
+fn stream_online(login: &str) -> String {
+    notification(
+        "stream.online",
+        &format!(
+            "{{\"id\":\"9001\",\"broadcaster_user_id\":\"1337\",\"broadcaster_user_login\":\"{}\",\
+            \"broadcaster_user_name\":\"{}\",\"type\":\"live\",\
+            \"started_at\":\"2021-11-16T10:11:12Z\"}}",
+            login, login
+        ),
+    )
+}
+
+fn receiver() -> Arc<EventSubReceiver> {
+    Arc::new(EventSubReceiver::new(
+        SECRET.to_owned(),
+        SocketAddr::from(([127, 0, 0, 1], 0)),
+    ))
+}
+
+#[tokio::test]
+async fn challenges_are_answered_over_http() {
+    let addr = EventSubReceiver::serve(receiver(), None).unwrap();
+    let body = "{\"challenge\":\"pogchamp-kappa-360noscope-vohiyo\",\"subscription\":{\"id\":\"f1c2a387\",\
+        \"status\":\"webhook_callback_verification_pending\",\"type\":\"stream.online\",\
+        \"version\":\"1\",\"condition\":{\"broadcaster_user_id\":\"1337\"}}}";
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("http://{}/eventsub", addr))
+        .headers(signed("e76c6bd4", "webhook_callback_verification", body))
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.text().await.unwrap(),
+        "pogchamp-kappa-360noscope-vohiyo"
+    );
+
+    let resp = client
+        .get(format!("http://{}/eventsub", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[tokio::test]
+async fn unsigned_and_stale_messages_are_refused() {
+    let receiver = receiver();
+    let mut events = receiver.subscribe();
+    let body = stream_online("forsen");
+
+    let mut forged = signed("a1", "notification", &body);
+    forged.insert(
+        "Twitch-Eventsub-Message-Signature",
+        "sha256=0123456789abcdef".parse().unwrap(),
+    );
+    assert_eq!(
+        receiver.handle(&forged, body.as_bytes()).0,
+        StatusCode::FORBIDDEN
+    );
+
+    let tampered = signed("a2", "notification", &body);
+    let tampered_body = body.replace("forsen", "xqc");
+    assert_eq!(
+        receiver.handle(&tampered, tampered_body.as_bytes()).0,
+        StatusCode::FORBIDDEN
+    );
+
+    let an_hour_ago = (Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+    let stale = headers("a3", "notification", &an_hour_ago, &body);
+    assert_eq!(
+        receiver.handle(&stale, body.as_bytes()).0,
+        StatusCode::FORBIDDEN
+    );
+
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn notifications_are_typed_and_dispatched_once() {
+    let receiver = receiver();
+    let mut events = receiver.subscribe();
+
+    let online = stream_online("forsen");
+    for _ in 0..2 {
+        let (status, _) =
+            receiver.handle(&signed("b1", "notification", &online), online.as_bytes());
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    let raid = notification(
+        "channel.raid",
+        "{\"from_broadcaster_user_id\":\"1\",\"from_broadcaster_user_login\":\"xqc\",\
+        \"from_broadcaster_user_name\":\"xQc\",\"to_broadcaster_user_id\":\"1337\",\
+        \"to_broadcaster_user_login\":\"forsen\",\"to_broadcaster_user_name\":\"forsen\",\
+        \"viewers\":9001}",
+    );
+    receiver.handle(&signed("b2", "notification", &raid), raid.as_bytes());
+
+    let revocation = "{\"subscription\":{\"id\":\"f1c2a387\",\"status\":\"authorization_revoked\",\
+        \"type\":\"channel.update\",\"version\":\"2\",\"condition\":{}}}";
+    receiver.handle(
+        &signed("b3", "revocation", revocation),
+        revocation.as_bytes(),
+    );
+
+    assert_eq!(
+        events.recv().await.unwrap(),
+        TwitchEvent::StreamOnline {
+            broadcaster_id: "1337".to_owned(),
+            broadcaster_login: "forsen".to_owned()
+        }
+    );
+    assert_eq!(
+        events.recv().await.unwrap(),
+        TwitchEvent::ChannelRaid {
+            from_broadcaster_login: "xqc".to_owned(),
+            to_broadcaster_login: "forsen".to_owned(),
+            viewers: 9001
+        }
+    );
+    assert_eq!(
+        events.recv().await.unwrap(),
+        TwitchEvent::Revoked {
+            subscription_type: "channel.update".to_owned(),
+            status: "authorization_revoked".to_owned()
+        }
+    );
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn reconcile_creates_missing_and_removes_stale_subscriptions() {
+    let subscription = |id: &str, status: &str, type_field: &str, condition: &str| {
+        format!(
+            "{{\"id\":\"{}\",\"status\":\"{}\",\"type\":\"{}\",\"version\":\"1\",\
+            \"condition\":{},\"transport\":{{\"method\":\"webhook\",\
+            \"callback\":\"https://bot.example/eventsub\"}},\"created_at\":\"2021-11-16T10:11:12Z\",\
+            \"cost\":1}}",
+            id, status, type_field, condition
+        )
+    };
+    let existing = format!(
+        "{{\"data\":[{},{},{}],\"pagination\":{{}}}}",
+        subscription(
+            "keep",
+            "enabled",
+            "stream.online",
+            "{\"broadcaster_user_id\":\"22484632\"}"
+        ),
+        subscription(
+            "failed",
+            "webhook_callback_verification_failed",
+            "stream.offline",
+            "{\"broadcaster_user_id\":\"22484632\"}"
+        ),
+        subscription(
+            "left",
+            "enabled",
+            "stream.online",
+            "{\"broadcaster_user_id\":\"11148817\"}"
+        ),
+    );
+
+    let mock = MockHttp::start(vec![
+        MockRoute::new("GET", "/oauth2/validate", 200, &validate_json(5000000)),
+        MockRoute::new(
+            "GET",
+            "/helix/users?login=forsen",
+            200,
+            &helix_user_json("22484632", "forsen"),
+        ),
+        MockRoute::new("GET", "/helix/eventsub/subscriptions", 200, &existing),
+        MockRoute::new(
+            "POST",
+            "/helix/eventsub/subscriptions",
+            202,
+            "{\"data\":[]}",
+        ),
+        MockRoute::new("DELETE", "/helix/eventsub/subscriptions", 204, ""),
+    ])
+    .await;
+    let helix = Arc::new(helix_for(&mock, Some("token")).await);
+    let users = Arc::new(UserCache::new(Arc::clone(&helix)));
+    let manager = EventSubManager::new(
+        helix,
+        users,
+        &EventSubConfig {
+            secret: SECRET.to_owned(),
+            callback_url: "https://bot.example/eventsub".to_owned(),
+            listen_addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+            bot_user_id: None,
+        },
+    );
+
+    manager
+        .reconcile(&vec!["forsen".to_owned()].into_iter().collect())
+        .await
+        .unwrap();
+
+    let requests = mock.requests();
+    let mut deleted: Vec<&String> = requests
+        .iter()
+        .filter(|r| r.starts_with("DELETE"))
+        .collect();
+    deleted.sort();
+    assert_eq!(
+        deleted,
+        vec![
+            "DELETE /helix/eventsub/subscriptions?id=failed",
+            "DELETE /helix/eventsub/subscriptions?id=left"
+        ]
+    );
+    // stream.offline again, channel.update and channel.raid
+    assert_eq!(requests.iter().filter(|r| r.starts_with("POST")).count(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn reconcile_ignores_empty_condition_fields() {
+    let subscription = |id: &str, type_field: &str, condition: &str| {
+        format!(
+            "{{\"id\":\"{}\",\"status\":\"enabled\",\"type\":\"{}\",\"version\":\"1\",\
+            \"condition\":{},\"transport\":{{\"method\":\"webhook\",\
+            \"callback\":\"https://bot.example/eventsub\"}},\"created_at\":\"2021-11-16T10:11:12Z\",\
+            \"cost\":1}}",
+            id, type_field, condition
+        )
+    };
+    let existing = format!(
+        "{{\"data\":[{},{},{},{}],\"pagination\":{{}}}}",
+        subscription(
+            "online",
+            "stream.online",
+            "{\"broadcaster_user_id\":\"22484632\"}"
+        ),
+        subscription(
+            "offline",
+            "stream.offline",
+            "{\"broadcaster_user_id\":\"22484632\"}"
+        ),
+        subscription(
+            "update",
+            "channel.update",
+            "{\"broadcaster_user_id\":\"22484632\"}"
+        ),
+        subscription(
+            "raid",
+            "channel.raid",
+            "{\"from_broadcaster_user_id\":\"\",\"to_broadcaster_user_id\":\"22484632\"}"
+        ),
+    );
+
+    let mock = MockHttp::start(vec![
+        MockRoute::new("GET", "/oauth2/validate", 200, &validate_json(5000000)),
+        MockRoute::new(
+            "GET",
+            "/helix/users?login=forsen",
+            200,
+            &helix_user_json("22484632", "forsen"),
+        ),
+        MockRoute::new("GET", "/helix/eventsub/subscriptions", 200, &existing),
+        MockRoute::new(
+            "POST",
+            "/helix/eventsub/subscriptions",
+            202,
+            "{\"data\":[]}",
+        ),
+        MockRoute::new("DELETE", "/helix/eventsub/subscriptions", 204, ""),
+    ])
+    .await;
+    let helix = Arc::new(helix_for(&mock, Some("token")).await);
+    let users = Arc::new(UserCache::new(Arc::clone(&helix)));
+    let manager = EventSubManager::new(
+        helix,
+        users,
+        &EventSubConfig {
+            secret: SECRET.to_owned(),
+            callback_url: "https://bot.example/eventsub".to_owned(),
+            listen_addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+            bot_user_id: None,
+        },
+    );
+
+    manager
+        .reconcile(&vec!["forsen".to_owned()].into_iter().collect())
+        .await
+        .unwrap();
+
+    // every subscription is already there, so nothing is recreated
+    let requests = mock.requests();
+    assert!(
+        !requests
+            .iter()
+            .any(|r| r.starts_with("POST") || r.starts_with("DELETE")),
+        "{:?}",
+        requests
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn stream_online_events_reach_the_stream_tracker() {
+    let bot = TestBotBuilder::new(&["forsen"])
+        .eventsub(SECRET)
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users?login=forsen",
+            200,
+            &helix_user_json("22484632", "forsen"),
+        ))
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/streams?user_login=forsen",
+            200,
+            &helix_stream_json("s1", "forsen", "eventsub", "Just Chatting", 10),
+        ))
+        .start()
+        .await;
+    let receiver = bot.bot.api().eventsub_receiver().unwrap();
+    // the bot is listening once it starts reconciling its subscriptions
+    wait_until(|| {
+        bot.helix_requests()
+            .contains(&"GET /helix/eventsub/subscriptions".to_owned())
+    })
+    .await;
+
+    let online = stream_online("forsen");
+    let (status, _) = receiver.handle(&signed("c1", "notification", &online), online.as_bytes());
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    wait_until(|| bot.db.stream_sessions().len() == 1).await;
+    assert_eq!(bot.db.stream_sessions()[0].title, "eventsub");
+}
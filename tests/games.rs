@@ -0,0 +1,186 @@
+mod common;
+
+use std::time::Duration;
+
+use borrowbot::games::hangman;
+use borrowbot::types::DataDir;
+use common::{MockRoute, TestBotBuilder};
+
+// everything but kappa gets filtered out of the list
+const WORDS: &str = "kappa\nno\nHello World\nx1y2z3\n";
+
+const SEVENTV_CHANNEL: &str = "{\"emote_set\":{\"emotes\":[{\"id\":\"a\",\"name\":\"forsenE\",\
+    \"data\":{\"owner\":{\"username\":\"someone\"}}}]}}";
+
+fn with_words(channels: &[&str]) -> TestBotBuilder {
+    TestBotBuilder::new(channels)
+        .with_moderator()
+        .data_file("games/words.txt", WORDS)
+}
+
+#[tokio::test(start_paused = true)]
+async fn hangman_is_played_with_letters_and_words() {
+    let mut bot = with_words(&["forsen"]).start().await;
+
+    bot.chat("forsen", "alice", 1, "&hangman").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Hangman! Guess a letter or the whole word: _ _ _ _ _ (6 misses allowed)"
+    );
+    bot.chat("forsen", "bob", 2, "a").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "bob found 2 a: _ a _ _ a (+1, 1 this game)"
+    );
+    bot.chat("forsen", "carol", 3, "z").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "No z, 5 misses left: _ a _ _ a"
+    );
+    // letters that were guessed already and chat that isn't a guess are left alone
+    bot.chat("forsen", "carol", 3, "A").await;
+    bot.chat("forsen", "carol", 3, "what a game").await;
+    bot.chat("forsen", "bob", 2, "&hangman").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, There's already a game of hangman running in #forsen, stop it with &hangman stop"
+    );
+
+    bot.chat("forsen", "carol", 3, "KAPPA").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "carol got it: kappa (+3, 3 this game)"
+    );
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Hangman over! Scores: carol 3, bob 1"
+    );
+
+    bot.chat("forsen", "dave", 4, "&hangman").await;
+    bot.expect_message_in("forsen").await;
+    for letter in &["b", "c", "d", "e", "f"] {
+        bot.chat("forsen", "erin", 5, letter).await;
+        bot.expect_message_in("forsen").await;
+    }
+    bot.chat("forsen", "erin", 5, "g").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "No g, out of guesses. The word was kappa"
+    );
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Hangman over, nobody scored"
+    );
+
+    bot.chat("forsen", "frank", 6, "&hangman top").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@frank, Top hangman players in #forsen: 1. carol (3), 2. bob (1)"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn emotes_are_guessed_from_hints() {
+    let mut bot = with_words(&["forsen"])
+        .emote_route(MockRoute::new(
+            "GET",
+            "/7tv/users/twitch/1",
+            200,
+            SEVENTV_CHANNEL,
+        ))
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&emoteguess").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Guess the emote! It's a 7TV emote with 7 letters: _______, type it in chat"
+    );
+    bot.chat("forsen", "bob", 2, "forsen").await;
+    assert_eq!(bot.expect_message_in("forsen").await, "Hint: f______");
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Hint: f__s__E, uploaded by someone"
+    );
+    bot.chat("forsen", "bob", 2, "is it forsenE").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "bob got it: forsenE (+1, 1 this game)"
+    );
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Emote guessing over! Scores: bob 1"
+    );
+
+    bot.chat("forsen", "carol", 3, "&emoteguess").await;
+    bot.expect_message_in("forsen").await;
+    assert_eq!(bot.expect_message_in("forsen").await, "Hint: f______");
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Hint: f__s__E, uploaded by someone"
+    );
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Time's up, it was forsenE"
+    );
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Emote guessing over, nobody scored"
+    );
+
+    bot.chat("forsen", "bob", 2, "&emoteguess score").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, You have 1 emoteguess points"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn moderators_can_stop_any_game() {
+    let mut bot = with_words(&["forsen", "pajlada"]).start().await;
+
+    bot.chat("forsen", "alice", 1, "&hangman").await;
+    bot.expect_message_in("forsen").await;
+    // a game in one channel doesn't keep another from playing
+    bot.chat("pajlada", "bob", 2, "&hangman").await;
+    bot.expect_message_in("pajlada").await;
+    bot.chat("forsen", "carol", 3, "p").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "carol found 2 p: _ _ p p _ (+1, 1 this game)"
+    );
+
+    bot.chat("forsen", "dave", 4, "&hangman stop").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@dave, Sorry, only moderators can stop hangman"
+    );
+    bot.chat("forsen", "modguy", 9, "&emoteguess stop").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, There's no game of emoteguess running in #forsen"
+    );
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "modguy", 9, "&hangman stop").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Hangman over! Scores: carol 1"
+    );
+    bot.chat("forsen", "carol", 3, "k").await;
+    bot.expect_silence(Duration::from_secs(10)).await;
+
+    bot.chat("pajlada", "carol", 3, "k").await;
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "carol found 1 k: k _ _ _ _ (+1, 1 this game)"
+    );
+}
+
+#[test]
+fn hangman_falls_back_to_the_shipped_word_list() {
+    let empty = DataDir::new(std::env::temp_dir().join("borrowbot-no-data-dir"));
+    let shipped = include_str!("../data/games/words.txt");
+
+    let word = hangman::random_word(&empty).unwrap();
+    assert!(shipped.lines().any(|line| line == word), "{}", word);
+}
@@ -0,0 +1,249 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use borrowbot::api::helix::{Helix, HelixError};
+use borrowbot::api::usercache::UserCache;
+use chrono::Utc;
+use common::{app_token_json, helix_for, helix_user_json, validate_json, MockHttp, MockRoute};
+use reqwest::StatusCode;
+use tokio::time::Instant;
+
+#[tokio::test(start_paused = true)]
+async fn rejected_startup_token_is_replaced() {
+    let mock = MockHttp::start(vec![
+        MockRoute::new("GET", "/oauth2/validate", 401, "{\"status\":401}"),
+        MockRoute::new(
+            "POST",
+            "/oauth2/token",
+            200,
+            &app_token_json("fresh", 5000000),
+        ),
+    ])
+    .await;
+
+    let helix = helix_for(&mock, Some("stale")).await;
+
+    assert_eq!(helix.access_token().await, "fresh");
+    assert!(helix.token_expires_at().await.is_some());
+    assert_eq!(
+        mock.requests(),
+        vec![
+            "GET /oauth2/validate",
+            "POST /oauth2/token?client_id=test_client_id&client_secret=test_client_secret&grant_type=client_credentials"
+        ]
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn unauthorized_requests_refresh_the_token_and_retry() {
+    let mock = MockHttp::start(vec![
+        MockRoute::new("GET", "/oauth2/validate", 200, &validate_json(5000000)),
+        MockRoute::new(
+            "POST",
+            "/oauth2/token",
+            200,
+            &app_token_json("fresh", 5000000),
+        ),
+        MockRoute::new("GET", "/helix/users", 401, "{\"status\":401}")
+            .then(200, &helix_user_json("11148817", "pajlada")),
+    ])
+    .await;
+
+    let helix = helix_for(&mock, Some("revoked")).await;
+    let user = helix.get_user_by_login("pajlada").await.unwrap().unwrap();
+
+    assert_eq!(user.id, "11148817");
+    assert_eq!(helix.access_token().await, "fresh");
+    let authorizations = mock.authorizations();
+    assert_eq!(authorizations[1].as_deref(), Some("Bearer revoked"));
+    assert_eq!(authorizations[3].as_deref(), Some("Bearer fresh"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn tokens_close_to_expiring_are_refreshed_ahead_of_time() {
+    let mock = MockHttp::start(vec![
+        MockRoute::new("GET", "/oauth2/validate", 200, &validate_json(5000000))
+            .then(200, &validate_json(600)),
+        MockRoute::new(
+            "POST",
+            "/oauth2/token",
+            200,
+            &app_token_json("fresh", 5000000),
+        ),
+    ])
+    .await;
+
+    let helix = Arc::new(helix_for(&mock, Some("original")).await);
+    Helix::start_token_refresh_loop(Arc::clone(&helix));
+
+    tokio::time::sleep(Duration::from_secs(3601)).await;
+    for _ in 0..100 {
+        if helix.access_token().await == "fresh" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert_eq!(helix.access_token().await, "fresh");
+}
+
+#[tokio::test(start_paused = true)]
+async fn server_errors_are_retried() {
+    let mock = MockHttp::start(vec![
+        MockRoute::new("GET", "/oauth2/validate", 200, &validate_json(5000000)),
+        MockRoute::new("GET", "/helix/users", 503, "{\"status\":503}")
+            .then(502, "{\"status\":502}")
+            .then(200, &helix_user_json("11148817", "pajlada")),
+    ])
+    .await;
+
+    let helix = helix_for(&mock, Some("token")).await;
+    let user = helix.get_user_by_login("pajlada").await.unwrap().unwrap();
+
+    assert_eq!(user.login, "pajlada");
+    assert_eq!(
+        mock.requests()
+            .iter()
+            .filter(|r| r.starts_with("GET /helix/users"))
+            .count(),
+        3
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn persistent_failures_are_typed() {
+    let mock = MockHttp::start(vec![
+        MockRoute::new("GET", "/oauth2/validate", 200, &validate_json(5000000)),
+        MockRoute::new(
+            "POST",
+            "/oauth2/token",
+            200,
+            &app_token_json("fresh", 5000000),
+        ),
+        MockRoute::new("GET", "/helix/users?login=down", 500, "{\"status\":500}"),
+        MockRoute::new("GET", "/helix/users?login=limited", 429, "{\"status\":429}"),
+        MockRoute::new(
+            "GET",
+            "/helix/users?login=rejected",
+            401,
+            "{\"status\":401}",
+        ),
+    ])
+    .await;
+
+    let helix = helix_for(&mock, Some("token")).await;
+
+    assert!(matches!(
+        helix.get_user_by_login("down").await,
+        Err(HelixError::Status(StatusCode::INTERNAL_SERVER_ERROR))
+    ));
+    assert!(matches!(
+        helix.get_user_by_login("limited").await,
+        Err(HelixError::RateLimited(_))
+    ));
+    assert!(matches!(
+        helix.get_user_by_login("rejected").await,
+        Err(HelixError::Unauthorized)
+    ));
+    assert!(matches!(
+        helix.get_user_by_login("missing").await,
+        Err(HelixError::NotFound)
+    ));
+    // the first attempt plus three retries
+    assert_eq!(
+        mock.requests()
+            .iter()
+            .filter(|r| r.as_str() == "GET /helix/users?login=down")
+            .count(),
+        4
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn empty_rate_limit_bucket_delays_the_next_request() {
+    let reset = (Utc::now() + chrono::Duration::seconds(30)).timestamp();
+    let mock = MockHttp::start(vec![
+        MockRoute::new("GET", "/oauth2/validate", 200, &validate_json(5000000)),
+        MockRoute::new("GET", "/helix/users", 200, &helix_user_json("1", "first"))
+            .header("Ratelimit-Remaining", "0")
+            .header("Ratelimit-Reset", &reset.to_string())
+            .then(200, &helix_user_json("2", "second"))
+            .header("Ratelimit-Remaining", "799"),
+    ])
+    .await;
+
+    let helix = helix_for(&mock, Some("token")).await;
+    helix.get_user_by_login("first").await.unwrap();
+
+    let started = Instant::now();
+    let user = helix.get_user_by_login("second").await.unwrap().unwrap();
+
+    assert_eq!(user.login, "second");
+    assert!(started.elapsed() >= Duration::from_secs(25));
+}
+
+#[tokio::test(start_paused = true)]
+async fn out_of_range_rate_limit_reset_is_ignored() {
+    let mock = MockHttp::start(vec![
+        MockRoute::new("GET", "/oauth2/validate", 200, &validate_json(5000000)),
+        MockRoute::new("GET", "/helix/users", 200, &helix_user_json("1", "first"))
+            .header("Ratelimit-Remaining", "0")
+            .header("Ratelimit-Reset", &i64::MAX.to_string())
+            .then(200, &helix_user_json("2", "second")),
+    ])
+    .await;
+
+    let helix = helix_for(&mock, Some("token")).await;
+    helix.get_user_by_login("first").await.unwrap();
+    let user = helix.get_user_by_login("second").await.unwrap().unwrap();
+
+    assert_eq!(user.login, "second");
+}
+
+#[tokio::test(start_paused = true)]
+async fn user_lookups_are_split_into_batches_of_100() {
+    let mock = MockHttp::start(vec![
+        MockRoute::new("GET", "/oauth2/validate", 200, &validate_json(5000000)),
+        MockRoute::new("GET", "/helix/users", 200, &helix_user_json("1", "user0")),
+    ])
+    .await;
+
+    let helix = helix_for(&mock, Some("token")).await;
+    let logins: Vec<String> = (0..120).map(|i| format!("user{}", i)).collect();
+    let logins: Vec<&str> = logins.iter().map(|login| login.as_str()).collect();
+    let ids = ["1", "2", "3"];
+
+    let users = helix.get_users(&logins, &ids).await.unwrap();
+
+    let requests: Vec<String> = mock
+        .requests()
+        .into_iter()
+        .filter(|r| r.starts_with("GET /helix/users"))
+        .collect();
+    assert_eq!(users.len(), 2);
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].matches("login=").count(), 100);
+    assert_eq!(requests[1].matches("login=").count(), 20);
+    assert_eq!(requests[1].matches("id=").count(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn requeued_chatters_are_taken_again() {
+    let mock = MockHttp::start(vec![MockRoute::new(
+        "GET",
+        "/oauth2/validate",
+        200,
+        &validate_json(5000000),
+    )])
+    .await;
+
+    let users = UserCache::new(Arc::new(helix_for(&mock, Some("token")).await));
+    users.requeue(vec!["1".to_owned(), "2".to_owned()]);
+
+    let mut pending = users.take_pending(100);
+    pending.sort();
+    assert_eq!(pending, vec!["1", "2"]);
+    assert!(users.take_pending(100).is_empty());
+}
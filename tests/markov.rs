@@ -0,0 +1,112 @@
+mod common;
+
+use std::time::Duration;
+
+use borrowbot::markov::transitions;
+use common::{MockRoute, TestBotBuilder};
+
+#[test]
+fn messages_are_split_into_word_transitions() {
+    let steps: Vec<(String, String)> = transitions("  hello  there chat ")
+        .into_iter()
+        .map(|(prefix, next)| (prefix.replace('\u{2}', "^"), next.replace('\u{3}', "$")))
+        .collect();
+
+    assert_eq!(
+        steps,
+        vec![
+            ("^ ^".to_owned(), "hello".to_owned()),
+            ("^ hello".to_owned(), "there".to_owned()),
+            ("hello there".to_owned(), "chat".to_owned()),
+            ("there chat".to_owned(), "$".to_owned()),
+        ]
+    );
+    assert!(transitions("   ").is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn markov_repeats_what_the_channel_taught_it() {
+    let mut bot = TestBotBuilder::new(&["forsen", "pajlada"]).start().await;
+
+    bot.chat("forsen", "alice", 1, "&markov").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, I haven't learned anything from #forsen yet"
+    );
+
+    // with one message to go on there's only one thing to say
+    bot.chat("forsen", "bob", 2, "the weather is nice today")
+        .await;
+    bot.chat("pajlada", "carol", 3, "something else entirely")
+        .await;
+    bot.chat("forsen", "dave", 4, "&markov").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@dave, the weather is nice today"
+    );
+
+    bot.chat("forsen", "dave", 4, "&markov nice").await;
+    bot.expect_silence(Duration::from_secs(5)).await;
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    bot.chat("forsen", "dave", 4, "&markov nice").await;
+    assert_eq!(bot.expect_message_in("forsen").await, "@dave, nice today");
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    bot.chat("forsen", "dave", 4, "&markov sunny").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@dave, I've never seen #forsen say sunny"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn users_can_opt_out_of_being_learned_from() {
+    let mut bot = TestBotBuilder::new(&["forsen", "pajlada"]).start().await;
+
+    bot.chat("pajlada", "bob", 2, "bob says this everywhere")
+        .await;
+    bot.chat("forsen", "alice", 1, "&markov @bob").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, bob says this everywhere"
+    );
+
+    bot.chat("forsen", "bob", 2, "&markov optout").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, I won't learn from your messages anymore"
+    );
+    bot.chat("forsen", "bob", 2, "please forget me").await;
+
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    bot.chat("forsen", "alice", 1, "&markov @bob").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, bob opted out of markov"
+    );
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    bot.chat("forsen", "alice", 1, "&markov").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, I haven't learned anything from #forsen yet"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn markov_output_goes_through_the_banphrase_check() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .banphrase_route(MockRoute::new(
+            "POST",
+            "/banphrases/test",
+            200,
+            "{\"banned\":true,\"input_message\":\"\",\"banphrase_data\":null}",
+        ))
+        .start()
+        .await;
+
+    bot.chat("forsen", "bob", 2, "something banned").await;
+    bot.chat("forsen", "alice", 1, "&markov").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Uh oh, the anticipated response contained a banphrase monkaS"
+    );
+}
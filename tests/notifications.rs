@@ -0,0 +1,100 @@
+mod common;
+
+use std::time::Duration;
+
+use borrowbot::notifications::batch_mentions;
+use common::{helix_stream_json, MockRoute, TestBotBuilder};
+
+#[test]
+fn mentions_are_packed_into_as_few_messages_as_possible() {
+    let logins: Vec<String> = (0..100).map(|i| format!("chatter{:03}", i)).collect();
+
+    let messages = batch_mentions("forsen is live!", &logins);
+
+    // 15 characters of header, then 11 for each mention with its space
+    assert_eq!(messages.len(), 3);
+    assert!(messages[0].starts_with("forsen is live! @chatter000 @chatter001"));
+    assert!(messages.iter().all(|m| m.chars().count() <= 490));
+    assert_eq!(
+        messages
+            .iter()
+            .map(|m| m.matches('@').count())
+            .sum::<usize>(),
+        100
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn subscribers_are_pinged_on_stream_changes() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .helix_route(
+            MockRoute::new("GET", "/helix/streams", 200, "{\"data\":[]}")
+                .then(
+                    200,
+                    &helix_stream_json("s1", "forsen", "first", "Just Chatting", 60),
+                )
+                .then(
+                    200,
+                    &helix_stream_json("s1", "forsen", "second", "Just Chatting", 120),
+                ),
+        )
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&notify live").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, I'll ping you when forsen goes live"
+    );
+    bot.chat("forsen", "bob", 2, "&notify live").await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "carol", 3, "&notify title").await;
+    bot.expect_message_in("forsen").await;
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "carol", 3, "&notify title").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, You're already getting those pings"
+    );
+
+    let (_, went_live) = bot
+        .next_message_within(Duration::from_secs(180))
+        .await
+        .unwrap();
+    assert_eq!(
+        went_live,
+        "forsen is live! first [Just Chatting] @alice @bob"
+    );
+
+    let (_, title_change) = bot
+        .next_message_within(Duration::from_secs(120))
+        .await
+        .unwrap();
+    assert_eq!(title_change, "forsen changed the title: second @carol");
+}
+
+#[tokio::test(start_paused = true)]
+async fn unnotify_removes_subscriptions() {
+    let mut bot = TestBotBuilder::new(&["forsen"]).start().await;
+
+    bot.chat("forsen", "alice", 1, "&unnotify").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, You weren't getting any of those pings"
+    );
+
+    bot.chat("forsen", "bob", 2, "&notify title").await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "bob", 2, "&unnotify live").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, You weren't getting any of those pings"
+    );
+
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "bob", 2, "&unnotify title").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, Okay, no more pings"
+    );
+}
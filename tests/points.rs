@@ -0,0 +1,251 @@
+mod common;
+
+use std::time::Duration;
+
+use borrowbot::database::PointsRepository;
+use common::TestBotBuilder;
+
+#[tokio::test(start_paused = true)]
+async fn chatting_earns_points_at_a_capped_rate() {
+    let mut bot = TestBotBuilder::new(&["forsen", "pajlada"]).start().await;
+
+    bot.chat("forsen", "alice", 1, "hello").await;
+    bot.chat("forsen", "alice", 1, "spam").await;
+    bot.chat("forsen", "alice", 1, "spam").await;
+    bot.chat("pajlada", "alice", 1, "hi over here too").await;
+    bot.chat("forsen", "alice", 1, "&points").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, You have 10 points"
+    );
+
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    bot.chat("forsen", "alice", 1, "a minute later").await;
+    bot.chat("forsen", "bob", 2, "hey").await;
+    bot.chat("forsen", "bob", 2, "&points alice").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, alice has 20 points"
+    );
+    bot.chat("forsen", "carol", 3, "&toppoints").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, Top points in #forsen: 1. alice (20), 2. bob (10)"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn points_can_be_given_away() {
+    let builder = TestBotBuilder::new(&["forsen"]);
+    builder
+        .db()
+        .add_points("forsen", 1, "alice", 40)
+        .await
+        .unwrap();
+    let mut bot = builder.start().await;
+    bot.chat("forsen", "bob", 2, "hi").await;
+
+    bot.chat("forsen", "alice", 1, "&give bob 100").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, You only have 40 points"
+    );
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "alice", 1, "&give alice 10").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, You can't give points to yourself"
+    );
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "alice", 1, "&give @Bob 30").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Gave 30 points to bob, you have 10 left"
+    );
+    bot.chat("forsen", "bob", 2, "&give alice all").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, Gave 40 points to alice, you have 0 left"
+    );
+    bot.chat("forsen", "bob", 2, "&points").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, You have 0 points"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn bets_pay_out_or_take_the_stake() {
+    let builder = TestBotBuilder::new(&["forsen"]);
+    builder
+        .db()
+        .add_points("forsen", 1, "alice", 100)
+        .await
+        .unwrap();
+    builder
+        .db()
+        .add_points("forsen", 2, "bob", 100)
+        .await
+        .unwrap();
+    let mut bot = builder.start().await;
+
+    bot.chat("forsen", "alice", 1, "&roulette 40").await;
+    let response = bot.expect_message_in("forsen").await;
+    let balance = match response.as_str() {
+        "@alice, You won 40 points and now have 140" => 140,
+        "@alice, You lost 40 points and now have 60" => 60,
+        _ => panic!("unexpected roulette result: {}", response),
+    };
+    assert_eq!(bot.db.get_points("forsen", 1).await.unwrap(), balance);
+
+    bot.chat("forsen", "bob", 2, "&slots 10").await;
+    let response = bot.expect_message_in("forsen").await;
+    let (reels, outcome) = response
+        .strip_prefix("@bob, [ ")
+        .and_then(|rest| rest.split_once(" ] "))
+        .unwrap();
+    let reels: Vec<&str> = reels.split(" | ").collect();
+    let expected = if reels[0] == reels[1] && reels[1] == reels[2] {
+        "Jackpot! You won 90 points and now have 190"
+    } else if reels[0] == reels[1] || reels[1] == reels[2] || reels[0] == reels[2] {
+        "You got your 10 points back and now have 100"
+    } else {
+        "You lost 10 points and now have 90"
+    };
+    assert_eq!(outcome, expected);
+
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "carol", 3, "&slots 10").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, You don't have any points yet, chat a bit to earn some"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn simultaneous_spending_cant_overdraw() {
+    let builder = TestBotBuilder::new(&["forsen"]);
+    builder
+        .db()
+        .add_points("forsen", 1, "alice", 100)
+        .await
+        .unwrap();
+    let mut bot = builder.start().await;
+    bot.chat("forsen", "bob", 2, "hi").await;
+
+    bot.chat("forsen", "alice", 1, "&give bob 100").await;
+    bot.chat("forsen", "alice", 1, "&roulette 100").await;
+    bot.chat("forsen", "alice", 1, "&slots 100").await;
+
+    let mut spent = 0;
+    for _ in 0..3 {
+        let response = bot.expect_message_in("forsen").await;
+        if response.contains("Gave")
+            || response.contains("You won")
+            || response.contains("You lost")
+            || response.contains("You got your")
+        {
+            spent += 1;
+        }
+    }
+    assert_eq!(spent, 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn duels_move_the_bet_to_the_winner() {
+    let builder = TestBotBuilder::new(&["forsen"]);
+    builder
+        .db()
+        .add_points("forsen", 1, "alice", 100)
+        .await
+        .unwrap();
+    builder
+        .db()
+        .add_points("forsen", 2, "bob", 50)
+        .await
+        .unwrap();
+    let mut bot = builder.start().await;
+    bot.chat("forsen", "bob", 2, "hi").await;
+
+    bot.chat("forsen", "bob", 2, "&duel accept").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, You don't have a duel waiting"
+    );
+    bot.chat("forsen", "alice", 1, "&duel bob 30").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, You challenged bob to a duel for 30 points, bob has 1m to answer with \
+        &duel accept or &duel decline"
+    );
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "bob", 2, "&duel accept").await;
+    let response = bot.expect_message_in("forsen").await;
+    let (alice, bob) = match response.as_str() {
+        "@bob, alice won the duel against bob and takes 30 points" => (130, 30),
+        "@bob, bob won the duel against alice and takes 30 points" => (70, 90),
+        _ => panic!("unexpected duel result: {}", response),
+    };
+    assert_eq!(bot.db.get_points("forsen", 1).await.unwrap(), alice);
+    assert_eq!(bot.db.get_points("forsen", 2).await.unwrap(), bob);
+
+    // challenges run out
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "alice", 1, "&duel bob 10").await;
+    bot.expect_message_in("forsen").await;
+    tokio::time::sleep(Duration::from_secs(61)).await;
+    bot.chat("forsen", "bob", 2, "&duel accept").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, You don't have a duel waiting"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn moderators_can_turn_off_and_reset_the_economy() {
+    let builder = TestBotBuilder::new(&["forsen"]);
+    builder.db().add_user(9, "modguy", 1);
+    builder
+        .db()
+        .add_points("forsen", 1, "alice", 100)
+        .await
+        .unwrap();
+    builder
+        .db()
+        .add_points("forsen", 2, "bob", 50)
+        .await
+        .unwrap();
+    let mut bot = builder.start().await;
+    bot.chat("forsen", "bob", 2, "hi").await;
+
+    bot.chat("forsen", "modguy", 9, "&resetpoints bob").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Reset bob's points in #forsen"
+    );
+    bot.chat("forsen", "modguy", 9, "&disable points").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Disabled &points in #forsen"
+    );
+
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    bot.chat("forsen", "bob", 2, "no more earning").await;
+    bot.chat("forsen", "alice", 1, "&roulette 10").await;
+    bot.chat("forsen", "bob", 2, "&points").await;
+    bot.expect_silence(Duration::from_secs(10)).await;
+    assert_eq!(bot.db.get_points("forsen", 2).await.unwrap(), 0);
+
+    bot.chat("forsen", "modguy", 9, "&enable points").await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "modguy", 9, "&resetpoints all").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Reset 1 balances in #forsen"
+    );
+    bot.chat("forsen", "alice", 1, "&toppoints").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Nobody in #forsen has any points yet"
+    );
+}
@@ -0,0 +1,291 @@
+mod common;
+
+use std::time::Duration;
+
+use borrowbot::database::PointsRepository;
+use borrowbot::polls::{Bet, Poll, Polls};
+use common::TestBotBuilder;
+
+#[tokio::test(start_paused = true)]
+async fn chat_votes_by_typing_a_number() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .with_moderator()
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&poll \"Best game?\" a | b")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Sorry, only moderators have access to the poll command"
+    );
+    bot.chat(
+        "forsen",
+        "modguy",
+        9,
+        "&poll \"Best game?\" Minecraft | Terraria | Factorio --duration 1m",
+    )
+    .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Poll started: Best game? | 1) Minecraft 2) Terraria 3) Factorio | type a \
+         number to vote, ends in 1m"
+    );
+
+    bot.chat("forsen", "alice", 1, "1").await;
+    bot.chat("forsen", "bob", 2, "3").await;
+    bot.chat("forsen", "carol", 3, " 3 ").await;
+    // only the first vote counts, and only for options that exist
+    bot.chat("forsen", "alice", 1, "2").await;
+    bot.chat("forsen", "dave", 4, "7").await;
+    bot.chat("forsen", "erin", 5, "3 is the best").await;
+
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Poll: Best game? | 1) Minecraft: 1 (33%), 2) Terraria: 0 (0%), 3) Factorio: 2 (66%) \
+         | 30s left"
+    );
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Poll over: Best game? | 1) Minecraft: 1 (33%), 2) Terraria: 0 (0%), 3) Factorio: 2 \
+         (66%) | Factorio wins"
+    );
+
+    bot.chat("forsen", "bob", 2, "1").await;
+    bot.expect_silence(Duration::from_secs(60)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn moderators_can_end_or_cancel_a_poll() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .with_moderator()
+        .start()
+        .await;
+
+    bot.chat("forsen", "modguy", 9, "&poll \"Pizza?\" yes")
+        .await;
+    assert!(bot
+        .expect_message_in("forsen")
+        .await
+        .starts_with("@modguy, Usage: &poll"));
+    bot.chat(
+        "forsen",
+        "modguy",
+        9,
+        "&poll \"Pizza?\" yes | no --duration 2h",
+    )
+    .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Polls can run between 10s and 30m"
+    );
+    bot.chat(
+        "forsen",
+        "modguy",
+        9,
+        "&poll \"Pizza?\" yes | no --duration 10m",
+    )
+    .await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "modguy", 9, "&poll \"Pasta?\" yes | no")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, There's already a poll running in #forsen, end it with &poll end"
+    );
+
+    bot.chat("forsen", "alice", 1, "2").await;
+    bot.chat("forsen", "bob", 2, "1").await;
+    bot.chat("forsen", "modguy", 9, "&poll end").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Poll over: Pizza? | 1) yes: 1 (50%), 2) no: 1 (50%) | it's a tie between yes and no"
+    );
+
+    bot.chat("forsen", "modguy", 9, "&poll \"Pasta?\" yes | no")
+        .await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "modguy", 9, "&poll cancel").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Cancelled the poll, bets were refunded"
+    );
+    bot.chat("forsen", "modguy", 9, "&poll cancel").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, There's no poll running in #forsen"
+    );
+    bot.expect_silence(Duration::from_secs(120)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn winning_bets_split_the_losing_ones() {
+    let builder = TestBotBuilder::new(&["forsen"]).with_moderator();
+    for (uid, login, points) in &[(1, "alice", 100), (2, "bob", 50), (3, "carol", 30)] {
+        builder
+            .db()
+            .add_points("forsen", *uid, login, *points)
+            .await
+            .unwrap();
+    }
+    let mut bot = builder.start().await;
+
+    bot.chat("forsen", "alice", 1, "&bet 1 10").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, There's no poll running in #forsen"
+    );
+    bot.chat(
+        "forsen",
+        "modguy",
+        9,
+        "&poll \"Will he win?\" yes | no --duration 1m --bets",
+    )
+    .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Poll started: Will he win? | 1) yes 2) no | type a number to vote, ends in \
+         1m, bet points with &bet <option> <amount>"
+    );
+
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "alice", 1, "&bet 1 60").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, You bet 60 points on yes"
+    );
+    bot.chat("forsen", "bob", 2, "&bet 3 50").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, Pick an option between 1 and 2"
+    );
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "bob", 2, "&bet 2 50").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, You bet 50 points on no"
+    );
+    bot.chat("forsen", "carol", 3, "&bet 2 20").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, You bet 20 points on no"
+    );
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("forsen", "carol", 3, "&bet 1 5").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, You already placed a bet on this poll"
+    );
+
+    // votes are chat, so they earn alice and bob 10 points
+    bot.chat("forsen", "alice", 1, "1").await;
+    bot.chat("forsen", "bob", 2, "2").await;
+    bot.chat("forsen", "dave", 4, "1").await;
+    assert!(bot
+        .expect_message_in("forsen")
+        .await
+        .starts_with("Poll: Will he win?"));
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Poll over: Will he win? | 1) yes: 2 (66%), 2) no: 1 (33%) | yes wins"
+    );
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Bets: 70 points from 2 losing bets went to 1 winners"
+    );
+
+    assert_eq!(bot.db.get_points("forsen", 1).await.unwrap(), 180);
+    assert_eq!(bot.db.get_points("forsen", 2).await.unwrap(), 10);
+    assert_eq!(bot.db.get_points("forsen", 3).await.unwrap(), 10);
+}
+
+#[tokio::test(start_paused = true)]
+async fn bets_are_held_until_the_poll_is_settled() {
+    let builder = TestBotBuilder::new(&["forsen"]).with_moderator();
+    builder
+        .db()
+        .add_points("forsen", 1, "alice", 100)
+        .await
+        .unwrap();
+    let mut bot = builder.start().await;
+
+    bot.chat("forsen", "modguy", 9, "&poll \"Pizza?\" yes | no --bets")
+        .await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "alice", 1, "&bet 2 40").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, You bet 40 points on no"
+    );
+    assert_eq!(bot.db.get_points("forsen", 1).await.unwrap(), 60);
+
+    bot.chat("forsen", "modguy", 9, "&poll cancel").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Cancelled the poll, bets were refunded"
+    );
+    assert_eq!(bot.db.get_points("forsen", 1).await.unwrap(), 100);
+}
+
+#[tokio::test(start_paused = true)]
+async fn bets_are_refused_once_the_poll_ended() {
+    let polls = Polls::new();
+    polls.start_poll(
+        "forsen",
+        Poll::new(
+            "Pizza?".to_owned(),
+            vec!["yes".to_owned(), "no".to_owned()],
+            Duration::from_secs(10),
+            true,
+        ),
+    );
+    let bet = Bet {
+        login: "alice".to_owned(),
+        option: 0,
+        amount: 10,
+    };
+    assert!(polls.check_bet("forsen", 1, &bet).is_ok());
+
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    assert_eq!(
+        polls.bet("forsen", 1, bet),
+        Err("There's no poll running in #forsen".to_owned())
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn bets_are_refunded_when_nobody_bet_on_the_winner() {
+    let builder = TestBotBuilder::new(&["forsen"]).with_moderator();
+    builder
+        .db()
+        .add_points("forsen", 1, "alice", 100)
+        .await
+        .unwrap();
+    let mut bot = builder.start().await;
+
+    bot.chat(
+        "forsen",
+        "modguy",
+        9,
+        "&poll \"Pizza?\" yes | no --duration 1m --bets",
+    )
+    .await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "alice", 1, "&bet 2 40").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, You bet 40 points on no"
+    );
+    bot.chat("forsen", "bob", 2, "1").await;
+
+    assert!(bot
+        .expect_message_in("forsen")
+        .await
+        .starts_with("Poll: Pizza?"));
+    assert!(bot.expect_message_in("forsen").await.ends_with("yes wins"));
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Nobody bet on the winner, all bets were refunded"
+    );
+    assert_eq!(bot.db.get_points("forsen", 1).await.unwrap(), 100);
+}
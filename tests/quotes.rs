@@ -0,0 +1,176 @@
+mod common;
+
+use common::TestBotBuilder;
+
+#[tokio::test(start_paused = true)]
+async fn quotes_can_be_added_found_and_deleted() {
+    let mut bot = TestBotBuilder::new(&["forsen", "pajlada"])
+        .with_moderator()
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&quote").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, #forsen has no quotes yet"
+    );
+    bot.chat("forsen", "alice", 1, "&addquote @Bob I never lose")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Added quote #1"
+    );
+
+    // quoting what was just said in chat
+    bot.chat("forsen", "carol", 3, "this is my best take").await;
+    bot.chat("forsen", "carol", 3, "and this one is worse")
+        .await;
+    bot.chat("forsen", "bob", 2, "&addquote carol ~2").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, Added quote #2"
+    );
+    bot.chat("forsen", "carol", 3, "&addquote carol ~").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, Added quote #3"
+    );
+    bot.chat("forsen", "dave", 4, "&addquote dave ~").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@dave, Sorry, I didn't find that message from dave in #forsen"
+    );
+
+    let date = chrono::Utc::now().format("%Y-%m-%d");
+    bot.chat("forsen", "eve", 5, "&quote 2").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        format!("@eve, #2 \"this is my best take\" - carol, {}", date)
+    );
+    bot.chat("forsen", "frank", 6, "&quote bob").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        format!("@frank, #1 \"I never lose\" - bob, {}", date)
+    );
+    bot.chat("forsen", "grace", 7, "&quote one is WORSE").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        format!("@grace, #3 \"and this one is worse\" - carol, {}", date)
+    );
+    bot.chat("pajlada", "eve", 5, "&quote 1").await;
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "@eve, There's no quote #1 in #pajlada"
+    );
+    // every channel counts its own quotes
+    bot.chat("pajlada", "eve", 5, "&addquote eve hello").await;
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "@eve, Added quote #1"
+    );
+
+    bot.chat("forsen", "alice", 1, "&delquote 1").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Sorry, only moderators have access to the delquote command"
+    );
+    bot.chat("forsen", "modguy", 9, "&delquote #1").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Deleted quote #1"
+    );
+    bot.chat("forsen", "modguy", 9, "&delquote 1").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, There's no quote #1 in #forsen"
+    );
+    bot.chat("forsen", "frank", 6, "&quote bob").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@frank, No quotes in #forsen are from or mention bob"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn quotes_move_between_channels_as_json() {
+    let builder = TestBotBuilder::new(&["xqc", "nymn"])
+        .data_file(
+            "quotes/otherbot.json",
+            r#"[
+                {"id": 7, "quote": "first!", "user": "Alice", "date": "2020-01-02T03:04:05Z"},
+                {"text": "no author here"},
+                {"text": "   "}
+            ]"#,
+        )
+        .data_file("quotes/broken.json", "{\"not\": \"a list\"}");
+    builder.db().add_user(9, "owner", 2);
+    let mut bot = builder.start().await;
+
+    bot.chat("xqc", "owner", 9, "&quotes import ../secrets")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("xqc").await,
+        "@owner, Sorry, that's not a file name I can use"
+    );
+    bot.chat("xqc", "owner", 9, "&quotes import missing").await;
+    assert_eq!(
+        bot.expect_message_in("xqc").await,
+        "@owner, Sorry, I couldn't read quotes/missing.json"
+    );
+    bot.chat("xqc", "owner", 9, "&quotes import broken").await;
+    assert_eq!(
+        bot.expect_message_in("xqc").await,
+        "@owner, Sorry, quotes/broken.json isn't a list of quotes"
+    );
+    bot.chat("xqc", "owner", 9, "&quotes import otherbot.json")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("xqc").await,
+        "@owner, Imported 2 quotes into #xqc"
+    );
+    // numbers from the file are kept, quotes without one go after them
+    bot.chat("xqc", "owner", 9, "&quote alice").await;
+    assert_eq!(
+        bot.expect_message_in("xqc").await,
+        "@owner, #7 \"first!\" - alice, 2020-01-02"
+    );
+    bot.chat("xqc", "owner", 9, "&quote 8").await;
+    let response = bot.expect_message_in("xqc").await;
+    assert!(
+        response.starts_with("@owner, #8 \"no author here\" - unknown, "),
+        "{}",
+        response
+    );
+
+    bot.chat("xqc", "owner", 9, "&quotes export").await;
+    assert_eq!(
+        bot.expect_message_in("xqc").await,
+        "@owner, Exported 2 quotes from #xqc to quotes/xqc.json"
+    );
+    bot.chat("nymn", "owner", 9, "&quotes import xqc").await;
+    assert_eq!(
+        bot.expect_message_in("nymn").await,
+        "@owner, Imported 2 quotes into #nymn"
+    );
+    bot.chat("nymn", "owner", 9, "&quote author").await;
+    let response = bot.expect_message_in("nymn").await;
+    assert!(
+        response.starts_with("@owner, #8 \"no author here\" - unknown, "),
+        "{}",
+        response
+    );
+
+    // importing the same file again can't reuse the numbers
+    bot.chat("nymn", "owner", 9, "&quotes import xqc").await;
+    assert_eq!(
+        bot.expect_message_in("nymn").await,
+        "@owner, Imported 2 quotes into #nymn"
+    );
+    bot.chat("nymn", "owner", 9, "&quote 10").await;
+    let response = bot.expect_message_in("nymn").await;
+    assert!(
+        response.starts_with("@owner, #10 \"no author here\" - unknown, "),
+        "{}",
+        response
+    );
+}
@@ -0,0 +1,279 @@
+mod common;
+
+use std::time::Duration;
+
+use borrowbot::database::ReminderRepository;
+use borrowbot::reminders::{parse_duration, Reminder};
+use chrono::Utc;
+use common::{helix_user_json, wait_until, MockRoute, TestBotBuilder};
+
+fn reminder(author: (i32, &str), target: (i32, &str), channel: &str, message: &str) -> Reminder {
+    Reminder {
+        id: 0,
+        author_uid: author.0,
+        author_login: author.1.to_owned(),
+        target_uid: target.0,
+        target_login: target.1.to_owned(),
+        channel: channel.to_owned(),
+        message: message.to_owned(),
+        created_at: Utc::now(),
+        due_at: None,
+    }
+}
+
+#[test]
+fn durations_are_parsed_from_chat() {
+    assert_eq!(parse_duration("90s"), Some(chrono::Duration::seconds(90)));
+    assert_eq!(parse_duration("2h"), Some(chrono::Duration::hours(2)));
+    assert_eq!(parse_duration("1d12h"), Some(chrono::Duration::hours(36)));
+    assert_eq!(parse_duration("1W"), Some(chrono::Duration::weeks(1)));
+    assert_eq!(parse_duration("0m"), None);
+    assert_eq!(parse_duration("12"), None);
+    assert_eq!(parse_duration("h"), None);
+    assert_eq!(parse_duration("tomorrow"), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn reminders_wait_for_the_target_to_chat_anywhere() {
+    let mut bot = TestBotBuilder::new(&["forsen", "pajlada"])
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users?login=nobody",
+            200,
+            "{\"data\":[]}",
+        ))
+        .start()
+        .await;
+
+    bot.chat("forsen", "bob", 2, "hi").await;
+    bot.chat("forsen", "alice", 1, "&remind @bob hello there")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, I'll remind bob when they next chat (id 1)"
+    );
+    bot.chat("forsen", "carol", 3, "&remind me to stretch")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, Usage: &remind <user|me> [in <time>] <message>"
+    );
+    bot.chat("forsen", "dave", 4, "&remind nobody hi").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@dave, Sorry, I couldn't find user nobody"
+    );
+
+    bot.chat("pajlada", "bob", 2, "good morning").await;
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "@bob, reminder from alice (0s ago): hello there"
+    );
+
+    // delivered reminders are gone
+    bot.chat("pajlada", "bob", 2, "anyone here?").await;
+    bot.expect_silence(Duration::from_secs(10)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn timed_reminders_survive_a_restart() {
+    let builder = TestBotBuilder::new(&["forsen"]);
+
+    // due while the bot was down
+    let mut missed = reminder((3, "carol"), (4, "dave"), "forsen", "drink water");
+    missed.created_at = Utc::now() - chrono::Duration::hours(3);
+    missed.due_at = Some(Utc::now() - chrono::Duration::hours(1));
+    builder.db().add_reminder(&missed).await.unwrap();
+
+    let mut bot = builder.start().await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@dave, reminder from carol (2h ago): drink water"
+    );
+
+    bot.chat("forsen", "alice", 1, "&remind me in 2h stretch")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, I'll remind you in 2h (id 2)"
+    );
+    bot.chat("forsen", "bob", 2, "&remind alice in 30m check the oven")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, I'll remind alice in 30m (id 3)"
+    );
+
+    assert_eq!(
+        bot.next_message_within(Duration::from_secs(3600)).await,
+        Some((
+            "forsen".to_owned(),
+            "@alice, reminder from bob (30m ago): check the oven".to_owned()
+        ))
+    );
+    // the scheduler counts from the wall clock, which stands still while the test skips ahead
+    assert_eq!(
+        bot.next_message_within(Duration::from_secs(3 * 3600)).await,
+        Some((
+            "forsen".to_owned(),
+            "@alice, reminder from 2h ago: stretch".to_owned()
+        ))
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn reminders_are_limited_and_can_be_cancelled() {
+    let builder = TestBotBuilder::new(&["forsen"]);
+    // superusers skip the cooldown
+    builder.db().add_user(1, "alice", 2);
+    let mut bot = builder.start().await;
+
+    for i in 1..=10 {
+        bot.chat(
+            "forsen",
+            "alice",
+            1,
+            &format!("&remind me in {}h thing {}", i, i),
+        )
+        .await;
+        assert_eq!(
+            bot.expect_message_in("forsen").await,
+            format!("@alice, I'll remind you in {}h (id {})", i, i)
+        );
+    }
+    bot.chat("forsen", "alice", 1, "&remind me in 1d one more")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, You already have 10 reminders waiting, cancel one with &unremind <id>"
+    );
+
+    bot.chat("forsen", "bob", 2, "&unremind 1").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, You don't have a reminder with id 1"
+    );
+    bot.chat("forsen", "alice", 1, "&unremind 1").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Cancelled reminder 1"
+    );
+
+    // the cancelled one never goes out, the next one still does
+    assert_eq!(
+        bot.next_message_within(Duration::from_secs(3 * 3600)).await,
+        Some((
+            "forsen".to_owned(),
+            "@alice, reminder from 2h ago: thing 2".to_owned()
+        ))
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn reminders_for_channels_the_bot_left_are_whispered() {
+    let builder = TestBotBuilder::new(&["forsen"])
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users",
+            200,
+            &helix_user_json("999", "borrowbot"),
+        ))
+        .helix_route(MockRoute::new("POST", "/helix/whispers", 204, ""));
+
+    let mut left = reminder((3, "carol"), (4, "dave"), "pajlada", "drink water");
+    left.due_at = Some(Utc::now());
+    builder.db().add_reminder(&left).await.unwrap();
+
+    let bot = builder.start().await;
+    wait_until(|| {
+        bot.helix_requests()
+            .contains(&"POST /helix/whispers?from_user_id=999&to_user_id=4".to_owned())
+    })
+    .await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn reminders_are_kept_until_they_are_delivered() {
+    let builder = TestBotBuilder::new(&["forsen"])
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users",
+            200,
+            &helix_user_json("999", "borrowbot"),
+        ))
+        .helix_route(
+            MockRoute::new("POST", "/helix/whispers", 400, "{\"status\":400}").then(204, ""),
+        );
+
+    let mut left = reminder((3, "carol"), (4, "dave"), "pajlada", "drink water");
+    left.due_at = Some(Utc::now());
+    builder.db().add_reminder(&left).await.unwrap();
+
+    let bot = builder.start().await;
+    let whispers = || {
+        bot.helix_requests()
+            .iter()
+            .filter(|r| r.starts_with("POST /helix/whispers"))
+            .count()
+    };
+    wait_until(|| whispers() == 1).await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(bot.db.get_due_reminders(Utc::now()).await.unwrap().len(), 1);
+
+    // the scheduler tries again after its retry interval
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    wait_until(|| whispers() == 2).await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert!(bot
+        .db
+        .get_due_reminders(Utc::now())
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn undeliverable_reminders_are_dropped_eventually() {
+    let builder = TestBotBuilder::new(&["forsen"])
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users",
+            200,
+            &helix_user_json("999", "borrowbot"),
+        ))
+        .helix_route(MockRoute::new(
+            "POST",
+            "/helix/whispers",
+            400,
+            "{\"status\":400}",
+        ));
+
+    let mut left = reminder((3, "carol"), (4, "dave"), "pajlada", "drink water");
+    left.due_at = Some(Utc::now());
+    builder.db().add_reminder(&left).await.unwrap();
+
+    let bot = builder.start().await;
+    let whispers = || {
+        bot.helix_requests()
+            .iter()
+            .filter(|r| r.starts_with("POST /helix/whispers"))
+            .count()
+    };
+    for attempt in 1..10 {
+        wait_until(|| whispers() == attempt).await;
+        tokio::time::sleep(Duration::from_secs(61)).await;
+    }
+
+    wait_until(|| whispers() == 10).await;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert!(bot
+        .db
+        .get_due_reminders(Utc::now())
+        .await
+        .unwrap()
+        .is_empty());
+
+    // nothing is left to retry
+    tokio::time::sleep(Duration::from_secs(120)).await;
+    assert_eq!(whispers(), 10);
+}
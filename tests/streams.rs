@@ -0,0 +1,138 @@
+mod common;
+
+use std::time::Duration;
+
+use borrowbot::database::StreamRepository;
+use borrowbot::streams::StreamEvent;
+use chrono::Utc;
+use common::{helix_stream_json, helix_user_json, wait_until, MockRoute, TestBotBuilder};
+
+#[tokio::test(start_paused = true)]
+async fn poller_records_stream_sessions() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .helix_route(
+            MockRoute::new(
+                "GET",
+                "/helix/streams",
+                200,
+                &helix_stream_json("s1", "forsen", "first", "Just Chatting", 3600),
+            )
+            .then(
+                200,
+                &helix_stream_json("s1", "forsen", "second", "Just Chatting", 3660),
+            )
+            .then(200, "{\"data\":[]}"),
+        )
+        .start()
+        .await;
+    let mut events = bot.bot.streams().subscribe();
+
+    // the first poll happens as soon as the bot starts
+    wait_until(|| bot.db.stream_sessions().len() == 1).await;
+    bot.chat("forsen", "alice", 1, "&uptime").await;
+    let response = bot.expect_message_in("forsen").await;
+    assert!(
+        response.starts_with("@alice, forsen has been live for 1h"),
+        "{}",
+        response
+    );
+    bot.chat("forsen", "alice", 1, "&title").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, forsen's title: first"
+    );
+
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    wait_until(|| bot.db.stream_change_count() == 1).await;
+    assert_eq!(bot.db.stream_sessions()[0].title, "second");
+
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    wait_until(|| bot.db.stream_sessions()[0].ended_at.is_some()).await;
+    assert_eq!(bot.db.stream_sessions().len(), 1);
+
+    bot.chat("forsen", "bob", 2, "&lastseen live forsen").await;
+    let response = bot.expect_message_in("forsen").await;
+    assert!(
+        response.starts_with("@bob, forsen was last live") && response.ends_with("for 1h"),
+        "{}",
+        response
+    );
+
+    assert!(matches!(
+        events.recv().await,
+        Ok(StreamEvent::Online { .. })
+    ));
+    assert_eq!(
+        events.recv().await.unwrap(),
+        StreamEvent::TitleChanged {
+            channel: "forsen".to_owned(),
+            title: "second".to_owned()
+        }
+    );
+    assert!(matches!(
+        events.recv().await,
+        Ok(StreamEvent::Offline { .. })
+    ));
+}
+
+#[tokio::test(start_paused = true)]
+async fn channels_that_are_not_joined_are_looked_up_on_helix() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/streams?user_login=xqc",
+            200,
+            &helix_stream_json("s2", "xqc", "react", "Just Chatting", 120),
+        ))
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/users?login=pajlada",
+            200,
+            &helix_user_json("11148817", "pajlada"),
+        ))
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/channels?broadcaster_id=11148817",
+            200,
+            "{\"data\":[{\"broadcaster_id\":\"11148817\",\"broadcaster_login\":\"pajlada\",\
+            \"broadcaster_name\":\"pajlada\",\"game_id\":\"1\",\"game_name\":\"Chess\",\
+            \"title\":\"chess\"}]}",
+        ))
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&uptime xqc").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, xqc has been live for 2m"
+    );
+
+    bot.chat("forsen", "alice", 1, "&game pajlada").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, pajlada is offline, their category is Chess"
+    );
+
+    bot.chat("forsen", "bob", 2, "&lastseen live pajlada").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@bob, I haven't seen pajlada live yet"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn sessions_left_open_are_closed_once_the_channel_is_offline() {
+    let builder = TestBotBuilder::new(&["forsen"]);
+    builder
+        .db()
+        .start_stream_session("forsen", "s0", Utc::now(), "before restart", "Chess")
+        .await
+        .unwrap();
+    let bot = builder.start().await;
+
+    assert!(bot.bot.streams().live_session("forsen").await.is_some());
+    tokio::time::sleep(Duration::from_secs(61)).await;
+    wait_until(|| bot.db.stream_sessions()[0].ended_at.is_some()).await;
+
+    assert!(bot.bot.streams().live_session("forsen").await.is_none());
+}
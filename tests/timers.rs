@@ -0,0 +1,182 @@
+mod common;
+
+use std::time::Duration;
+
+use borrowbot::database::TimerRepository;
+use borrowbot::timers::{Timer, TimerCondition, TimerSchedule};
+use chrono::Utc;
+use common::{helix_stream_json, MockRoute, TestBotBuilder};
+
+#[tokio::test(start_paused = true)]
+async fn recurring_timers_wait_for_enough_chat() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .with_moderator()
+        .start()
+        .await;
+
+    bot.chat("forsen", "alice", 1, "&timer add hydrate 20m hi")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Sorry, only moderators have access to the timer command"
+    );
+    bot.chat("forsen", "modguy", 9, "&timer add spam 10s hi")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Timers can't repeat more often than once a minute"
+    );
+    bot.chat("forsen", "modguy", 9, "&timer once later 9999999w hi")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Timers can't be more than a year apart or away"
+    );
+    bot.chat(
+        "forsen",
+        "modguy",
+        9,
+        "&timer add hydrate 20m --lines 3 Drink some water",
+    )
+    .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Added timer hydrate (every 20m, 3 lines)"
+    );
+
+    bot.chat("forsen", "alice", 1, "one").await;
+    bot.chat("forsen", "alice", 1, "two").await;
+    bot.expect_silence(Duration::from_secs(25 * 60)).await;
+
+    bot.chat("forsen", "bob", 2, "three").await;
+    assert_eq!(bot.expect_message_in("forsen").await, "Drink some water");
+
+    // the lines start over once it went out
+    bot.expect_silence(Duration::from_secs(25 * 60)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn timers_can_be_listed_paused_and_deleted() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .with_moderator()
+        .start()
+        .await;
+
+    bot.chat("forsen", "modguy", 9, "&timer once raid 10m Raid incoming!")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Added timer raid (once in 10m)"
+    );
+    bot.chat(
+        "forsen",
+        "modguy",
+        9,
+        "&timer add social 1h Follow on twitter",
+    )
+    .await;
+    bot.expect_message_in("forsen").await;
+    bot.chat("forsen", "modguy", 9, "&timer pause raid").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Paused timer raid"
+    );
+    bot.chat("forsen", "modguy", 9, "&timer list").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Timers in #forsen: raid (once in 10m, paused), social (every 1h)"
+    );
+    bot.expect_silence(Duration::from_secs(15 * 60)).await;
+
+    bot.chat("forsen", "modguy", 9, "&timer resume raid").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Resumed timer raid"
+    );
+    assert_eq!(bot.expect_message_in("forsen").await, "Raid incoming!");
+
+    bot.chat("forsen", "modguy", 9, "&timer delete social")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Deleted timer social"
+    );
+    bot.chat("forsen", "modguy", 9, "&timer delete raid").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, There's no timer called raid in #forsen"
+    );
+    bot.chat("forsen", "modguy", 9, "&timer list").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, There are no timers in #forsen"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn timers_only_go_out_in_the_right_stream_state() {
+    let mut bot = TestBotBuilder::new(&["forsen"])
+        .with_moderator()
+        .helix_route(MockRoute::new(
+            "GET",
+            "/helix/streams",
+            200,
+            &helix_stream_json("s1", "forsen", "title", "Just Chatting", 60),
+        ))
+        .start()
+        .await;
+
+    bot.chat(
+        "forsen",
+        "modguy",
+        9,
+        "&timer once offline 5m --offline We're offline",
+    )
+    .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Added timer offline (once in 5m, offline only)"
+    );
+    bot.chat(
+        "forsen",
+        "modguy",
+        9,
+        "&timer once live 5m --live We're live",
+    )
+    .await;
+    bot.expect_message_in("forsen").await;
+
+    assert_eq!(
+        bot.next_message_within(Duration::from_secs(10 * 60)).await,
+        Some(("forsen".to_owned(), "We're live".to_owned()))
+    );
+    bot.expect_silence(Duration::from_secs(10 * 60)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn timers_resume_after_a_restart() {
+    let builder = TestBotBuilder::new(&["forsen"]).with_moderator();
+    builder
+        .db()
+        .add_timer(&Timer {
+            id: 0,
+            channel: "forsen".to_owned(),
+            name: "hydrate".to_owned(),
+            message: "Drink some water".to_owned(),
+            schedule: TimerSchedule::Every(chrono::Duration::minutes(20)),
+            min_lines: 0,
+            condition: TimerCondition::Always,
+            paused: false,
+            created_at: Utc::now() - chrono::Duration::hours(5),
+            last_sent_at: Some(Utc::now() - chrono::Duration::minutes(18)),
+        })
+        .await
+        .unwrap();
+    let mut bot = builder.start().await;
+
+    bot.expect_silence(Duration::from_secs(90)).await;
+    assert_eq!(
+        bot.next_message_within(Duration::from_secs(60)).await,
+        Some(("forsen".to_owned(), "Drink some water".to_owned()))
+    );
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use std::time::Duration;
+
+use borrowbot::games::trivia::parse_pack;
+use common::TestBotBuilder;
+
+const CAPITALS: &str = r#"[
+    {"question": "What's the capital of France?", "answer": "Paris", "category": "Geography"},
+    {"question": "What's the capital of Japan?", "answers": ["Tokyo", "Edo"], "category": "Geography"},
+    {"question": "", "answer": "left out"}
+]"#;
+
+const COLORS: &str = "question,answer\n\
+    \"Name a primary color, any of them\",red,blue,yellow\n";
+
+fn with_packs(channels: &[&str]) -> TestBotBuilder {
+    TestBotBuilder::new(channels)
+        .with_moderator()
+        .data_file("trivia/capitals.json", CAPITALS)
+        .data_file("trivia/colors.csv", COLORS)
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_game_goes_through_the_pack_and_keeps_score() {
+    let mut bot = with_packs(&["forsen"]).start().await;
+
+    bot.chat("forsen", "alice", 1, "&trivia start capitals")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Sorry, only moderators can start trivia"
+    );
+    bot.chat("forsen", "modguy", 9, "&trivia start capitals")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@modguy, Starting trivia: 2 questions from the capitals pack, answer in chat!"
+    );
+
+    // the questions come in a random order
+    let answer = |question: &str| {
+        if question.ends_with("France?") {
+            ("pariss", "Paris", "P____")
+        } else {
+            ("edo", "Edo", "T____")
+        }
+    };
+
+    let question = bot.expect_message_in("forsen").await;
+    assert!(question.starts_with("Question 1/2: [Geography] What's the capital of"));
+    let (typed, shown, _) = answer(&question);
+    bot.chat("forsen", "bob", 2, "london").await;
+    bot.chat("forsen", "alice", 1, typed).await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        format!("alice got it: {} (+3, 3 this game)", shown)
+    );
+
+    let question = bot.expect_message_in("forsen").await;
+    assert!(question.starts_with("Question 2/2: [Geography] What's the capital of"));
+    let (typed, shown, hint) = answer(&question);
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        format!("Hint: {}", hint)
+    );
+    bot.chat("forsen", "bob", 2, typed).await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        format!("bob got it: {} (+2, 2 this game)", shown)
+    );
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "Trivia over! Scores: alice 3, bob 2"
+    );
+
+    bot.chat("forsen", "carol", 3, "&trivia top").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@carol, Top trivia players in #forsen: 1. alice (3), 2. bob (2)"
+    );
+    bot.chat("forsen", "alice", 1, "&trivia score bob").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, bob has 2 trivia points"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn questions_get_hints_and_time_out() {
+    let mut bot = with_packs(&["pajlada", "forsen"]).start().await;
+
+    bot.chat("pajlada", "modguy", 9, "&trivia start nope").await;
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "@modguy, There's no trivia pack called nope"
+    );
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("pajlada", "modguy", 9, "&trivia start colors")
+        .await;
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "@modguy, Starting trivia: 1 question from the colors pack, answer in chat!"
+    );
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "Question 1/1: Name a primary color, any of them"
+    );
+    assert_eq!(bot.expect_message_in("pajlada").await, "Hint: r__");
+    // other channels and commands go on as usual meanwhile
+    bot.chat("forsen", "alice", 1, "&trivia packs").await;
+    assert_eq!(
+        bot.expect_message_in("forsen").await,
+        "@alice, Trivia packs: capitals, colors"
+    );
+    assert_eq!(bot.expect_message_in("pajlada").await, "Hint: r__");
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "Time's up, the answer was red"
+    );
+    bot.chat("pajlada", "alice", 1, "red").await;
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "Trivia over, nobody scored"
+    );
+
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("pajlada", "modguy", 9, "&trivia start colors")
+        .await;
+    bot.expect_message_in("pajlada").await;
+    bot.expect_message_in("pajlada").await;
+    bot.chat("pajlada", "carol", 3, "BLUE!").await;
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "carol got it: blue (+3, 3 this game)"
+    );
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "Trivia over! Scores: carol 3"
+    );
+
+    // moderators can end a game early
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("pajlada", "modguy", 9, "&trivia start capitals 1")
+        .await;
+    bot.expect_message_in("pajlada").await;
+    bot.expect_message_in("pajlada").await;
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    bot.chat("pajlada", "modguy", 9, "&trivia stop").await;
+    assert_eq!(
+        bot.expect_message_in("pajlada").await,
+        "@modguy, Trivia over, nobody scored"
+    );
+    bot.expect_silence(Duration::from_secs(60)).await;
+}
+
+#[test]
+fn the_shipped_pack_is_complete() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/data/trivia/general.json");
+    let contents = std::fs::read_to_string(path).unwrap();
+    let listed = serde_json::from_str::<Vec<serde_json::Value>>(&contents)
+        .unwrap()
+        .len();
+
+    let questions = parse_pack("general.json", &contents).unwrap();
+    assert_eq!(questions.len(), listed);
+}